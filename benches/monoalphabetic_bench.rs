@@ -8,7 +8,7 @@ pub fn benchmark_monoalphabetic_encryption(c: &mut Criterion) {
         fs::read_to_string("benches/shakespeare_henry5.txt").expect("Error reading Shakespeare.");
     let key = "zyxwvutsrqponmlkjihgfedcba";
     c.bench_function("monoalphabetic encrypt 'zyxwvutsrqponmlkjihgfedcba'", |b| {
-        b.iter(|| encrypt_data("monoalphabetic", &data, key))
+        b.iter(|| encrypt_data("monoalphabetic", &data, key).unwrap())
     });
 }
 
@@ -8,7 +8,7 @@ pub fn benchmark_scytale_encryption(c: &mut Criterion) {
         fs::read_to_string("benches/shakespeare_henry5.txt").expect("Error reading Shakespeare.");
     let key = "7";
     c.bench_function("scytale encrypt 7", |b| {
-        b.iter(|| encrypt_data("scytale", &data, key))
+        b.iter(|| encrypt_data("scytale", &data, key).unwrap())
     });
 }
 
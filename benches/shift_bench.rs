@@ -8,7 +8,7 @@ pub fn benchmark_shift_encryption(c: &mut Criterion) {
         fs::read_to_string("benches/shakespeare_henry5.txt").expect("Error reading Shakespeare.");
     let key = "20";
     c.bench_function("shift encrypt 20", |b| {
-        b.iter(|| encrypt_data("shift", &data, key))
+        b.iter(|| encrypt_data("shift", &data, key).unwrap())
     });
 }
 
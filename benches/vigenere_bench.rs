@@ -8,7 +8,7 @@ pub fn benchmark_vigenere_encryption(c: &mut Criterion) {
         fs::read_to_string("benches/shakespeare_henry5.txt").expect("Error reading Shakespeare.");
     let key = "rebus";
     c.bench_function("vigenere encrypt 'rebus'", |b| {
-        b.iter(|| encrypt_data("vigenere", &data, key))
+        b.iter(|| encrypt_data("vigenere", &data, key).unwrap())
     });
 }
 
@@ -0,0 +1,408 @@
+/// This module holds the alphabets the ciphers work on.
+///
+/// An alphabet is an ordered set of (uppercase) symbols, plus a set of rules used to fold
+/// characters that don't belong to it onto the ones that do (e.g. 'J' onto 'I' in Latin).
+use crate::CipherError;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+    folds: BTreeMap<char, String>,
+}
+
+impl Alphabet {
+    /// Creates an alphabet out of the given symbols, in the given order.
+    ///
+    /// Letters are uppercased; the symbols must be unique and can't be whitespace.
+    pub fn new(symbols: &str) -> Result<Self, CipherError> {
+        let mut alphabet = Alphabet {
+            symbols: Vec::new(),
+            folds: BTreeMap::new(),
+        };
+
+        for ch in symbols.chars() {
+            let upper = Alphabet::single_uppercase(ch).unwrap_or(ch);
+
+            if upper.is_whitespace() {
+                return Err(CipherError::InvalidKey(
+                    "an alphabet can't contain whitespace".to_string(),
+                ));
+            }
+            if alphabet.contains(upper) {
+                return Err(CipherError::InvalidKey(format!(
+                    "the symbol {} appears more than once in the alphabet",
+                    upper
+                )));
+            }
+
+            alphabet.symbols.push(upper);
+        }
+
+        if alphabet.symbols.len() < 2 {
+            return Err(CipherError::InvalidKey(
+                "an alphabet must have at least two symbols".to_string(),
+            ));
+        }
+
+        Ok(alphabet)
+    }
+
+    /// Adds a rule folding the character from onto the symbols in to (e.g. 'Ä' onto "AE").
+    ///
+    /// The rule applies to both the lowercase and the uppercase form of from.
+    pub fn with_fold(mut self, from: char, to: &str) -> Result<Self, CipherError> {
+        let to = to.to_uppercase();
+
+        if let Some(ch) = to.chars().find(|ch| !self.contains(*ch)) {
+            return Err(CipherError::InvalidKey(format!(
+                "can't fold {} onto {}, since {} is not part of the alphabet",
+                from, to, ch
+            )));
+        }
+
+        let upper = Alphabet::single_uppercase(from).unwrap_or(from);
+        self.folds.insert(from, to.clone());
+        self.folds.insert(upper, to);
+
+        Ok(self)
+    }
+
+    /// The 26 letters of the English alphabet.
+    pub fn english() -> Self {
+        Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap()
+    }
+
+    /// The 21 letters of the Italian alphabet.
+    ///
+    /// Accented vowels are folded onto the plain ones, while the foreign letters are folded onto
+    /// the closest Italian spelling (J and Y onto I, K onto C, W onto V and X onto CS).
+    pub fn italian() -> Self {
+        Alphabet::new("ABCDEFGHILMNOPQRSTUVZ")
+            .and_then(|a| a.with_fold('J', "I"))
+            .and_then(|a| a.with_fold('K', "C"))
+            .and_then(|a| a.with_fold('W', "V"))
+            .and_then(|a| a.with_fold('X', "CS"))
+            .and_then(|a| a.with_fold('Y', "I"))
+            .and_then(|a| a.with_accents_folded())
+            .unwrap()
+    }
+
+    /// The 26 letters of the English alphabet, with the umlauts and the sharp s folded onto their
+    /// usual transliterations (Ä onto AE, Ö onto OE, Ü onto UE and ß onto SS).
+    pub fn german() -> Self {
+        Alphabet::english()
+            .with_fold('Ä', "AE")
+            .and_then(|a| a.with_fold('Ö', "OE"))
+            .and_then(|a| a.with_fold('Ü', "UE"))
+            .and_then(|a| a.with_fold('ß', "SS"))
+            .and_then(|a| a.with_fold('ẞ', "SS"))
+            .unwrap()
+    }
+
+    /// The 26 letters of the English alphabet, with the accented letters and the cedilla folded
+    /// onto the plain ones and the ligatures split (Œ onto OE and Æ onto AE).
+    pub fn french() -> Self {
+        let folds = [
+            ('À', "A"),
+            ('Â', "A"),
+            ('Æ', "AE"),
+            ('Ç', "C"),
+            ('È', "E"),
+            ('É', "E"),
+            ('Ê', "E"),
+            ('Ë', "E"),
+            ('Î', "I"),
+            ('Ï', "I"),
+            ('Ô', "O"),
+            ('Œ', "OE"),
+            ('Ù', "U"),
+            ('Û', "U"),
+            ('Ü', "U"),
+            ('Ÿ', "Y"),
+        ];
+
+        folds
+            .iter()
+            .try_fold(Alphabet::english(), |alphabet, (from, to)| {
+                alphabet.with_fold(*from, to)
+            })
+            .unwrap()
+    }
+
+    /// The 23 letters of the classical Latin alphabet, with I/J and U/V merged (and W written as
+    /// UU).
+    pub fn latin() -> Self {
+        Alphabet::new("ABCDEFGHIKLMNOPQRSTUXYZ")
+            .and_then(|a| a.with_fold('J', "I"))
+            .and_then(|a| a.with_fold('V', "U"))
+            .and_then(|a| a.with_fold('W', "UU"))
+            .unwrap()
+    }
+
+    /// The 26 letters of the English alphabet followed by the 10 digits.
+    pub fn alphanumeric() -> Self {
+        Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789").unwrap()
+    }
+
+    /// Appends the digits that are not already part of the alphabet, in order.
+    pub fn with_digits(mut self) -> Self {
+        for digit in '0'..='9' {
+            if !self.contains(digit) {
+                self.symbols.push(digit);
+            }
+        }
+
+        self
+    }
+
+    /// Folds the most common accented vowels onto the plain ones.
+    fn with_accents_folded(self) -> Result<Self, CipherError> {
+        let accented = [
+            ('À', "A"),
+            ('Á', "A"),
+            ('È', "E"),
+            ('É', "E"),
+            ('Ì', "I"),
+            ('Í', "I"),
+            ('Ò', "O"),
+            ('Ó', "O"),
+            ('Ù', "U"),
+            ('Ú', "U"),
+        ];
+
+        accented
+            .iter()
+            .try_fold(self, |alphabet, (from, to)| alphabet.with_fold(*from, to))
+    }
+
+    /// The uppercase form of ch, if it is a single character.
+    fn single_uppercase(ch: char) -> Option<char> {
+        let mut upper = ch.to_uppercase();
+
+        match (upper.next(), upper.next()) {
+            (Some(u), None) => Some(u),
+            _ => None,
+        }
+    }
+
+    /// The number of symbols in the alphabet.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Alphabets always have at least two symbols, so this is always false.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// The symbols of the alphabet, in order.
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn contains(&self, ch: char) -> bool {
+        self.index_of(ch).is_some()
+    }
+
+    /// The position of ch in the alphabet (if it is a symbol of the alphabet).
+    pub fn index_of(&self, ch: char) -> Option<usize> {
+        self.symbols.iter().position(|s| *s == ch)
+    }
+
+    /// The symbol at position idx, wrapping around the end of the alphabet.
+    pub fn symbol_at(&self, idx: usize) -> char {
+        self.symbols[idx % self.symbols.len()]
+    }
+
+    /// Shifts the symbol ch by n positions in either direction, wrapping around.
+    pub fn shift(&self, ch: char, n: i64) -> Result<char, CipherError> {
+        let idx = self.index_of(ch).ok_or(CipherError::InvalidSymbol(ch))?;
+        let m = self.len() as i64;
+
+        Ok(self.symbol_at((idx as i64 + n).rem_euclid(m) as usize))
+    }
+
+    /// Applies case folding and the folding rules to ch, pushing the result onto out.
+    ///
+    /// Characters that have no single uppercase form (and no rule of their own) are dropped.
+    pub fn fold_into(&self, ch: char, out: &mut String) {
+        if let Some(to) = self.folds.get(&ch) {
+            out.push_str(to);
+        } else if let Some(upper) = Alphabet::single_uppercase(ch) {
+            match self.folds.get(&upper) {
+                Some(to) => out.push_str(to),
+                None => out.push(upper),
+            }
+        }
+    }
+
+    /// The symbol ch stands for on its own, after case folding and the folding rules (if ch is
+    /// folded onto more than one symbol, it doesn't stand for any).
+    pub fn symbol_for(&self, ch: char) -> Option<char> {
+        if self.contains(ch) {
+            return Some(ch);
+        }
+
+        let mut folded = String::new();
+        self.fold_into(ch, &mut folded);
+
+        let mut chars = folded.chars();
+        match (chars.next(), chars.next()) {
+            (Some(symbol), None) if self.contains(symbol) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    /// Applies case folding and the folding rules to the whole text.
+    pub fn fold(&self, text: &str) -> String {
+        let mut folded = String::with_capacity(text.len());
+
+        for ch in text.chars() {
+            self.fold_into(ch, &mut folded);
+        }
+
+        folded
+    }
+
+    /// Cleans up the text, keeping only the symbols of the alphabet (after folding) and the
+    /// digits, which the ciphers leave untouched.
+    pub fn clean(&self, text: &str) -> String {
+        self.fold(text)
+            .chars()
+            .filter(|ch| self.contains(*ch) || ch.is_ascii_digit())
+            .collect()
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::english()
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = CipherError;
+
+    /// Parses either the name of one of the predefined alphabets (english, italian, german,
+    /// french, latin, alphanumeric) or the list of symbols making up a custom one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "english" => Ok(Alphabet::english()),
+            "italian" => Ok(Alphabet::italian()),
+            "german" => Ok(Alphabet::german()),
+            "french" => Ok(Alphabet::french()),
+            "latin" => Ok(Alphabet::latin()),
+            "alphanumeric" => Ok(Alphabet::alphanumeric()),
+            _ => Alphabet::new(s),
+        }
+    }
+}
+
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbols.iter().collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let alphabet = Alphabet::new("abc").unwrap();
+        assert_eq!(alphabet.symbols(), &['A', 'B', 'C']);
+
+        assert!(Alphabet::new("").is_err());
+        assert!(Alphabet::new("a").is_err());
+        assert!(Alphabet::new("abca").is_err());
+        assert!(Alphabet::new("ab c").is_err());
+    }
+
+    #[test]
+    fn test_predefined_sizes() {
+        assert_eq!(Alphabet::english().len(), 26);
+        assert_eq!(Alphabet::italian().len(), 21);
+        assert_eq!(Alphabet::german().len(), 26);
+        assert_eq!(Alphabet::french().len(), 26);
+        assert_eq!(Alphabet::latin().len(), 23);
+        assert_eq!(Alphabet::alphanumeric().len(), 36);
+    }
+
+    #[test]
+    fn test_with_digits() {
+        assert_eq!(Alphabet::alphanumeric(), Alphabet::english().with_digits());
+        assert_eq!(
+            Alphabet::alphanumeric(),
+            Alphabet::alphanumeric().with_digits()
+        );
+
+        let latin = Alphabet::latin().with_digits();
+        assert_eq!(latin.len(), 33);
+        assert_eq!("IULIUS", latin.fold("Julius"));
+    }
+
+    #[test]
+    fn test_shift() {
+        let english = Alphabet::english();
+        assert_eq!('B', english.shift('A', 1).unwrap());
+        assert_eq!('A', english.shift('Z', 1).unwrap());
+        assert_eq!('Z', english.shift('A', -1).unwrap());
+        assert_eq!('B', english.shift('A', 27).unwrap());
+        assert!(english.shift('a', 1).is_err());
+
+        let italian = Alphabet::italian();
+        assert_eq!('A', italian.shift('Z', 1).unwrap());
+        assert_eq!('L', italian.shift('I', 1).unwrap());
+
+        let alphanumeric = Alphabet::alphanumeric();
+        assert_eq!('0', alphanumeric.shift('Z', 1).unwrap());
+        assert_eq!('A', alphanumeric.shift('9', 1).unwrap());
+    }
+
+    #[test]
+    fn test_folding() {
+        assert_eq!("STRASSE", Alphabet::german().fold("Straße"));
+        assert_eq!("MUELLER", Alphabet::german().fold("Müller"));
+        assert_eq!("IULIUS", Alphabet::latin().fold("Julius"));
+        assert_eq!("UUUUE", Alphabet::latin().fold("Wvue"));
+        assert_eq!("PERCHE", Alphabet::italian().fold("perché"));
+        assert_eq!("CILO", Alphabet::italian().fold("kilo"));
+        assert_eq!("COEUR ARRETE", Alphabet::french().fold("Cœur arrêté"));
+    }
+
+    #[test]
+    fn test_symbol_for() {
+        let italian = Alphabet::italian();
+        assert_eq!(Some('E'), italian.symbol_for('è'));
+        assert_eq!(Some('I'), italian.symbol_for('j'));
+        assert_eq!(None, italian.symbol_for('x'));
+        assert_eq!(None, italian.symbol_for(' '));
+
+        assert_eq!(None, Alphabet::german().symbol_for('ß'));
+    }
+
+    #[test]
+    fn test_clean() {
+        let english = Alphabet::english();
+        assert_eq!(
+            "NOWWITHNUMB3RZ00712",
+            english.clean("NoW@wITHéé˛Ånumb3rz00712")
+        );
+
+        let german = Alphabet::german();
+        assert_eq!("GRUESSE1", german.clean("Grüße #1"));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Alphabet::latin(), "Latin".parse::<Alphabet>().unwrap());
+
+        let custom = "zyx".parse::<Alphabet>().unwrap();
+        assert_eq!("ZYX", custom.to_string());
+    }
+}
@@ -0,0 +1,183 @@
+/// Breaks Hill ciphers given a piece of plaintext and the ciphertext it encrypts to (a
+/// known-plaintext attack), by solving for the key matrix.
+use crate::ciphers::hill::{invert, multiply};
+use crate::keys::HillKey;
+use crate::{Alphabet, CipherError};
+
+/// How many blocks are searched for a set of plaintext blocks making up an invertible matrix.
+pub const MAX_BLOCKS: usize = 16;
+
+/// Recovers the n×n key matrix of a Hill cipher (working on the English alphabet) given some
+/// plaintext and the matching ciphertext, which must both start at the beginning of a block.
+///
+/// Since the key maps every plaintext block P onto its ciphertext block C, n blocks of plaintext
+/// taken as the columns of a matrix P give KP = C, so K = CP⁻¹ as long as P can be inverted
+/// modulo 26: the first n blocks for which that happens are used, and the key is then checked
+/// against all the others.
+pub fn recover_hill_key(
+    plaintext: &str,
+    ciphertext: &str,
+    size: usize,
+) -> Result<HillKey, CipherError> {
+    if size < 2 {
+        return Err(CipherError::InvalidKey(
+            "the matrix of a Hill cipher must be at least 2×2".to_string(),
+        ));
+    }
+
+    let alphabet = Alphabet::english();
+    let m = alphabet.len();
+    let plain = indexes(plaintext, &alphabet);
+    let cipher = indexes(ciphertext, &alphabet);
+    let blocks = plain.len().min(cipher.len()) / size;
+
+    // the matrix whose columns are the chosen blocks of the text
+    let columns = |text: &[usize], chosen: &[usize]| {
+        (0..size * size)
+            .map(|cell| text[chosen[cell % size] * size + cell / size])
+            .collect::<Vec<usize>>()
+    };
+
+    let mut chosen = (0..size).collect::<Vec<usize>>();
+    let key = loop {
+        if chosen[size - 1] >= blocks.min(MAX_BLOCKS) {
+            return Err(CipherError::InvalidLength(format!(
+                "recovering a {}×{} Hill key takes {} blocks of plaintext forming a matrix invertible modulo {}, and none could be found",
+                size, size, size, m
+            )));
+        }
+        if let Some(inverse) = invert(&columns(&plain, &chosen), size, m) {
+            break multiply(&columns(&cipher, &chosen), &inverse, size, m);
+        }
+
+        next_combination(&mut chosen, blocks.min(MAX_BLOCKS));
+    };
+
+    for block in 0..blocks {
+        let (p, c) = (&plain[block * size..], &cipher[block * size..]);
+        let matches = (0..size).all(|row| {
+            (0..size)
+                .map(|col| key[row * size + col] * p[col])
+                .sum::<usize>()
+                % m
+                == c[row]
+        });
+
+        if !matches {
+            return Err(CipherError::InvalidKey(format!(
+                "the plaintext doesn't encrypt to the ciphertext under any {}×{} Hill key",
+                size, size
+            )));
+        }
+    }
+
+    HillKey::new(key.into_iter().map(|n| n as i64).collect())
+}
+
+/// The indexes in the alphabet of the symbols of the text, skipping anything else.
+fn indexes(text: &str, alphabet: &Alphabet) -> Vec<usize> {
+    alphabet
+        .clean(text)
+        .chars()
+        .filter_map(|ch| alphabet.index_of(ch))
+        .collect()
+}
+
+/// Moves on to the next (increasing) combination of indexes below n, leaving the last index at n
+/// or more once they're over.
+fn next_combination(chosen: &mut [usize], n: usize) {
+    let k = chosen.len();
+
+    match (0..k).rev().find(|&i| chosen[i] < n - k + i) {
+        Some(i) => {
+            chosen[i] += 1;
+            for j in i + 1..k {
+                chosen[j] = chosen[j - 1] + 1;
+            }
+        }
+        None => chosen[k - 1] = n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cipher, HillCipher};
+
+    #[test]
+    fn test_recover() {
+        let plaintext = "Linear algebra makes short work of the Hill cipher";
+
+        for key in ["GYBNQKURP", "hill", "3,3,2,5"].iter() {
+            let cipher = HillCipher::new(key).unwrap();
+            let ciphertext = cipher.encrypt(plaintext).unwrap();
+            let size = cipher.key().size();
+
+            let recovered = recover_hill_key(plaintext, &ciphertext, size).unwrap();
+            let expected = cipher
+                .key()
+                .matrix()
+                .iter()
+                .map(|n| n.rem_euclid(26))
+                .collect::<Vec<i64>>();
+            assert_eq!(expected, recovered.matrix());
+        }
+    }
+
+    #[test]
+    fn test_not_enough_plaintext() {
+        let cipher = HillCipher::new("GYBNQKURP").unwrap();
+        let ciphertext = cipher.encrypt("attackatdawn").unwrap();
+
+        // AAA, BBB and CCC are all multiples of the same block
+        assert!(matches!(
+            recover_hill_key("aaabbbccc", &cipher.encrypt("aaabbbccc").unwrap(), 3),
+            Err(CipherError::InvalidLength(_))
+        ));
+        assert!(matches!(
+            recover_hill_key("attack", &ciphertext, 3),
+            Err(CipherError::InvalidLength(_))
+        ));
+        assert!(recover_hill_key("attackatdawn", &ciphertext, 1).is_err());
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let ciphertext = HillCipher::new("hill")
+            .unwrap()
+            .encrypt("attackatdawn")
+            .unwrap();
+
+        // AT and TA are enough to get the key, which then fails on DU
+        assert!(matches!(
+            recover_hill_key("attackatdusk", &ciphertext, 2),
+            Err(CipherError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_next_combination() {
+        let mut chosen = vec![0, 1];
+        let mut all = vec![chosen.clone()];
+
+        loop {
+            next_combination(&mut chosen, 4);
+            if chosen[1] >= 4 {
+                break;
+            }
+            all.push(chosen.clone());
+        }
+
+        assert_eq!(
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ],
+            all
+        );
+    }
+}
@@ -0,0 +1,28 @@
+/// Solvers that recover the key of a ciphertext (and the plaintext with it) without knowing
+/// anything but the cipher that was used, by scoring the candidates against English (or, for the
+/// Hill cipher, from a matching piece of plaintext).
+pub mod hill;
+pub mod shift;
+pub mod substitution;
+pub mod transposition;
+pub mod vigenere;
+
+pub use hill::recover_hill_key;
+pub use shift::crack_shift;
+pub use substitution::{crack_substitution, SubstitutionSolver};
+pub use transposition::{crack_columnar, crack_scytale};
+pub use vigenere::crack_vigenere;
+
+/// A possible solution found by a solver: the key, how much the plaintext it gives looks like
+/// English (the higher, the better) and the plaintext itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate<K> {
+    pub key: K,
+    pub score: f64,
+    pub plaintext: String,
+}
+
+/// Sorts the candidates from the best to the worst.
+fn rank<K>(candidates: &mut [Candidate<K>]) {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
@@ -0,0 +1,71 @@
+/// Breaks shift ciphers by trying every shift.
+use super::{rank, Candidate};
+use crate::analysis::LanguageModel;
+use crate::keys::ShiftKey;
+use crate::text::TextMode;
+use crate::{Alphabet, Cipher, CipherError, ShiftCipher};
+
+/// Decrypts the ciphertext with all the 26 shifts, returning the candidates from the most to the
+/// least likely to be English.
+///
+/// The plaintexts keep the spaces and punctuation of the ciphertext, to make them easier to read.
+pub fn crack_shift(ciphertext: &str) -> Result<Vec<Candidate<ShiftKey>>, CipherError> {
+    let mut candidates = (0..Alphabet::english().len() as i8)
+        .map(|shift| {
+            let key = ShiftKey::new(shift);
+            let plaintext = ShiftCipher::from_key(key)
+                .with_mode(TextMode::preserve_format())
+                .decrypt(ciphertext)?;
+
+            Ok(Candidate {
+                key,
+                score: LanguageModel::english().score(&plaintext),
+                plaintext,
+            })
+        })
+        .collect::<Result<Vec<Candidate<ShiftKey>>, CipherError>>()?;
+
+    rank(&mut candidates);
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crack() {
+        let plaintexts = [
+            "Meet me at the usual place at ten rather than eight o'clock",
+            "Defend the east wall of the castle",
+            "The quick brown fox jumps over the lazy dog",
+        ];
+
+        for plaintext in plaintexts {
+            for shift in [1, 3, 13, 25] {
+                let ciphertext = ShiftCipher::new(shift)
+                    .unwrap()
+                    .with_mode(TextMode::preserve_format())
+                    .encrypt(plaintext)
+                    .unwrap();
+                let candidates = crack_shift(&ciphertext).unwrap();
+
+                assert_eq!(26, candidates.len());
+                assert_eq!(shift, candidates[0].key.shift());
+                assert_eq!(plaintext, candidates[0].plaintext);
+                assert!(candidates[0].score >= candidates[1].score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let candidates = crack_shift("").unwrap();
+
+        assert_eq!(26, candidates.len());
+        assert!(candidates
+            .iter()
+            .all(|candidate| candidate.plaintext.is_empty()));
+    }
+}
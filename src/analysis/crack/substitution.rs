@@ -0,0 +1,332 @@
+/// Breaks monoalphabetic substitutions by hill climbing: starting from the key that matches the
+/// letter frequencies of the ciphertext with those of English, pairs of letters are swapped for
+/// as long as that makes the plaintext score better on quadgrams. Since the climb can get stuck,
+/// it is repeated from a number of random keys, keeping the best result.
+use super::Candidate;
+use crate::analysis::LanguageModel;
+use crate::keys::SubstitutionAlphabet;
+use crate::text::TextMode;
+use crate::{Alphabet, Cipher, CipherError, MonoalphaCipher};
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+/// The letters of English, from the most to the least common.
+const ENGLISH_ORDER: &str = "ETAOINSHRDLCUMWFGYPBVKJXQZ";
+
+/// Searches for the key of a monoalphabetic substitution, possibly with some letters known in
+/// advance (the cribs).
+///
+/// The search is random, but seeded: the same solver always gives the same result for the same
+/// ciphertext.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstitutionSolver {
+    restarts: usize,
+    seed: u64,
+    /// The (plaintext, ciphertext) pairs of letters known in advance, as indices.
+    cribs: Vec<(usize, usize)>,
+}
+
+impl Default for SubstitutionSolver {
+    fn default() -> Self {
+        SubstitutionSolver::new()
+    }
+}
+
+impl SubstitutionSolver {
+    /// Creates a solver climbing 20 times (once from the frequency order, then from random keys)
+    /// with no cribs.
+    pub fn new() -> Self {
+        SubstitutionSolver {
+            restarts: 20,
+            seed: 0,
+            cribs: Vec::new(),
+        }
+    }
+
+    /// Sets how many times the climb is run (at least once, from the frequency order).
+    pub fn with_restarts(mut self, restarts: usize) -> Self {
+        self.restarts = restarts.max(1);
+        self
+    }
+
+    /// Sets the seed of the random keys the climb restarts from.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Locks the given plaintext letter to the given ciphertext letter, e.g. ('e', 'x') if E is
+    /// known to be encrypted as X.
+    ///
+    /// Fails if the characters aren't letters, or if either of them is already locked to a
+    /// different letter.
+    pub fn with_crib(mut self, plain: char, cipher: char) -> Result<Self, CipherError> {
+        let alphabet = Alphabet::english();
+        let index = |ch: char| {
+            alphabet
+                .symbol_for(ch)
+                .and_then(|symbol| alphabet.index_of(symbol))
+                .ok_or_else(|| {
+                    CipherError::InvalidKey(format!("a crib can only pair letters, got {:?}", ch))
+                })
+        };
+        let (p, c) = (index(plain)?, index(cipher)?);
+
+        for &(other_p, other_c) in &self.cribs {
+            if (other_p == p) != (other_c == c) {
+                return Err(CipherError::InvalidKey(format!(
+                    "the crib {}={} conflicts with {}={}",
+                    alphabet.symbol_at(p),
+                    alphabet.symbol_at(c),
+                    alphabet.symbol_at(other_p),
+                    alphabet.symbol_at(other_c)
+                )));
+            }
+        }
+        if !self.cribs.contains(&(p, c)) {
+            self.cribs.push((p, c));
+        }
+
+        Ok(self)
+    }
+
+    /// Searches for the key of the ciphertext, returning the best one found along with the
+    /// plaintext it gives (which keeps the spaces and punctuation of the ciphertext).
+    pub fn solve(&self, ciphertext: &str) -> Result<Candidate<SubstitutionAlphabet>, CipherError> {
+        let english = LanguageModel::english();
+        let letters = english
+            .indices(ciphertext)
+            .into_iter()
+            .map(|idx| idx as u8)
+            .collect::<Vec<u8>>();
+
+        let mut locked = [false; 26];
+        for &(_, c) in &self.cribs {
+            locked[c] = true;
+        }
+        let free = (0..26).filter(|&c| !locked[c]).collect::<Vec<usize>>();
+
+        let start = self.frequency_key(&letters);
+        let mut rng = Pcg64::seed_from_u64(self.seed);
+
+        let mut best = Climb::new(&letters, start, english);
+        best.run(&free);
+
+        for _ in 1..self.restarts {
+            // a random key, but with the cribs still in place
+            let mut key = start;
+            let mut shuffled = free.iter().map(|&c| key[c]).collect::<Vec<u8>>();
+            shuffled.shuffle(&mut rng);
+            for (&c, p) in free.iter().zip(shuffled) {
+                key[c] = p;
+            }
+
+            let mut climb = Climb::new(&letters, key, english);
+            climb.run(&free);
+
+            if climb.score > best.score {
+                best = climb;
+            }
+        }
+
+        let key = best.encryption_key().parse::<SubstitutionAlphabet>()?;
+        let plaintext = MonoalphaCipher::from_key(key.clone())
+            .with_mode(TextMode::preserve_format())
+            .decrypt(ciphertext)?;
+
+        Ok(Candidate {
+            key,
+            score: best.score,
+            plaintext,
+        })
+    }
+
+    /// The decryption key (ciphertext letter to plaintext letter) matching the letters of the
+    /// ciphertext, from the most to the least common, with those of English, with the cribs put
+    /// in place.
+    fn frequency_key(&self, letters: &[u8]) -> [u8; 26] {
+        let mut counts = [0usize; 26];
+        for &c in letters {
+            counts[c as usize] += 1;
+        }
+
+        let mut by_frequency = (0..26).collect::<Vec<usize>>();
+        by_frequency.sort_by(|&a, &b| counts[b].cmp(&counts[a]).then(a.cmp(&b)));
+
+        let mut key = [0u8; 26];
+        for (&c, p) in by_frequency.iter().zip(ENGLISH_ORDER.bytes()) {
+            key[c] = p - b'A';
+        }
+
+        for &(p, c) in &self.cribs {
+            let other = key.iter().position(|&q| q as usize == p).unwrap();
+            key.swap(c, other);
+        }
+
+        key
+    }
+}
+
+/// A single hill climb, from a given decryption key.
+struct Climb<'a> {
+    letters: &'a [u8],
+    english: &'a LanguageModel,
+    key: [u8; 26],
+    score: f64,
+    /// Where the decrypted letters are put, to avoid allocating for every swap.
+    buffer: Vec<u8>,
+}
+
+impl<'a> Climb<'a> {
+    fn new(letters: &'a [u8], key: [u8; 26], english: &'a LanguageModel) -> Self {
+        let mut climb = Climb {
+            letters,
+            english,
+            key,
+            score: 0.0,
+            buffer: Vec::with_capacity(letters.len()),
+        };
+        climb.score = climb.evaluate();
+
+        climb
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let key = self.key;
+
+        self.buffer.clear();
+        self.buffer
+            .extend(self.letters.iter().map(|&c| key[c as usize]));
+
+        self.english.score_indices(&self.buffer)
+    }
+
+    /// Swaps pairs of the free letters until no swap improves the score.
+    fn run(&mut self, free: &[usize]) {
+        let mut improved = true;
+
+        while improved {
+            improved = false;
+
+            for (i, &a) in free.iter().enumerate() {
+                for &b in &free[i + 1..] {
+                    self.key.swap(a, b);
+                    let score = self.evaluate();
+
+                    if score > self.score {
+                        self.score = score;
+                        improved = true;
+                    } else {
+                        self.key.swap(a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The key in the form taken by `MonoalphaCipher`: for each plaintext letter, the ciphertext
+    /// letter it becomes.
+    fn encryption_key(&self) -> String {
+        let mut key = [b'A'; 26];
+        for (c, &p) in self.key.iter().enumerate() {
+            key[p as usize] = b'A' + c as u8;
+        }
+
+        key.iter().map(|&ch| ch as char).collect()
+    }
+}
+
+/// Breaks the ciphertext of a monoalphabetic substitution with the default solver.
+pub fn crack_substitution(
+    ciphertext: &str,
+) -> Result<Candidate<SubstitutionAlphabet>, CipherError> {
+    SubstitutionSolver::new().solve(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "It is a truth universally acknowledged, that a single man in \
+        possession of a good fortune, must be in want of a wife. However little known the \
+        feelings or views of such a man may be on his first entering a neighbourhood, this truth \
+        is so well fixed in the minds of the surrounding families, that he is considered the \
+        rightful property of some one or other of their daughters.";
+    const KEY: &str = "QWERTYUIOPASDFGHJKLZXCVBNM";
+
+    fn encrypt(plaintext: &str) -> String {
+        MonoalphaCipher::new(KEY)
+            .unwrap()
+            .with_mode(TextMode::preserve_format())
+            .encrypt(plaintext)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_crack() {
+        let candidate = crack_substitution(&encrypt(PLAINTEXT)).unwrap();
+
+        assert_eq!(PLAINTEXT, candidate.plaintext);
+        // the key plugs straight into the cipher
+        let cipher = MonoalphaCipher::new(candidate.key.letters()).unwrap();
+        assert_eq!(
+            cipher.clean_input(PLAINTEXT),
+            cipher.decrypt(&encrypt(PLAINTEXT)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_seeded() {
+        let ciphertext = encrypt(&PLAINTEXT[..120]);
+        let solver = SubstitutionSolver::new().with_restarts(5).with_seed(42);
+
+        assert_eq!(
+            solver.solve(&ciphertext).unwrap(),
+            solver.solve(&ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cribs() {
+        // too short to be broken without help, but the cribs lock the letters in place
+        let ciphertext = encrypt("Meet me at the old mill");
+        let solver = SubstitutionSolver::new()
+            .with_crib('m', 'D')
+            .unwrap()
+            .with_crib('L', 's')
+            .unwrap();
+        let candidate = solver.solve(&ciphertext).unwrap();
+
+        assert_eq!(Some('D'), candidate.key.letters().chars().nth(12));
+        assert_eq!(Some('S'), candidate.key.letters().chars().nth(11));
+        assert!(candidate.plaintext.starts_with('M'));
+
+        // the same crib twice is fine, but not a letter locked to two different ones
+        assert!(SubstitutionSolver::new()
+            .with_crib('e', 't')
+            .unwrap()
+            .with_crib('e', 't')
+            .is_ok());
+        assert!(SubstitutionSolver::new()
+            .with_crib('e', 't')
+            .unwrap()
+            .with_crib('e', 'x')
+            .is_err());
+        assert!(SubstitutionSolver::new()
+            .with_crib('e', 't')
+            .unwrap()
+            .with_crib('a', 't')
+            .is_err());
+        assert!(SubstitutionSolver::new().with_crib('1', 't').is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        let candidate = crack_substitution("").unwrap();
+
+        assert_eq!("", candidate.plaintext);
+        assert_eq!(26, candidate.key.letters().len());
+    }
+}
@@ -0,0 +1,359 @@
+/// Breaks transposition ciphers. A scytale has so few possible lengths that they can all be
+/// tried, and so do the orders of the columns of a columnar transposition with a short key; for
+/// longer keys the columns are anagrammed by hill climbing instead.
+use super::{rank, Candidate};
+use crate::analysis::LanguageModel;
+use crate::ciphers::transposition::{clean, untranspose, Grid};
+use crate::keys::{ColumnarKey, ScytaleLength};
+use crate::text::DigitPolicy;
+use crate::{Cipher, CipherError, Scytale};
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+/// Up to this many columns, every order of the columns is tried.
+pub const BRUTE_FORCE_COLUMNS: usize = 7;
+
+/// The widest grid looked for by default.
+pub const MAX_COLUMNS: usize = 12;
+
+/// How many times the columns of wider grids are anagrammed, from different orders.
+const RESTARTS: usize = 10;
+
+/// Tries every length of the scytale, returning the candidates from the most to the least likely
+/// to be English (judging by their quadgrams).
+pub fn crack_scytale(ciphertext: &str) -> Result<Vec<Candidate<ScytaleLength>>, CipherError> {
+    let english = LanguageModel::english();
+    let len = clean(ciphertext, DigitPolicy::default()).chars().count();
+
+    let mut candidates = (1..=len.max(1))
+        .map(|length| {
+            let key = ScytaleLength::new(length)?;
+            let plaintext = Scytale::from_key(key).decrypt(ciphertext)?;
+
+            Ok(Candidate {
+                key,
+                score: english.score(&plaintext),
+                plaintext,
+            })
+        })
+        .collect::<Result<Vec<Candidate<ScytaleLength>>, CipherError>>()?;
+
+    rank(&mut candidates);
+
+    Ok(candidates)
+}
+
+/// Tries the grids from 2 to max_columns columns wide (as long as they have at least two rows),
+/// returning the best key found for each width, from the most to the least likely to be English.
+///
+/// All the orders of the columns are tried up to `BRUTE_FORCE_COLUMNS` columns; for wider grids
+/// pairs of columns are swapped as long as that improves the plaintext, starting over from a few
+/// random orders (the same ones every time).
+pub fn crack_columnar(
+    ciphertext: &str,
+    max_columns: usize,
+) -> Result<Vec<Candidate<ColumnarKey>>, CipherError> {
+    let text = clean(ciphertext, DigitPolicy::default())
+        .chars()
+        .collect::<Vec<char>>();
+    let widest = max_columns.min(text.len() / 2).min(26);
+
+    let mut candidates = (2..=widest)
+        .map(|columns| {
+            let anagrammer = Anagrammer::new(&text, columns);
+            let (order, score) = if columns <= BRUTE_FORCE_COLUMNS {
+                anagrammer.brute_force()
+            } else {
+                anagrammer.climb()
+            };
+
+            Ok(Candidate {
+                key: key_for(&order).parse()?,
+                score,
+                plaintext: anagrammer.decrypt(&order),
+            })
+        })
+        .collect::<Result<Vec<Candidate<ColumnarKey>>, CipherError>>()?;
+
+    rank(&mut candidates);
+
+    Ok(candidates)
+}
+
+/// A key giving the order of the columns: the column read first gets an A, the next one a B and
+/// so on.
+fn key_for(order: &[usize]) -> String {
+    let mut key = vec!['A'; order.len()];
+    for (rank, &column) in order.iter().enumerate() {
+        key[column] = (b'A' + rank as u8) as char;
+    }
+
+    key.into_iter().collect()
+}
+
+/// The inverse of a permutation of 0..n: the order the columns are read in from the rank of
+/// each column, and back.
+fn inverse(permutation: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; permutation.len()];
+    for (i, &j) in permutation.iter().enumerate() {
+        inverse[j] = i;
+    }
+
+    inverse
+}
+
+/// All the permutations of 0..n, in lexicographic order.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    let mut permutations = vec![(0..n).collect::<Vec<usize>>()];
+
+    loop {
+        let mut next = permutations.last().unwrap().clone();
+
+        // the usual next permutation: find the last ascent, swap and reverse the tail
+        let Some(i) = (1..n).rev().find(|&i| next[i - 1] < next[i]) else {
+            return permutations;
+        };
+        let j = (i..n).rev().find(|&j| next[j] > next[i - 1]).unwrap();
+        next.swap(i - 1, j);
+        next[i..].reverse();
+
+        permutations.push(next);
+    }
+}
+
+/// Searches for the order of the columns of a grid of a given width.
+struct Anagrammer<'a> {
+    text: &'a [char],
+    /// The indices of the characters of the text in the English alphabet (None for digits).
+    letters: Vec<Option<u8>>,
+    columns: usize,
+    english: &'a LanguageModel,
+}
+
+impl<'a> Anagrammer<'a> {
+    fn new(text: &'a [char], columns: usize) -> Self {
+        let letters = text
+            .iter()
+            .map(|ch| ch.is_ascii_uppercase().then(|| *ch as u8 - b'A'))
+            .collect();
+
+        Anagrammer {
+            text,
+            letters,
+            columns,
+            english: LanguageModel::english(),
+        }
+    }
+
+    fn decrypt(&self, order: &[usize]) -> String {
+        let reading_order = Grid::new(self.text.len(), self.columns).by_columns(order);
+
+        untranspose(self.text, &reading_order)
+    }
+
+    /// Scores the plaintext given by the order, without going through strings (this is where
+    /// the solver spends its time).
+    fn score(&self, order: &[usize]) -> f64 {
+        let reading_order = Grid::new(self.text.len(), self.columns).by_columns(order);
+
+        let mut plaintext = vec![None; self.letters.len()];
+        for (letter, &idx) in self.letters.iter().zip(&reading_order) {
+            plaintext[idx] = *letter;
+        }
+
+        self.english
+            .score_indices(&plaintext.into_iter().flatten().collect::<Vec<u8>>())
+    }
+
+    /// The best of all the orders.
+    fn brute_force(&self) -> (Vec<usize>, f64) {
+        permutations(self.columns)
+            .into_iter()
+            .map(|order| {
+                let score = self.score(&order);
+                (order, score)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+    }
+
+    /// The orders that differ from the given one by swapping two columns of the grid, by moving a
+    /// column elsewhere or by rotating all the columns (the only way out of a rotated grid, which
+    /// reads almost as well as the right one).
+    ///
+    /// The moves are made on the columns of the grid rather than on the order they are read in:
+    /// that way the columns already next to each other in the grid stay so.
+    fn neighbours(order: &[usize]) -> Vec<Vec<usize>> {
+        let ranks = inverse(order);
+        let n = ranks.len();
+        let mut neighbours = Vec::new();
+
+        for i in 0..n {
+            for j in 0..n {
+                if i < j {
+                    let mut swapped = ranks.clone();
+                    swapped.swap(i, j);
+                    neighbours.push(swapped);
+                }
+                if i != j {
+                    let mut moved = ranks.clone();
+                    let column = moved.remove(i);
+                    moved.insert(j, column);
+                    neighbours.push(moved);
+                }
+            }
+        }
+        for k in 1..n {
+            let mut rotated = ranks.clone();
+            rotated.rotate_left(k);
+            neighbours.push(rotated);
+        }
+
+        neighbours.iter().map(|ranks| inverse(ranks)).collect()
+    }
+
+    /// The best of the orders found by hill climbing from the natural order and from random ones.
+    fn climb(&self) -> (Vec<usize>, f64) {
+        let mut rng = Pcg64::seed_from_u64(self.columns as u64);
+        let mut best: Option<(Vec<usize>, f64)> = None;
+
+        for restart in 0..RESTARTS {
+            let mut order = (0..self.columns).collect::<Vec<usize>>();
+            if restart > 0 {
+                order.shuffle(&mut rng);
+            }
+            let mut score = self.score(&order);
+
+            // moves to the best neighbour of the current order, then looks at the neighbours of
+            // the new one, until none of them is any better
+            while let Some((neighbour, new_score)) = Anagrammer::neighbours(&order)
+                .into_iter()
+                .map(|neighbour| {
+                    let new_score = self.score(&neighbour);
+                    (neighbour, new_score)
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .filter(|(_, new_score)| *new_score > score)
+            {
+                order = neighbour;
+                score = new_score;
+            }
+
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_score)| score > *best_score)
+            {
+                best = Some((order, score));
+            }
+        }
+
+        best.unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnarCipher;
+
+    const PLAINTEXT: &str = "We are discovered, flee at once and take the road to the north \
+        before the soldiers reach the river crossing at dawn";
+
+    const LONG_PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the \
+        age of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of light, it was the season of darkness, it was the \
+        spring of hope";
+
+    #[test]
+    fn test_crack_scytale() {
+        let clean_plaintext = clean(PLAINTEXT, DigitPolicy::default());
+
+        for length in [2, 5, 9] {
+            let ciphertext = Scytale::new(length).unwrap().encrypt(PLAINTEXT).unwrap();
+            let candidates = crack_scytale(&ciphertext).unwrap();
+
+            assert_eq!(clean_plaintext.len(), candidates.len());
+            assert_eq!(length, candidates[0].key.length());
+            assert_eq!(clean_plaintext, candidates[0].plaintext);
+        }
+    }
+
+    #[test]
+    fn test_crack_columnar() {
+        let clean_plaintext = clean(PLAINTEXT, DigitPolicy::default());
+
+        // brute force and hill climbing
+        for key in ["ZEBRAS", "CRYPTOGRAM", "KEY"] {
+            let ciphertext = ColumnarCipher::new(key)
+                .unwrap()
+                .encrypt(PLAINTEXT)
+                .unwrap();
+            let candidates = crack_columnar(&ciphertext, MAX_COLUMNS).unwrap();
+
+            assert_eq!(clean_plaintext, candidates[0].plaintext);
+            // the key found gives the same order as the original one
+            assert_eq!(
+                key.parse::<ColumnarKey>().unwrap().order(),
+                candidates[0].key.order()
+            );
+        }
+
+        // wider grids need a longer text
+        let clean_plaintext = clean(LONG_PLAINTEXT, DigitPolicy::default());
+
+        for key in ["BLACKSMITH", "SHIPWRECKED", "CRYPTOGRAPHY"] {
+            let ciphertext = ColumnarCipher::new(key)
+                .unwrap()
+                .encrypt(LONG_PLAINTEXT)
+                .unwrap();
+            let candidates = crack_columnar(&ciphertext, MAX_COLUMNS).unwrap();
+
+            assert_eq!(clean_plaintext, candidates[0].plaintext);
+            assert_eq!(
+                key.parse::<ColumnarKey>().unwrap().order(),
+                candidates[0].key.order()
+            );
+        }
+
+        assert!(crack_columnar("", MAX_COLUMNS).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_key_for() {
+        assert_eq!("EDBCFA", key_for(&[5, 2, 3, 1, 0, 4]));
+        assert_eq!(
+            vec![5, 2, 3, 1, 0, 4],
+            key_for(&[5, 2, 3, 1, 0, 4])
+                .parse::<ColumnarKey>()
+                .unwrap()
+                .order()
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        assert_eq!(vec![4, 3, 1, 2, 5, 0], inverse(&[5, 2, 3, 1, 0, 4]));
+        assert_eq!(
+            vec![5, 2, 3, 1, 0, 4],
+            inverse(&inverse(&[5, 2, 3, 1, 0, 4]))
+        );
+    }
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(
+            vec![
+                vec![0, 1, 2],
+                vec![0, 2, 1],
+                vec![1, 0, 2],
+                vec![1, 2, 0],
+                vec![2, 0, 1],
+                vec![2, 1, 0]
+            ],
+            permutations(3)
+        );
+        assert_eq!(5040, permutations(7).len());
+    }
+}
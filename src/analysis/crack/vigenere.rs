@@ -0,0 +1,333 @@
+/// Breaks Vigenere ciphers: the period is estimated first (by Kasiski examination and by the index
+/// of coincidence), then every letter of the keyword is recovered on its own, as the shift of the
+/// column of letters it encrypted.
+use super::{rank, Candidate};
+use crate::analysis::frequency::{chi_squared, index_of_coincidence, NgramCounts};
+use crate::analysis::{english, LanguageModel};
+use crate::keys::VigenereKeyword;
+use crate::text::TextMode;
+use crate::{Alphabet, Cipher, CipherError, VigenereCipher};
+
+use std::collections::HashMap;
+
+/// The longest period looked for, unless stated otherwise.
+pub const MAX_PERIOD: usize = 20;
+
+/// How many of the likeliest periods the solver tries.
+const PERIODS_TRIED: usize = 3;
+
+/// The fewest letters a column must have for its index of coincidence to mean anything.
+pub(crate) const MIN_COLUMN_LEN: usize = 5;
+
+/// The letters of the text (after folding), which are the only characters the key advances over.
+pub(crate) fn letters(text: &str) -> Vec<char> {
+    let alphabet = Alphabet::english();
+
+    alphabet
+        .fold(text)
+        .chars()
+        .filter(|ch| alphabet.contains(*ch))
+        .collect()
+}
+
+/// The letters of the text that were encrypted with the given letter of a key of the given
+/// period.
+fn column(letters: &[char], period: usize, idx: usize) -> String {
+    letters.iter().skip(idx).step_by(period).collect()
+}
+
+/// Kasiski examination: finds the sequences of three to five letters that appear more than once
+/// and counts, for each period from 2 to max_period, how many of the distances between their
+/// repetitions it divides.
+///
+/// Returns the periods with at least one vote, the most voted first.
+pub fn kasiski(ciphertext: &str, max_period: usize) -> Vec<(usize, usize)> {
+    let letters = letters(ciphertext);
+    let mut votes = vec![0; max_period + 1];
+
+    for len in 3..=5 {
+        let mut seen: HashMap<&[char], usize> = HashMap::new();
+
+        for (pos, sequence) in letters.windows(len).enumerate() {
+            // only the distance from the previous repetition, the others are sums of these
+            if let Some(previous) = seen.insert(sequence, pos) {
+                let distance = pos - previous;
+
+                for (period, votes) in votes.iter_mut().enumerate().skip(2) {
+                    if distance.is_multiple_of(period) {
+                        *votes += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut periods = votes
+        .into_iter()
+        .enumerate()
+        .filter(|(_, votes)| *votes > 0)
+        .collect::<Vec<(usize, usize)>>();
+    periods.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    periods
+}
+
+/// Friedman's estimate of the period, based on how far the index of coincidence of the whole
+/// ciphertext is from that of English (None if it looks no different from random letters).
+pub fn friedman(ciphertext: &str) -> Option<f64> {
+    let ic = index_of_coincidence(&NgramCounts::letters(ciphertext));
+
+    if ic <= english::RANDOM_INDEX_OF_COINCIDENCE {
+        return None;
+    }
+
+    Some(
+        (english::INDEX_OF_COINCIDENCE - english::RANDOM_INDEX_OF_COINCIDENCE)
+            / (ic - english::RANDOM_INDEX_OF_COINCIDENCE),
+    )
+}
+
+/// The average index of coincidence of the columns the letters split into with the given period.
+pub(crate) fn columns_ic(letters: &[char], period: usize) -> f64 {
+    (0..period)
+        .map(|idx| index_of_coincidence(&NgramCounts::letters(&column(letters, period, idx))))
+        .sum::<f64>()
+        / period as f64
+}
+
+/// Ranks the periods up to max_period (as long as the columns aren't too short) by the average
+/// index of coincidence of their columns, which is close to English for the actual period and
+/// close to random letters for the others.
+///
+/// The multiples of the actual period look just as good (if not better, with short columns), so a
+/// period is replaced by its smallest divisor that does almost as well, or that at least looks
+/// more like English than like random letters.
+pub fn estimate_periods(ciphertext: &str, max_period: usize) -> Vec<(usize, f64)> {
+    let halfway = (english::INDEX_OF_COINCIDENCE + english::RANDOM_INDEX_OF_COINCIDENCE) / 2.0;
+    let letters = letters(ciphertext);
+    let longest = max_period.min(letters.len() / MIN_COLUMN_LEN).max(1);
+
+    let ics = (1..=longest)
+        .map(|period| (period, columns_ic(&letters, period)))
+        .collect::<Vec<(usize, f64)>>();
+
+    let mut ranked = ics.clone();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut periods: Vec<(usize, f64)> = Vec::new();
+    for (period, ic) in ranked {
+        let divisor = ics
+            .iter()
+            .find(|(other, other_ic)| {
+                period.is_multiple_of(*other) && *other_ic >= (0.9 * ic).min(halfway)
+            })
+            .copied()
+            .unwrap_or((period, ic));
+
+        if !periods.iter().any(|(other, _)| *other == divisor.0) {
+            periods.push(divisor);
+        }
+    }
+
+    periods
+}
+
+/// Recovers the keyword of the given period, taking for each letter the shift that makes its
+/// column look the most like English (the one with the lowest chi-squared).
+///
+/// A keyword that repeats itself (e.g. "LEMLEM" with a period twice the actual one) is cut down
+/// to the part that repeats.
+pub fn recover_key(ciphertext: &str, period: usize) -> Result<VigenereKeyword, CipherError> {
+    if period == 0 {
+        return Err(CipherError::InvalidKey(
+            "the period of a Vigenere cipher must be greater than 0".to_string(),
+        ));
+    }
+
+    let alphabet = Alphabet::english();
+    let letters = letters(ciphertext);
+
+    let key = (0..period)
+        .map(|idx| {
+            let column = column(&letters, period, idx);
+            let chi = |shift: usize| {
+                let decrypted = column
+                    .chars()
+                    .map(|ch| {
+                        alphabet.symbol_at(alphabet.index_of(ch).unwrap() + alphabet.len() - shift)
+                    })
+                    .collect::<String>();
+
+                chi_squared(&NgramCounts::letters(&decrypted), &english::MONOGRAMS)
+                    .expect("Letters are compared with letters.")
+            };
+
+            let shift = (0..alphabet.len())
+                .min_by(|&a, &b| chi(a).total_cmp(&chi(b)))
+                .unwrap();
+            alphabet.symbol_at(shift)
+        })
+        .collect::<Vec<char>>();
+
+    // the shortest prefix the key is made of
+    let unit = (1..=period)
+        .find(|&len| period.is_multiple_of(len) && (len..period).all(|i| key[i] == key[i % len]))
+        .unwrap();
+
+    key[..unit].iter().collect::<String>().parse()
+}
+
+/// Breaks the ciphertext, trying either the given period or the likeliest ones (by index of
+/// coincidence, then by Kasiski examination), and returns the candidates from the most to the
+/// least likely to be English.
+///
+/// The plaintexts keep the spaces and punctuation of the ciphertext, to make them easier to read.
+/// Fails if the given period is longer than the ciphertext.
+pub fn crack_vigenere(
+    ciphertext: &str,
+    period: Option<usize>,
+) -> Result<Vec<Candidate<VigenereKeyword>>, CipherError> {
+    let periods = match period {
+        Some(period) => {
+            let len = letters(ciphertext).len();
+            if period > len {
+                return Err(CipherError::InvalidLength(format!(
+                    "the period ({}) can't be longer than the ciphertext ({} letters)",
+                    period, len
+                )));
+            }
+
+            vec![period]
+        }
+        None => {
+            let mut periods = estimate_periods(ciphertext, MAX_PERIOD)
+                .into_iter()
+                .take(PERIODS_TRIED)
+                .map(|(period, _)| period)
+                .collect::<Vec<usize>>();
+
+            // the repetitions may point somewhere else, especially in short ciphertexts
+            for (period, _) in kasiski(ciphertext, MAX_PERIOD)
+                .into_iter()
+                .take(PERIODS_TRIED)
+            {
+                if !periods.contains(&period) {
+                    periods.push(period);
+                }
+            }
+
+            periods
+        }
+    };
+
+    let mut candidates: Vec<Candidate<VigenereKeyword>> = Vec::new();
+    for period in periods {
+        let key = recover_key(ciphertext, period)?;
+        if candidates.iter().any(|candidate| candidate.key == key) {
+            continue;
+        }
+
+        let plaintext = VigenereCipher::from_key(key.clone())
+            .with_mode(TextMode::preserve_format())
+            .decrypt(ciphertext)?;
+
+        candidates.push(Candidate {
+            key,
+            score: LanguageModel::english().score(&plaintext),
+            plaintext,
+        });
+    }
+
+    rank(&mut candidates);
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age \
+        of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+        incredulity, it was the season of Light, it was the season of Darkness, it was the spring \
+        of hope, it was the winter of despair, we had everything before us, we had nothing before \
+        us, we were all going direct to Heaven, we were all going direct the other way.";
+
+    fn encrypt(key: &str) -> String {
+        VigenereCipher::new(key)
+            .unwrap()
+            .with_mode(TextMode::preserve_format())
+            .encrypt(PLAINTEXT)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_kasiski() {
+        let periods = kasiski(&encrypt("LEMON"), MAX_PERIOD);
+
+        // the period (or a multiple of it) gets the most votes
+        assert_eq!(0, periods[0].0 % 5);
+        assert!(kasiski("ABCDEFG", MAX_PERIOD).is_empty());
+    }
+
+    #[test]
+    fn test_friedman() {
+        let estimate = friedman(&encrypt("LEMON")).unwrap();
+        assert!((2.0..10.0).contains(&estimate));
+
+        let estimate = friedman(PLAINTEXT).unwrap();
+        assert!(estimate < 1.5);
+    }
+
+    #[test]
+    fn test_estimate_periods() {
+        for key in ["LEMON", "CIPHERS", "KEY"] {
+            let periods = estimate_periods(&encrypt(key), MAX_PERIOD);
+
+            assert_eq!(key.len(), periods[0].0);
+        }
+
+        assert_eq!(1, estimate_periods(PLAINTEXT, MAX_PERIOD)[0].0);
+        assert_eq!(vec![(1, 0.0)], estimate_periods("", MAX_PERIOD));
+    }
+
+    #[test]
+    fn test_recover_key() {
+        let ciphertext = encrypt("LEMON");
+
+        assert_eq!("LEMON", recover_key(&ciphertext, 5).unwrap().letters());
+        // a multiple of the period gives the same key
+        assert_eq!("LEMON", recover_key(&ciphertext, 10).unwrap().letters());
+        assert!(recover_key(&ciphertext, 0).is_err());
+    }
+
+    #[test]
+    fn test_crack() {
+        for key in ["LEMON", "CIPHERS", "KEY", "DICKENS"] {
+            let candidates = crack_vigenere(&encrypt(key), None).unwrap();
+
+            assert_eq!(key, candidates[0].key.letters());
+            assert_eq!(PLAINTEXT, candidates[0].plaintext);
+        }
+
+        // the period can be forced
+        let candidates = crack_vigenere(&encrypt("LEMON"), Some(5)).unwrap();
+        assert_eq!(1, candidates.len());
+        assert_eq!("LEMON", candidates[0].key.letters());
+
+        // the cleaned up ciphertext works just as well
+        let ciphertext = VigenereCipher::new("LEMON")
+            .unwrap()
+            .encrypt(PLAINTEXT)
+            .unwrap();
+        let candidates = crack_vigenere(&ciphertext, None).unwrap();
+        assert_eq!("LEMON", candidates[0].key.letters());
+
+        // a period can't be longer than the ciphertext
+        assert!(matches!(
+            crack_vigenere("abc", Some(50)),
+            Err(CipherError::InvalidLength(_))
+        ));
+        assert!(crack_vigenere("abc", Some(3)).is_ok());
+    }
+}
@@ -0,0 +1,165 @@
+/// Reference distributions of English text.
+///
+/// The letter frequencies are the usual ones from Lewand's "Cryptological Mathematics", while the
+/// bigram and trigram tables only list the most common ones (the rest of the distribution is
+/// spread over the n-grams that don't appear).
+use super::frequency::Reference;
+
+/// The index of coincidence of the letters of English text.
+pub const INDEX_OF_COINCIDENCE: f64 = 0.0667;
+
+/// The index of coincidence of random letters (1/26).
+pub const RANDOM_INDEX_OF_COINCIDENCE: f64 = 1.0 / 26.0;
+
+/// The frequencies of the 26 letters, in percent.
+pub const MONOGRAMS: Reference = Reference {
+    n: 1,
+    frequencies: &[
+        ("A", 8.167),
+        ("B", 1.492),
+        ("C", 2.782),
+        ("D", 4.253),
+        ("E", 12.702),
+        ("F", 2.228),
+        ("G", 2.015),
+        ("H", 6.094),
+        ("I", 6.966),
+        ("J", 0.153),
+        ("K", 0.772),
+        ("L", 4.025),
+        ("M", 2.406),
+        ("N", 6.749),
+        ("O", 7.507),
+        ("P", 1.929),
+        ("Q", 0.095),
+        ("R", 5.987),
+        ("S", 6.327),
+        ("T", 9.056),
+        ("U", 2.758),
+        ("V", 0.978),
+        ("W", 2.360),
+        ("X", 0.150),
+        ("Y", 1.974),
+        ("Z", 0.074),
+    ],
+};
+
+/// The frequencies of the 40 most common bigrams, in percent.
+pub const BIGRAMS: Reference = Reference {
+    n: 2,
+    frequencies: &[
+        ("TH", 3.56),
+        ("HE", 3.07),
+        ("IN", 2.43),
+        ("ER", 2.05),
+        ("AN", 1.99),
+        ("RE", 1.85),
+        ("ON", 1.76),
+        ("AT", 1.49),
+        ("EN", 1.45),
+        ("ND", 1.35),
+        ("TI", 1.34),
+        ("ES", 1.34),
+        ("OR", 1.28),
+        ("TE", 1.20),
+        ("OF", 1.17),
+        ("ED", 1.17),
+        ("IS", 1.13),
+        ("IT", 1.12),
+        ("AL", 1.09),
+        ("AR", 1.07),
+        ("ST", 1.05),
+        ("TO", 1.04),
+        ("NT", 1.04),
+        ("NG", 0.95),
+        ("SE", 0.93),
+        ("HA", 0.93),
+        ("AS", 0.87),
+        ("OU", 0.87),
+        ("IO", 0.83),
+        ("LE", 0.83),
+        ("VE", 0.83),
+        ("CO", 0.79),
+        ("ME", 0.79),
+        ("DE", 0.76),
+        ("HI", 0.76),
+        ("RI", 0.73),
+        ("RO", 0.73),
+        ("IC", 0.70),
+        ("NE", 0.69),
+        ("EA", 0.69),
+    ],
+};
+
+/// The frequencies of the 30 most common trigrams, in percent.
+pub const TRIGRAMS: Reference = Reference {
+    n: 3,
+    frequencies: &[
+        ("THE", 1.81),
+        ("AND", 0.73),
+        ("ING", 0.72),
+        ("ENT", 0.42),
+        ("ION", 0.42),
+        ("HER", 0.36),
+        ("FOR", 0.34),
+        ("THA", 0.33),
+        ("NTH", 0.33),
+        ("INT", 0.32),
+        ("ERE", 0.31),
+        ("TIO", 0.31),
+        ("TER", 0.30),
+        ("EST", 0.28),
+        ("ERS", 0.28),
+        ("ATI", 0.26),
+        ("HAT", 0.26),
+        ("ATE", 0.25),
+        ("ALL", 0.25),
+        ("ETH", 0.24),
+        ("HES", 0.24),
+        ("VER", 0.24),
+        ("HIS", 0.24),
+        ("OFT", 0.22),
+        ("ITH", 0.21),
+        ("FTH", 0.21),
+        ("STH", 0.21),
+        ("OTH", 0.21),
+        ("RES", 0.21),
+        ("ONT", 0.20),
+    ],
+};
+
+/// The reference distribution of the n-grams of the given length (only 1 to 3 are available).
+pub fn ngrams(n: usize) -> Option<&'static Reference> {
+    match n {
+        1 => Some(&MONOGRAMS),
+        2 => Some(&BIGRAMS),
+        3 => Some(&TRIGRAMS),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables() {
+        let total = MONOGRAMS.frequencies.iter().map(|(_, f)| f).sum::<f64>();
+        assert!((total - 100.0).abs() < 0.01);
+
+        for n in 1..=3 {
+            let reference = ngrams(n).unwrap();
+
+            assert_eq!(reference.n, n);
+            assert!(reference
+                .frequencies
+                .iter()
+                .all(|(ngram, _)| ngram.len() == n));
+        }
+        assert!(ngrams(4).is_none());
+
+        assert_eq!("E", MONOGRAMS.ranked()[0].0);
+        assert!((MONOGRAMS.frequency("Z") - 0.00074).abs() < 1e-9);
+        assert_eq!(0.0, BIGRAMS.frequency("QZ"));
+    }
+}
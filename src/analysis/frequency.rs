@@ -0,0 +1,343 @@
+/// N-gram statistics of a text and their comparison with a reference distribution.
+use crate::{Alphabet, CipherError};
+
+use std::collections::HashMap;
+
+/// The counts of the n-grams (runs of n consecutive symbols) of a text.
+///
+/// Only the symbols of the alphabet are taken into account (after folding): everything else,
+/// spaces and punctuation included, is dropped before counting, so n-grams may span words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NgramCounts {
+    n: usize,
+    counts: HashMap<String, usize>,
+    total: usize,
+}
+
+impl NgramCounts {
+    /// Counts the n-grams of the text made up of symbols of the given alphabet (fails unless n is
+    /// greater than 0).
+    pub fn count(text: &str, n: usize, alphabet: &Alphabet) -> Result<Self, CipherError> {
+        if n == 0 {
+            return Err(CipherError::InvalidLength(
+                "an n-gram must have at least one symbol".to_string(),
+            ));
+        }
+
+        Ok(NgramCounts::tally(text, n, alphabet))
+    }
+
+    /// Counts the letters of the English alphabet in the text.
+    pub fn letters(text: &str) -> Self {
+        NgramCounts::tally(text, 1, &Alphabet::english())
+    }
+
+    fn tally(text: &str, n: usize, alphabet: &Alphabet) -> Self {
+        let symbols = alphabet
+            .fold(text)
+            .chars()
+            .filter(|ch| alphabet.contains(*ch))
+            .collect::<Vec<char>>();
+
+        let mut counts = HashMap::new();
+        for window in symbols.windows(n) {
+            *counts.entry(window.iter().collect::<String>()).or_insert(0) += 1;
+        }
+
+        NgramCounts {
+            n,
+            counts,
+            total: symbols.len().saturating_sub(n - 1),
+        }
+    }
+
+    /// The length of the n-grams.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The number of n-grams in the text.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// How many times the n-gram appears in the text.
+    pub fn get(&self, ngram: &str) -> usize {
+        self.counts.get(ngram).copied().unwrap_or(0)
+    }
+
+    /// The relative frequency of the n-gram (between 0 and 1), or 0 if the text is too short to
+    /// have any.
+    pub fn frequency(&self, ngram: &str) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.get(ngram) as f64 / self.total as f64
+    }
+
+    /// The n-grams appearing in the text with their counts, the most common first (ties are
+    /// broken alphabetically).
+    pub fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked = self
+            .counts
+            .iter()
+            .map(|(ngram, count)| (ngram.as_str(), *count))
+            .collect::<Vec<(&str, usize)>>();
+
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        ranked
+    }
+}
+
+/// The reference distribution of the n-grams of a language, as a table of frequencies in
+/// percent.
+///
+/// The table doesn't need to list every n-gram: whatever is left to 100% is taken to be spread
+/// over the ones that are missing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reference {
+    /// The length of the n-grams.
+    pub n: usize,
+    pub frequencies: &'static [(&'static str, f64)],
+}
+
+impl Reference {
+    /// The relative frequency of the n-gram (between 0 and 1), or 0 if it isn't in the table.
+    pub fn frequency(&self, ngram: &str) -> f64 {
+        self.frequencies
+            .iter()
+            .find(|(other, _)| *other == ngram)
+            .map_or(0.0, |(_, frequency)| frequency / 100.0)
+    }
+
+    /// The relative frequency of all the n-grams missing from the table.
+    pub fn missing(&self) -> f64 {
+        let listed = self.frequencies.iter().map(|(_, f)| f).sum::<f64>() / 100.0;
+
+        (1.0 - listed).max(0.0)
+    }
+
+    /// The n-grams of the table with their relative frequencies, the most common first.
+    pub fn ranked(&self) -> Vec<(&'static str, f64)> {
+        let mut ranked = self
+            .frequencies
+            .iter()
+            .map(|(ngram, frequency)| (*ngram, frequency / 100.0))
+            .collect::<Vec<(&str, f64)>>();
+
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+
+        ranked
+    }
+}
+
+/// The chi-squared statistic of the counts against the reference distribution: the lower, the
+/// closer the text is to the language (an empty text gives 0).
+///
+/// The n-grams missing from the reference table are lumped together in a single category, which
+/// is left out if the table accounts for (almost) all of the distribution.
+///
+/// Fails if the counts and the reference are not about n-grams of the same length.
+pub fn chi_squared(counts: &NgramCounts, reference: &Reference) -> Result<f64, CipherError> {
+    check_orders(counts, reference)?;
+
+    let total = counts.total() as f64;
+    if total == 0.0 {
+        return Ok(0.0);
+    }
+
+    let mut listed = 0;
+    let mut chi = 0.0;
+
+    for (ngram, frequency) in reference.frequencies {
+        let observed = counts.get(ngram);
+        let expected = total * frequency / 100.0;
+
+        listed += observed;
+        chi += (observed as f64 - expected).powi(2) / expected;
+    }
+
+    // one category for everything the table doesn't list
+    let expected = total * reference.missing();
+    if expected > 1e-6 {
+        let observed = (counts.total() - listed) as f64;
+
+        chi += (observed - expected).powi(2) / expected;
+    }
+
+    Ok(chi)
+}
+
+/// The index of coincidence of the counts: the probability that two n-grams picked at random
+/// from the text are the same (0 if there are less than two).
+///
+/// For single letters it is about 0.067 in English and 1/26 (about 0.038) in random text.
+pub fn index_of_coincidence(counts: &NgramCounts) -> f64 {
+    let total = counts.total() as f64;
+    if total < 2.0 {
+        return 0.0;
+    }
+
+    let pairs = counts
+        .counts
+        .values()
+        .map(|&count| (count * count.saturating_sub(1)) as f64)
+        .sum::<f64>();
+
+    pairs / (total * (total - 1.0))
+}
+
+/// The log-likelihood of the counts under the reference distribution, i.e. the natural logarithm
+/// of the probability of seeing those n-grams in the language: the higher (the closer to 0), the
+/// more the text looks like the language.
+///
+/// The n-grams missing from the reference table are given a tenth of the frequency of the rarest
+/// one listed, so that they are unlikely without being impossible.
+///
+/// Fails if the counts and the reference are not about n-grams of the same length.
+pub fn log_likelihood(counts: &NgramCounts, reference: &Reference) -> Result<f64, CipherError> {
+    check_orders(counts, reference)?;
+
+    let floor = reference
+        .frequencies
+        .iter()
+        .map(|(_, frequency)| frequency / 100.0)
+        .fold(f64::INFINITY, f64::min)
+        / 10.0;
+
+    Ok(counts
+        .counts
+        .iter()
+        .map(|(ngram, count)| {
+            let frequency = reference.frequency(ngram);
+
+            *count as f64 * frequency.max(floor).ln()
+        })
+        .sum())
+}
+
+/// Makes sure the counts can be compared with the reference.
+fn check_orders(counts: &NgramCounts, reference: &Reference) -> Result<(), CipherError> {
+    if counts.n() != reference.n {
+        return Err(CipherError::InvalidLength(format!(
+            "can't compare {}-grams with a reference of {}-grams",
+            counts.n(),
+            reference.n
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::english;
+
+    #[test]
+    fn test_counts() {
+        let counts = NgramCounts::count("Hello, world!", 2, &Alphabet::english()).unwrap();
+
+        assert_eq!(2, counts.n());
+        assert_eq!(9, counts.total());
+        assert_eq!(1, counts.get("LL"));
+        // across the space
+        assert_eq!(1, counts.get("OW"));
+        assert_eq!(0, counts.get("XY"));
+        assert!((counts.frequency("LO") - 1.0 / 9.0).abs() < 1e-9);
+
+        let letters = NgramCounts::letters("Hello, world!");
+        assert_eq!(10, letters.total());
+        assert_eq!(vec![("L", 3), ("O", 2), ("D", 1)], letters.ranked()[..3]);
+
+        // too short for a single trigram
+        let counts = NgramCounts::count("ab", 3, &Alphabet::english()).unwrap();
+        assert_eq!(0, counts.total());
+        assert_eq!(0.0, counts.frequency("ABC"));
+    }
+
+    #[test]
+    fn test_other_alphabets() {
+        let alphabet = Alphabet::new("01").unwrap();
+        let counts = NgramCounts::count("0110 1a", 1, &alphabet).unwrap();
+
+        assert_eq!(5, counts.total());
+        assert_eq!(3, counts.get("1"));
+
+        // the folding rules apply as well
+        let counts = NgramCounts::count("Jovis", 1, &Alphabet::latin()).unwrap();
+        assert_eq!(2, counts.get("I"));
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        // texts of about the same length, since the statistic grows with it
+        let english = "Once upon a midnight dreary, while I pondered, weak and weary, over many a \
+            quaint and curious volume of forgotten lore, while I nodded, nearly napping, suddenly \
+            there came a tapping, as of some one gently rapping, rapping at my chamber door";
+        let random = "QZXJKVQZXJKVBWQZPXJYQZKXVJQZWXBYKQZJXVQWZPXKJYQ".repeat(4);
+
+        let english_chi = chi_squared(&NgramCounts::letters(english), &english::MONOGRAMS).unwrap();
+        let random_chi = chi_squared(&NgramCounts::letters(&random), &english::MONOGRAMS).unwrap();
+        assert!(english_chi < random_chi);
+
+        let english_chi = chi_squared(
+            &NgramCounts::count(english, 2, &Alphabet::english()).unwrap(),
+            &english::BIGRAMS,
+        )
+        .unwrap();
+        let random_chi = chi_squared(
+            &NgramCounts::count(&random, 2, &Alphabet::english()).unwrap(),
+            &english::BIGRAMS,
+        )
+        .unwrap();
+        assert!(english_chi < random_chi);
+
+        assert_eq!(
+            0.0,
+            chi_squared(&NgramCounts::letters(""), &english::MONOGRAMS).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_index_of_coincidence() {
+        assert!((index_of_coincidence(&NgramCounts::letters("AABB")) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(0.0, index_of_coincidence(&NgramCounts::letters("ABCD")));
+        assert_eq!(0.0, index_of_coincidence(&NgramCounts::letters("A")));
+    }
+
+    #[test]
+    fn test_log_likelihood() {
+        let english = NgramCounts::letters("Meet me at the usual place");
+        let shifted = NgramCounts::letters("Phhw ph dw wkh xvxdo sodfh");
+
+        assert!(
+            log_likelihood(&english, &english::MONOGRAMS).unwrap()
+                > log_likelihood(&shifted, &english::MONOGRAMS).unwrap()
+        );
+        assert_eq!(
+            0.0,
+            log_likelihood(&NgramCounts::letters(""), &english::MONOGRAMS).unwrap()
+        );
+
+        // a bigram missing from the table is unlikely, but not impossible
+        let rare = NgramCounts::count("QZ", 2, &Alphabet::english()).unwrap();
+        assert!(log_likelihood(&rare, &english::BIGRAMS)
+            .unwrap()
+            .is_finite());
+    }
+
+    #[test]
+    fn test_invalid_orders() {
+        assert!(matches!(
+            NgramCounts::count("abc", 0, &Alphabet::english()),
+            Err(CipherError::InvalidLength(_))
+        ));
+
+        let bigrams = NgramCounts::count("abc", 2, &Alphabet::english()).unwrap();
+        assert!(chi_squared(&bigrams, &english::MONOGRAMS).is_err());
+        assert!(log_likelihood(&bigrams, &english::MONOGRAMS).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+/// Guesses which of the ciphers of the crate produced a ciphertext, from the usual diagnostic
+/// statistics: substitutions keep the index of coincidence of English while polyalphabetic ciphers
+/// flatten it, transpositions even keep the letter frequencies, and so on.
+use super::crack::vigenere;
+use super::english;
+use super::frequency::{index_of_coincidence, NgramCounts};
+use crate::{Alphabet, CipherError};
+
+use std::fmt;
+
+/// The fewest letters the statistics need to tell anything apart (there is no index of
+/// coincidence with less than two).
+pub const MIN_LETTERS: usize = 2;
+
+/// The families of ciphers of the crate that can be told apart by their statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CipherFamily {
+    /// The letters are only moved around (scytale, columnar, double columnar and route).
+    Transposition,
+    /// Every letter is shifted by the same amount.
+    Shift,
+    /// Every letter is replaced by another one, always the same.
+    Monoalphabetic,
+    /// The letters are shifted by the letters of a repeating keyword.
+    Vigenere,
+    /// The letters are shifted by a keystream that never repeats.
+    Solitaire,
+}
+
+impl CipherFamily {
+    /// All the families, in the order they are listed.
+    pub const ALL: [CipherFamily; 5] = [
+        CipherFamily::Transposition,
+        CipherFamily::Shift,
+        CipherFamily::Monoalphabetic,
+        CipherFamily::Vigenere,
+        CipherFamily::Solitaire,
+    ];
+
+    /// The names (in the registry) of the ciphers of the family.
+    pub fn ciphers(&self) -> &'static [&'static str] {
+        match self {
+            CipherFamily::Transposition => &["scytale", "columnar", "double-columnar", "route"],
+            CipherFamily::Shift => &["shift"],
+            CipherFamily::Monoalphabetic => &["monoalphabetic"],
+            CipherFamily::Vigenere => &["vigenere"],
+            CipherFamily::Solitaire => &["solitaire"],
+        }
+    }
+}
+
+impl fmt::Display for CipherFamily {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CipherFamily::Transposition => "transposition",
+            CipherFamily::Shift => "shift",
+            CipherFamily::Monoalphabetic => "monoalphabetic",
+            CipherFamily::Vigenere => "vigenere",
+            CipherFamily::Solitaire => "solitaire",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// The diagnostic statistics of a ciphertext.
+///
+/// The chi-squared statistics are divided by the number of letters, so that they don't grow with
+/// the length of the text: they are well under 1 for English.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    /// The number of letters of the text.
+    pub letters: usize,
+    /// How many different letters appear in the text.
+    pub distinct_letters: usize,
+    /// Whether the number of letters is even.
+    pub even_length: bool,
+    /// Whether the text contains digits.
+    pub has_digits: bool,
+    /// The index of coincidence of the letters.
+    pub index_of_coincidence: f64,
+    /// How far the letter frequencies are from those of English.
+    pub chi_squared: f64,
+    /// How far the letter frequencies are from those of English once shifted back by the best
+    /// shift, along with that shift.
+    pub shifted_chi_squared: (usize, f64),
+    /// How far the letter frequencies, sorted, are from those of English, sorted as well (i.e.
+    /// whatever the letters are).
+    pub sorted_chi_squared: f64,
+    /// The period (from 2 on) whose columns have the highest average index of coincidence, along
+    /// with that index (None if the text is too short to be split into columns). It is as high as
+    /// English for the period of a Vigenere cipher, and for any period of a substitution.
+    pub periodic_ic: Option<(usize, f64)>,
+}
+
+impl Statistics {
+    /// Computes the statistics of the letters of the English alphabet in the text.
+    pub fn compute(text: &str) -> Self {
+        let alphabet = Alphabet::english();
+        let counts = NgramCounts::letters(text);
+        let observed = (0..alphabet.len())
+            .map(|idx| counts.get(&alphabet.symbol_at(idx).to_string()) as f64)
+            .collect::<Vec<f64>>();
+        let expected = (0..alphabet.len())
+            .map(|idx| english::MONOGRAMS.frequency(&alphabet.symbol_at(idx).to_string()))
+            .collect::<Vec<f64>>();
+
+        let letters = counts.total();
+        // the chi-squared statistic per letter of the observed counts against the expected
+        // frequencies, with the observed counts rotated by the given shift
+        let fit = |observed: &[f64], expected: &[f64], shift: usize| {
+            if letters == 0 {
+                return 0.0;
+            }
+
+            (0..observed.len())
+                .map(|idx| {
+                    let expected = expected[idx] * letters as f64;
+                    (observed[(idx + shift) % observed.len()] - expected).powi(2) / expected
+                })
+                .sum::<f64>()
+                / letters as f64
+        };
+
+        let shifted_chi_squared = (0..alphabet.len())
+            .map(|shift| (shift, fit(&observed, &expected, shift)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        let mut sorted_observed = observed.clone();
+        sorted_observed.sort_by(|a, b| b.total_cmp(a));
+        let mut sorted_expected = expected.clone();
+        sorted_expected.sort_by(|a, b| b.total_cmp(a));
+
+        Statistics {
+            letters,
+            distinct_letters: observed.iter().filter(|&&count| count > 0.0).count(),
+            even_length: letters.is_multiple_of(2),
+            has_digits: text.chars().any(|ch| ch.is_ascii_digit()),
+            index_of_coincidence: index_of_coincidence(&counts),
+            chi_squared: fit(&observed, &expected, 0),
+            shifted_chi_squared,
+            sorted_chi_squared: fit(&sorted_observed, &sorted_expected, 0),
+            periodic_ic: Statistics::periodic_ic(text),
+        }
+    }
+
+    /// The period from 2 to `vigenere::MAX_PERIOD` whose columns have the highest average index
+    /// of coincidence (as long as they aren't too short). Since the multiples of a period do just
+    /// as well, the smallest period doing almost as well as the best one is taken instead.
+    fn periodic_ic(text: &str) -> Option<(usize, f64)> {
+        let letters = vigenere::letters(text);
+        let longest = vigenere::MAX_PERIOD.min(letters.len() / vigenere::MIN_COLUMN_LEN);
+
+        let ics = (2..=longest)
+            .map(|period| (period, vigenere::columns_ic(&letters, period)))
+            .collect::<Vec<(usize, f64)>>();
+        let highest = ics.iter().map(|(_, ic)| *ic).fold(0.0, f64::max);
+
+        ics.into_iter().find(|(_, ic)| *ic >= 0.9 * highest)
+    }
+}
+
+/// A guess of the family of the cipher, with how likely it is (between 0 and 1, the likelihoods
+/// of all the guesses adding up to 1).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guess {
+    pub family: CipherFamily,
+    pub likelihood: f64,
+}
+
+/// How much an index of coincidence looks like English rather than random letters: 1 for
+/// English, 0 for random letters (and anything in between).
+fn englishness(ic: f64) -> f64 {
+    ((ic - english::RANDOM_INDEX_OF_COINCIDENCE)
+        / (english::INDEX_OF_COINCIDENCE - english::RANDOM_INDEX_OF_COINCIDENCE))
+        .clamp(0.0, 1.0)
+}
+
+/// How good a fit a chi-squared statistic per letter is: 1 for a perfect fit, 1/2 at the
+/// threshold and close to 0 well above it.
+fn good_fit(chi: f64) -> f64 {
+    const THRESHOLD: f64 = 0.5;
+
+    1.0 / (1.0 + (chi / THRESHOLD).powi(2))
+}
+
+/// Ranks the families of ciphers by how well the statistics match them, the likeliest first.
+///
+/// - the index of coincidence tells substitutions and transpositions (as high as English) from
+///   polyalphabetic ciphers (closer to random letters);
+/// - among the former, transpositions keep the letters of English, shift ciphers keep them up to
+///   a shift and monoalphabetic substitutions only keep their sorted frequencies;
+/// - among the latter, the columns of a Vigenere cipher look like English for the right period,
+///   while Solitaire never repeats itself.
+///
+/// The guesses get more reliable with the length of the text: a couple hundred letters are
+/// usually enough, while less than `MIN_LETTERS` give an error.
+pub fn identify(statistics: &Statistics) -> Result<Vec<Guess>, CipherError> {
+    if statistics.letters < MIN_LETTERS {
+        return Err(CipherError::InvalidLength(format!(
+            "can't tell anything from {} letters, at least {} are needed",
+            statistics.letters, MIN_LETTERS
+        )));
+    }
+
+    let english_ic = englishness(statistics.index_of_coincidence);
+    let periodic = statistics
+        .periodic_ic
+        .map_or(0.0, |(_, ic)| englishness(ic));
+    let fits = good_fit(statistics.chi_squared);
+    let fits_shifted = good_fit(statistics.shifted_chi_squared.1);
+    let fits_sorted = good_fit(statistics.sorted_chi_squared);
+
+    let weights = CipherFamily::ALL.map(|family| match family {
+        CipherFamily::Transposition => english_ic * fits,
+        CipherFamily::Shift => english_ic * fits_shifted * (1.0 - fits),
+        CipherFamily::Monoalphabetic => english_ic * fits_sorted * (1.0 - fits_shifted),
+        CipherFamily::Vigenere => (1.0 - english_ic) * periodic,
+        CipherFamily::Solitaire => (1.0 - english_ic) * (1.0 - periodic),
+    });
+    let total = weights.iter().sum::<f64>();
+
+    let mut guesses = CipherFamily::ALL
+        .iter()
+        .copied()
+        .zip(weights.iter().copied())
+        .map(|(family, weight)| Guess {
+            family,
+            likelihood: weight / total,
+        })
+        .collect::<Vec<Guess>>();
+    guesses.sort_by(|a, b| b.likelihood.total_cmp(&a.likelihood));
+
+    Ok(guesses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age \
+        of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+        incredulity, it was the season of Light, it was the season of Darkness, it was the spring \
+        of hope, it was the winter of despair.";
+
+    fn encrypt(cipher: &str, key: &str) -> String {
+        registry::build_cipher(cipher, key)
+            .unwrap()
+            .encrypt(PLAINTEXT)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_statistics() {
+        let statistics = Statistics::compute("Hello, world 42");
+
+        assert_eq!(10, statistics.letters);
+        assert_eq!(7, statistics.distinct_letters);
+        assert!(statistics.even_length);
+        assert!(statistics.has_digits);
+        assert!(Statistics::compute("Hi there").periodic_ic.is_none());
+
+        let plain = Statistics::compute(PLAINTEXT);
+        let shifted = Statistics::compute(&encrypt("shift", "3"));
+        assert!(plain.chi_squared < 0.5);
+        assert!(shifted.chi_squared > 1.0);
+        assert_eq!(3, shifted.shifted_chi_squared.0);
+        assert!((plain.sorted_chi_squared - shifted.sorted_chi_squared).abs() < 1e-9);
+        assert!((plain.index_of_coincidence - shifted.index_of_coincidence).abs() < 1e-9);
+
+        let empty = Statistics::compute("");
+        assert_eq!(0, empty.letters);
+        assert_eq!(0.0, empty.chi_squared);
+    }
+
+    #[test]
+    fn test_identify() {
+        for (cipher, key, family) in [
+            ("scytale", "7", CipherFamily::Transposition),
+            ("columnar", "zebras", CipherFamily::Transposition),
+            ("shift", "11", CipherFamily::Shift),
+            (
+                "monoalphabetic",
+                "qwertyuiopasdfghjklzxcvbnm",
+                CipherFamily::Monoalphabetic,
+            ),
+            ("vigenere", "lemon", CipherFamily::Vigenere),
+            ("solitaire", "pass:cryptonomicon", CipherFamily::Solitaire),
+        ] {
+            let guesses = identify(&Statistics::compute(&encrypt(cipher, key))).unwrap();
+
+            assert_eq!(family, guesses[0].family, "{}: {:?}", cipher, guesses);
+            assert!((guesses.iter().map(|g| g.likelihood).sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+
+        for text in ["", "42", "a 1"] {
+            assert!(matches!(
+                identify(&Statistics::compute(text)),
+                Err(CipherError::InvalidLength(_))
+            ));
+        }
+        assert_eq!(5, identify(&Statistics::compute("ab")).unwrap().len());
+    }
+}
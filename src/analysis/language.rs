@@ -0,0 +1,608 @@
+/// Language models: the log-probabilities of the n-grams of a language, from single letters to
+/// quadgrams, used to tell how much a text looks like it.
+///
+/// Models of English, Italian, German, French and Latin are built in; models of other languages
+/// (or of other kinds of text) can be trained on any text and saved to a file.
+///
+/// The built-in models in models/ were made with the train-model command: English out of Henry V
+/// (the text of the benchmarks, leaving out the n-grams seen only once, since the solvers need
+/// the rarer ones too), the others out of the sample texts in data/corpora.
+///
+/// Those samples are only 8 to 10 KB each, against the 160 KB of Henry V: the Italian, German,
+/// French and Latin models are toy models, good enough to tell the languages apart but with most
+/// of their trigrams and quadgrams never seen, so they should not be trusted with the solvers.
+/// Train a model on a longer text for any real use.
+use crate::{Alphabet, CipherError};
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// The longest n-grams a model keeps track of.
+pub const MAX_ORDER: usize = 4;
+
+/// The largest alphabet a model can be written in: the tables hold every possible n-gram, so
+/// they grow with the fourth power of the number of symbols (40 symbols already make for 2.5
+/// million quadgrams).
+pub const MAX_SYMBOLS: usize = 40;
+
+/// The names of the built-in models.
+pub const BUILTIN: [&str; 5] = ["english", "italian", "german", "french", "latin"];
+
+/// The predefined alphabets a model can refer to by name when it is saved.
+const ALPHABET_NAMES: [&str; 6] = [
+    "english",
+    "italian",
+    "german",
+    "french",
+    "latin",
+    "alphanumeric",
+];
+
+/// The log-probabilities (in base 10) of the n-grams of a language, from 1 to `MAX_ORDER`
+/// symbols long, over the alphabet the language is written in.
+///
+/// The n-grams that never appeared in the training text get a floor of their own, a hundredth of
+/// the probability of those appearing once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageModel {
+    name: String,
+    alphabet: Alphabet,
+    /// For each order, the log-probabilities of all the n-grams, indexed by the positions of
+    /// their symbols in the alphabet.
+    tables: Vec<Vec<f32>>,
+    /// For each order, the log-probability of the n-grams missing from the training text.
+    floors: Vec<f32>,
+}
+
+impl LanguageModel {
+    /// Starts training a model of the language written in the given alphabet (of at most
+    /// `MAX_SYMBOLS` symbols).
+    pub fn builder(name: &str, alphabet: Alphabet) -> Result<LanguageModelBuilder, CipherError> {
+        LanguageModelBuilder::new(name, alphabet)
+    }
+
+    /// The built-in model of English, trained on Shakespeare's Henry V.
+    pub fn english() -> &'static LanguageModel {
+        static MODEL: OnceLock<LanguageModel> = OnceLock::new();
+
+        MODEL.get_or_init(|| include_str!("models/english.txt").parse().unwrap())
+    }
+
+    /// The built-in model of Italian.
+    pub fn italian() -> &'static LanguageModel {
+        static MODEL: OnceLock<LanguageModel> = OnceLock::new();
+
+        MODEL.get_or_init(|| include_str!("models/italian.txt").parse().unwrap())
+    }
+
+    /// The built-in model of German.
+    pub fn german() -> &'static LanguageModel {
+        static MODEL: OnceLock<LanguageModel> = OnceLock::new();
+
+        MODEL.get_or_init(|| include_str!("models/german.txt").parse().unwrap())
+    }
+
+    /// The built-in model of French.
+    pub fn french() -> &'static LanguageModel {
+        static MODEL: OnceLock<LanguageModel> = OnceLock::new();
+
+        MODEL.get_or_init(|| include_str!("models/french.txt").parse().unwrap())
+    }
+
+    /// The built-in model of Latin.
+    pub fn latin() -> &'static LanguageModel {
+        static MODEL: OnceLock<LanguageModel> = OnceLock::new();
+
+        MODEL.get_or_init(|| include_str!("models/latin.txt").parse().unwrap())
+    }
+
+    /// The built-in model of the given language (one of `BUILTIN`), if there is one.
+    pub fn builtin(name: &str) -> Option<&'static LanguageModel> {
+        match name.to_lowercase().as_str() {
+            "english" => Some(LanguageModel::english()),
+            "italian" => Some(LanguageModel::italian()),
+            "german" => Some(LanguageModel::german()),
+            "french" => Some(LanguageModel::french()),
+            "latin" => Some(LanguageModel::latin()),
+            _ => None,
+        }
+    }
+
+    /// Reads a model saved with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CipherError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Writes the model to a file, in the format read by `load` (and by `from_str`).
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CipherError> {
+        fs::write(path, self.to_string())?;
+
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The log-probability of the text: the sum of those of its quadgrams. The higher (the closer
+    /// to 0), the more the text looks like the language; texts shorter than four symbols score 0.
+    pub fn score(&self, text: &str) -> f64 {
+        self.sum(&self.indices(text), MAX_ORDER)
+    }
+
+    /// Same as `score`, using the n-grams of the given length instead (None unless it is between
+    /// 1 and `MAX_ORDER`).
+    pub fn score_ngrams(&self, text: &str, n: usize) -> Option<f64> {
+        if !(1..=MAX_ORDER).contains(&n) {
+            return None;
+        }
+
+        Some(self.sum(&self.indices(text), n))
+    }
+
+    /// Same as `score`, for a text given as the positions of its symbols in the alphabet (this is
+    /// what the solvers spend their time on, so it goes without strings).
+    pub(crate) fn score_indices(&self, indices: &[u8]) -> f64 {
+        let table = &self.tables[MAX_ORDER - 1];
+
+        indices
+            .windows(MAX_ORDER)
+            .map(|ngram| {
+                let idx = ngram.iter().fold(0, |idx, &symbol| {
+                    idx * self.alphabet.len() + symbol as usize
+                });
+
+                table[idx] as f64
+            })
+            .sum()
+    }
+
+    /// The sum of the log-probabilities of the n-grams of the given order.
+    fn sum(&self, indices: &[usize], n: usize) -> f64 {
+        let table = &self.tables[n - 1];
+
+        indices
+            .windows(n)
+            .map(|ngram| table[self.index(ngram)] as f64)
+            .sum()
+    }
+
+    /// The log-probability of a single n-gram (None if it isn't made of 1 to `MAX_ORDER` symbols
+    /// of the alphabet).
+    pub fn log_probability(&self, ngram: &str) -> Option<f64> {
+        let folded = self.alphabet.fold(ngram);
+        let indices = folded
+            .chars()
+            .map(|ch| self.alphabet.index_of(ch))
+            .collect::<Option<Vec<usize>>>()?;
+
+        match indices.len() {
+            1..=MAX_ORDER => Some(self.tables[indices.len() - 1][self.index(&indices)] as f64),
+            _ => None,
+        }
+    }
+
+    /// The positions in the alphabet of the symbols of the text (after folding, everything else
+    /// is dropped).
+    pub(crate) fn indices(&self, text: &str) -> Vec<usize> {
+        indices(&self.alphabet, text)
+    }
+
+    fn index(&self, ngram: &[usize]) -> usize {
+        index(&self.alphabet, ngram)
+    }
+
+    /// The n-gram at the given position of the table of the given order.
+    fn ngram(&self, mut idx: usize, n: usize) -> String {
+        let mut ngram = vec![' '; n];
+        for ch in ngram.iter_mut().rev() {
+            *ch = self.alphabet.symbol_at(idx % self.alphabet.len());
+            idx /= self.alphabet.len();
+        }
+
+        ngram.into_iter().collect()
+    }
+}
+
+/// The positions in the alphabet of the symbols of the text (after folding).
+fn indices(alphabet: &Alphabet, text: &str) -> Vec<usize> {
+    alphabet
+        .fold(text)
+        .chars()
+        .filter_map(|ch| alphabet.index_of(ch))
+        .collect()
+}
+
+/// Makes sure the alphabet is small enough for the tables of a model to fit in memory.
+fn check_size(alphabet: &Alphabet) -> Result<(), CipherError> {
+    if alphabet.len() > MAX_SYMBOLS {
+        return Err(CipherError::InvalidModel(format!(
+            "the alphabet has {} symbols, but a model can't have more than {}",
+            alphabet.len(),
+            MAX_SYMBOLS
+        )));
+    }
+
+    Ok(())
+}
+
+/// The position of the n-gram (given as the positions of its symbols) in the table of its order.
+fn index(alphabet: &Alphabet, ngram: &[usize]) -> usize {
+    ngram
+        .iter()
+        .fold(0, |idx, &symbol| idx * alphabet.len() + symbol)
+}
+
+/// The format read by `LanguageModel::load`: the name of the model, its alphabet (by name if it
+/// is one of the predefined ones, otherwise as its symbols, leaving out the folding rules), the
+/// floor of each order and then the n-grams that aren't at the floor, one per line.
+///
+/// ```text
+/// name english
+/// alphabet english
+/// floor 1 -6.12
+/// ...
+/// E -0.907
+/// ...
+/// ```
+impl fmt::Display for LanguageModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alphabet = ALPHABET_NAMES
+            .iter()
+            .find(|name| name.parse::<Alphabet>().ok().as_ref() == Some(&self.alphabet))
+            .map_or_else(|| self.alphabet.to_string(), |name| name.to_string());
+
+        writeln!(f, "name {}", self.name)?;
+        writeln!(f, "alphabet {}", alphabet)?;
+        for (order, floor) in self.floors.iter().enumerate() {
+            writeln!(f, "floor {} {}", order + 1, floor)?;
+        }
+
+        for (order, (table, floor)) in self.tables.iter().zip(&self.floors).enumerate() {
+            for (idx, log_prob) in table.iter().enumerate() {
+                if log_prob != floor {
+                    writeln!(f, "{} {}", self.ngram(idx, order + 1), log_prob)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for LanguageModel {
+    type Err = CipherError;
+
+    /// Parses a model in the format written by `save` (empty lines and lines starting with # are
+    /// skipped).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: &str| CipherError::InvalidModel(format!("unexpected line '{}'", line));
+
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let mut header = |field: &str| match lines.next() {
+            Some(line) => match line.split_once(' ') {
+                Some((key, value)) if key == field => Ok(value.trim().to_string()),
+                _ => Err(invalid(line)),
+            },
+            None => Err(CipherError::InvalidModel(format!("missing the {}", field))),
+        };
+        let name = header("name")?;
+        let alphabet = header("alphabet")?.parse::<Alphabet>()?;
+        check_size(&alphabet)?;
+
+        let mut model = LanguageModel {
+            name,
+            tables: (1..=MAX_ORDER as u32)
+                .map(|n| vec![0.0; alphabet.len().pow(n)])
+                .collect(),
+            floors: vec![0.0; MAX_ORDER],
+            alphabet,
+        };
+        let mut floors_read = 0;
+
+        for line in lines {
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+
+            match fields[..] {
+                ["floor", order, log_prob] if floors_read < MAX_ORDER => {
+                    if order != (floors_read + 1).to_string() {
+                        return Err(invalid(line));
+                    }
+                    let log_prob = log_prob.parse::<f32>().map_err(|_| invalid(line))?;
+
+                    model.floors[floors_read] = log_prob;
+                    model.tables[floors_read].fill(log_prob);
+                    floors_read += 1;
+                }
+                [ngram, log_prob] if floors_read == MAX_ORDER => {
+                    let indices = ngram
+                        .chars()
+                        .map(|ch| model.alphabet.index_of(ch))
+                        .collect::<Option<Vec<usize>>>()
+                        .filter(|indices| (1..=MAX_ORDER).contains(&indices.len()))
+                        .ok_or_else(|| invalid(line))?;
+                    let log_prob = log_prob.parse::<f32>().map_err(|_| invalid(line))?;
+
+                    let idx = model.index(&indices);
+                    model.tables[indices.len() - 1][idx] = log_prob;
+                }
+                _ => return Err(invalid(line)),
+            }
+        }
+
+        if floors_read < MAX_ORDER {
+            return Err(CipherError::InvalidModel(format!(
+                "missing the floor of the {}-grams",
+                floors_read + 1
+            )));
+        }
+
+        Ok(model)
+    }
+}
+
+/// Trains a `LanguageModel` by counting the n-grams of some texts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageModelBuilder {
+    name: String,
+    alphabet: Alphabet,
+    /// For each order, the number of times each n-gram appeared.
+    counts: Vec<Vec<u32>>,
+    min_count: u32,
+}
+
+impl LanguageModelBuilder {
+    /// Creates a builder that hasn't seen any text yet, failing if the alphabet has more than
+    /// `MAX_SYMBOLS` symbols.
+    pub fn new(name: &str, alphabet: Alphabet) -> Result<Self, CipherError> {
+        check_size(&alphabet)?;
+
+        Ok(LanguageModelBuilder {
+            name: name.to_string(),
+            counts: (1..=MAX_ORDER as u32)
+                .map(|n| vec![0; alphabet.len().pow(n)])
+                .collect(),
+            min_count: 1,
+            alphabet,
+        })
+    }
+
+    /// Counts the n-grams of the symbols of the text (after folding, everything else is dropped).
+    /// The n-grams don't run from one text to the next.
+    pub fn with_text(mut self, text: &str) -> Self {
+        let indices = indices(&self.alphabet, text);
+
+        for (n, counts) in (1..=MAX_ORDER).zip(self.counts.iter_mut()) {
+            for ngram in indices.windows(n) {
+                counts[index(&self.alphabet, ngram)] += 1;
+            }
+        }
+
+        self
+    }
+
+    /// Counts the n-grams of the contents of the file.
+    pub fn with_file<P: AsRef<Path>>(self, path: P) -> Result<Self, CipherError> {
+        let text = fs::read_to_string(path)?;
+
+        Ok(self.with_text(&text))
+    }
+
+    /// Leaves out the n-grams appearing less than min_count times, which then get the floor like
+    /// the ones that never appeared: the model gets smaller, and so does the file it is saved to.
+    pub fn with_min_count(mut self, min_count: u32) -> Self {
+        self.min_count = min_count.max(1);
+        self
+    }
+
+    /// Turns the counts into log-probabilities (rounded to three decimals, which is plenty and
+    /// keeps the saved models small).
+    pub fn build(&self) -> LanguageModel {
+        let round = |log_prob: f64| ((log_prob * 1000.0).round() / 1000.0) as f32;
+        let mut model = LanguageModel {
+            name: self.name.clone(),
+            alphabet: self.alphabet.clone(),
+            tables: vec![Vec::new(); MAX_ORDER],
+            floors: vec![0.0; MAX_ORDER],
+        };
+
+        for (order, counts) in self.counts.iter().enumerate() {
+            let total = counts
+                .iter()
+                .map(|&count| count as f64)
+                .sum::<f64>()
+                .max(1.0);
+            let floor = round((0.01 / total).log10());
+
+            model.floors[order] = floor;
+            model.tables[order] = counts
+                .iter()
+                .map(|&count| match count {
+                    count if count < self.min_count => floor,
+                    count => round((count as f64 / total).log10()),
+                })
+                .collect();
+        }
+
+        model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let model = LanguageModel::builder("test", Alphabet::english())
+            .unwrap()
+            .with_text("abcdabcd")
+            .build();
+
+        // the log-probabilities are stored as f32
+        let log_probability = |ngram| model.log_probability(ngram).map(|p| p as f32);
+
+        // A appears twice out of eight letters, ABCD twice out of five quadgrams
+        assert_eq!(Some(-0.602), log_probability("a"));
+        assert_eq!(Some(-0.398), log_probability("ABCD"));
+        assert_eq!(Some(-2.903), log_probability("z"));
+        assert_eq!(None, log_probability("abcde"));
+        assert_eq!(None, log_probability(""));
+
+        assert!(model.score("abcd") > model.score("bcda"));
+        assert!(model.score("bcda") > model.score("zzzz"));
+        assert_eq!(0.0, model.score("abc"));
+        assert!((model.score_ngrams("abc", 1).unwrap() - 3.0 * -0.602).abs() < 1e-6);
+        assert_eq!(None, model.score_ngrams("abc", 0));
+        assert_eq!(None, model.score_ngrams("abc", MAX_ORDER + 1));
+
+        // the texts are counted separately
+        let model = LanguageModel::builder("test", Alphabet::english())
+            .unwrap()
+            .with_text("ab")
+            .with_text("cd")
+            .build();
+        assert_eq!(model.log_probability("ZZ"), model.log_probability("BC"));
+        assert!(model.log_probability("AB") > model.log_probability("BC"));
+    }
+
+    #[test]
+    fn test_min_count() {
+        let model = LanguageModel::builder("test", Alphabet::english())
+            .unwrap()
+            .with_text("aab")
+            .with_min_count(2)
+            .build();
+
+        assert_eq!(model.log_probability("b"), model.log_probability("z"));
+        assert!(model.log_probability("a") > model.log_probability("b"));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let model = LanguageModel::builder("latin", Alphabet::latin())
+            .unwrap()
+            .with_text("Gallia est omnis divisa in partes tres")
+            .build();
+        let saved = model.to_string();
+
+        assert!(saved.starts_with("name latin\nalphabet latin\nfloor 1 "));
+        assert_eq!(model, saved.parse::<LanguageModel>().unwrap());
+
+        // custom alphabets are written out
+        let model = LanguageModel::builder("binary", "01".parse().unwrap())
+            .unwrap()
+            .with_text("0110")
+            .build();
+        assert_eq!(model, model.to_string().parse::<LanguageModel>().unwrap());
+
+        assert!("".parse::<LanguageModel>().is_err());
+        assert!("name x\nalphabet english\nfloor 1 -2"
+            .parse::<LanguageModel>()
+            .is_err());
+        assert!(
+            "name x\nalphabet english\nfloor 2 -2\nfloor 1 -2\nfloor 3 -2\nfloor 4 -2"
+                .parse::<LanguageModel>()
+                .is_err()
+        );
+        assert!(
+            "name x\nalphabet english\nfloor 1 -2\nfloor 2 -2\nfloor 3 -2\nfloor 4 -2\nABCDE -1"
+                .parse::<LanguageModel>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_alphabet_size() {
+        let symbols = (0..200)
+            .map(|n| char::from_u32(0x4e00 + n).unwrap())
+            .collect::<String>();
+        let alphabet = symbols.parse::<Alphabet>().unwrap();
+
+        assert!(LanguageModel::builder("big", alphabet).is_err());
+        assert!(format!(
+            "name big\nalphabet {}\nfloor 1 -2\nfloor 2 -2\nfloor 3 -2\nfloor 4 -2",
+            symbols
+        )
+        .parse::<LanguageModel>()
+        .is_err());
+    }
+
+    #[test]
+    fn test_files() {
+        let dir = std::env::temp_dir();
+        let corpus = dir.join("classical_cryptography_corpus.txt");
+        let saved = dir.join("classical_cryptography_model.txt");
+
+        fs::write(&corpus, "Arma virumque cano, Troiae qui primus ab oris").unwrap();
+        let model = LanguageModel::builder("latin", Alphabet::latin())
+            .unwrap()
+            .with_file(&corpus)
+            .unwrap()
+            .build();
+        model.save(&saved).unwrap();
+        assert_eq!(model, LanguageModel::load(&saved).unwrap());
+
+        fs::remove_file(&corpus).unwrap();
+        fs::remove_file(&saved).unwrap();
+        assert!(LanguageModel::builder("latin", Alphabet::latin())
+            .unwrap()
+            .with_file(&corpus)
+            .is_err());
+    }
+
+    #[test]
+    fn test_builtin() {
+        let samples = [
+            (
+                "english",
+                "Now is the winter of our discontent made glorious summer",
+            ),
+            (
+                "italian",
+                "Nel mezzo del cammin di nostra vita mi ritrovai per una selva oscura",
+            ),
+            (
+                "german",
+                "Als Gregor Samsa eines Morgens aus unruhigen Träumen erwachte",
+            ),
+            (
+                "french",
+                "Longtemps, je me suis couché de bonne heure. Parfois, à peine ma bougie",
+            ),
+            (
+                "latin",
+                "Gallia est omnis divisa in partes tres, quarum unam incolunt Belgae",
+            ),
+        ];
+
+        for (language, sample) in samples.iter().copied() {
+            let model = LanguageModel::builtin(language).unwrap();
+            assert_eq!(language, model.name());
+
+            // each model likes its own language best (per symbol, since the alphabets differ)
+            let per_symbol = |model: &LanguageModel| {
+                model.score(sample) / model.indices(sample).len().saturating_sub(3) as f64
+            };
+            let best = BUILTIN
+                .iter()
+                .map(|name| LanguageModel::builtin(name).unwrap())
+                .max_by(|a, b| per_symbol(a).total_cmp(&per_symbol(b)))
+                .unwrap();
+            assert_eq!(language, best.name());
+        }
+
+        assert!(LanguageModel::builtin("klingon").is_none());
+    }
+}
@@ -0,0 +1,11 @@
+/// This module holds the tools used to analyse ciphertexts: n-gram statistics and the reference
+/// distributions of natural languages they are compared against.
+pub mod crack;
+pub mod english;
+pub mod frequency;
+pub mod identify;
+pub mod language;
+
+pub use frequency::{chi_squared, index_of_coincidence, log_likelihood, NgramCounts, Reference};
+pub use identify::{identify, CipherFamily, Guess, Statistics};
+pub use language::{LanguageModel, LanguageModelBuilder};
@@ -0,0 +1,252 @@
+use super::polybius::{PolybiusSquare, SquareLetters};
+use super::transposition::{transpose, untranspose, Grid};
+use super::utils::clean;
+use crate::keys::AdfgvxKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Cipher, CipherError};
+
+/// The ADFGX and ADFGVX ciphers, which replace every symbol with the labels of its row and column
+/// in a Polybius square and then shuffle the labels with a columnar transposition.
+///
+/// ADFGX uses a 5×5 square of letters (I and J merged), ADFGVX a 6×6 one of letters and digits.
+pub struct AdfgvxCipher {
+    key: AdfgvxKey,
+    square: PolybiusSquare,
+    /// The labels of the rows and of the columns of the square.
+    labels: Vec<char>,
+    digits: DigitPolicy,
+}
+
+impl AdfgvxCipher {
+    /// Initializes an ADFGVX cipher given the key (see AdfgvxKey).
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(AdfgvxCipher::from_key(key.parse()?))
+    }
+
+    /// Initializes an ADFGVX cipher with an already validated key.
+    pub fn from_key(key: AdfgvxKey) -> Self {
+        AdfgvxCipher {
+            square: PolybiusSquare::six(key.square()),
+            labels: "ADFGVX".chars().collect(),
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Initializes an ADFGX cipher given the key (digits in the keyword of the square are
+    /// ignored).
+    pub fn adfgx(key: &str) -> Result<Self, CipherError> {
+        Ok(AdfgvxCipher::adfgx_from_key(key.parse()?))
+    }
+
+    /// Initializes an ADFGX cipher with an already validated key.
+    pub fn adfgx_from_key(key: AdfgvxKey) -> Self {
+        AdfgvxCipher {
+            square: PolybiusSquare::five(key.square(), SquareLetters::MergeIJ),
+            labels: "ADFGX".chars().collect(),
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets what happens to the digits that aren't in the square (i.e. with ADFGX), which can
+    /// only be stripped, spelled out or passed through as they are.
+    ///
+    /// `DigitPolicy::Combined` is fine with ADFGVX, whose square already holds the digits.
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        let has_digits = self.square.alphabet().contains('0');
+
+        match digits {
+            DigitPolicy::Combined if !has_digits => {
+                return Err(CipherError::InvalidKey(
+                    "the ADFGX cipher can't encrypt the digits, use ADFGVX instead".to_string(),
+                ))
+            }
+            DigitPolicy::SeparateRing => {
+                return Err(CipherError::InvalidKey(
+                    "the ADFGVX cipher can't encrypt the digits in a ring of their own".to_string(),
+                ))
+            }
+            _ => self.digits = digits,
+        }
+
+        Ok(self)
+    }
+
+    pub fn key(&self) -> &AdfgvxKey {
+        &self.key
+    }
+
+    pub fn square(&self) -> &PolybiusSquare {
+        &self.square
+    }
+
+    /// The order in which the characters of a text of len characters are read by the
+    /// transposition.
+    fn reading_order(&self, len: usize) -> Vec<usize> {
+        let transposition = self.key.transposition();
+
+        Grid::new(len, transposition.columns()).by_columns(&transposition.order())
+    }
+
+    /// Turns the pairs of labels back into symbols, leaving the digits passed through as they
+    /// are.
+    fn unlabel(&self, text: &str) -> Result<String, CipherError> {
+        let mut plaintext = String::with_capacity(text.len() / 2);
+        let mut chars = text.chars();
+        let label = |ch: char| self.labels.iter().position(|&label| label == ch);
+
+        while let Some(ch) = chars.next() {
+            let row = match label(ch) {
+                Some(row) => row,
+                None if ch.is_ascii_digit() => {
+                    plaintext.push(ch);
+                    continue;
+                }
+                None => return Err(CipherError::InvalidSymbol(ch)),
+            };
+
+            match chars.next() {
+                Some(next) => match label(next) {
+                    Some(col) => plaintext.push(self.square.symbol_at(row, col)),
+                    None if next.is_ascii_digit() => {
+                        return Err(CipherError::InvalidLength(format!(
+                            "the label {} is followed by the digit {} rather than by another label",
+                            ch, next
+                        )))
+                    }
+                    None => return Err(CipherError::InvalidSymbol(next)),
+                },
+                None => {
+                    return Err(CipherError::InvalidLength(
+                        "the ciphertext ends in the middle of a pair of labels".to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(plaintext)
+    }
+}
+
+impl Cipher for AdfgvxCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.square.alphabet(), TextMode::Clean, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let mut labelled = Vec::with_capacity(plaintext.len() * 2);
+
+        for ch in self.clean_input(plaintext).chars() {
+            match self.square.coordinates(ch) {
+                Some((row, col)) => labelled.extend([self.labels[row], self.labels[col]].iter()),
+                None => labelled.push(ch),
+            }
+        }
+        let order = self.reading_order(labelled.len());
+
+        Ok(transpose(&labelled, &order))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext).chars().collect::<Vec<char>>();
+        let order = self.reading_order(clean_ciphertext.len());
+
+        self.unlabel(&untranspose(&clean_ciphertext, &order))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_adfgvx() {
+        // from https://en.wikipedia.org/wiki/ADFGVX_cipher
+        let cipher = AdfgvxCipher::new("na1c3h8tb2ome5wrpd4f6g7i9j0klqsuvxyz,privacy").unwrap();
+        let ciphertext = "DGDDDAGDDGAFADDFDADVDVFAADVX";
+
+        assert_eq!(ciphertext, cipher.encrypt("Attack at 1200AM").unwrap());
+        assert_eq!("ATTACKAT1200AM", cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_known_adfgx() {
+        // from https://en.wikipedia.org/wiki/ADFGVX_cipher
+        let cipher = AdfgvxCipher::adfgx("btalpdhozkqfvsngicuxmrewy,cargo").unwrap();
+        let ciphertext = "FAXDFADDDGDGFFFAFAXAFAFX";
+
+        assert_eq!(ciphertext, cipher.encrypt("Attack at once").unwrap());
+        assert_eq!("ATTACKATONCE", cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_ciphertext() {
+        let cipher = AdfgvxCipher::adfgx("btalpdhozkqfvsngicuxmrewy,cargo").unwrap();
+
+        assert!(matches!(
+            cipher.decrypt("FAXDF"),
+            Err(CipherError::InvalidLength(_))
+        ));
+        assert!(matches!(
+            cipher.decrypt("FAXDFB"),
+            Err(CipherError::InvalidSymbol('B'))
+        ));
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = AdfgvxCipher::adfgx("btalpdhozkqfvsngicuxmrewy,cargo").unwrap();
+        // digits are transposed along with the labels
+        assert_eq!(
+            "AT1",
+            cipher.decrypt(&cipher.encrypt("at1").unwrap()).unwrap()
+        );
+
+        let cipher = cipher.with_digits(DigitPolicy::Strip).unwrap();
+        assert_eq!(
+            "AT",
+            cipher.decrypt(&cipher.encrypt("at1").unwrap()).unwrap()
+        );
+
+        assert!(AdfgvxCipher::adfgx("btalp,cargo")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .is_err());
+        assert!(AdfgvxCipher::new("btalp,cargo")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .is_ok());
+        assert!(AdfgvxCipher::new("btalp,cargo")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let keys = [
+            "na1c3h8tb2ome5wrpd4f6g7i9j0klqsuvxyz,privacy",
+            "zebra,s",
+            "x,zebras",
+        ];
+        let plaintext = "the quick brown fox jumps over the lazy dog 1234567890";
+
+        for key in keys.iter() {
+            for cipher in [AdfgvxCipher::new(key), AdfgvxCipher::adfgx(key)].iter() {
+                let cipher = cipher.as_ref().unwrap();
+
+                for len in 0..plaintext.len() {
+                    let plaintext = &plaintext[..len];
+                    let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+                    assert_eq!(
+                        cipher.clean_input(plaintext),
+                        cipher.decrypt(&ciphertext).unwrap()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use super::utils::{clean, mod_inverse, substitute, Ring, DIGITS};
+use crate::keys::AffineKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+/// The affine cipher: the symbol at position x of the alphabet becomes the one at position
+/// ax + b (mod the size of the alphabet), and is brought back by multiplying by the inverse of a.
+///
+/// The shift cipher is the special case where a is 1, the multiplicative cipher the one where b
+/// is 0, and Atbash (the alphabet reversed) the one where both are -1.
+pub struct AffineCipher {
+    key: AffineKey,
+    alphabet: Alphabet,
+    /// The inverse of a modulo the size of the alphabet.
+    inverse: usize,
+    /// The inverse of a modulo 10, if the digits get a ring of their own.
+    digit_inverse: usize,
+    mode: TextMode,
+    digits: DigitPolicy,
+}
+
+impl AffineCipher {
+    /// Initializes an affine cipher with the given multiplier and shift (a must be coprime with
+    /// 26).
+    pub fn new(a: i64, b: i64) -> Result<Self, CipherError> {
+        AffineCipher::from_key(AffineKey::new(a, b))
+    }
+
+    /// Initializes an affine cipher with the given key, which still has to be checked against
+    /// the English alphabet.
+    pub fn from_key(key: AffineKey) -> Result<Self, CipherError> {
+        AffineCipher::with_alphabet(key, Alphabet::english())
+    }
+
+    /// Initializes the Atbash cipher, which reverses the alphabet.
+    pub fn atbash() -> Self {
+        AffineCipher::from_key(AffineKey::atbash()).unwrap()
+    }
+
+    /// Initializes a multiplicative cipher (a must be coprime with 26).
+    pub fn multiplicative(a: i64) -> Result<Self, CipherError> {
+        AffineCipher::from_key(AffineKey::multiplicative(a))
+    }
+
+    /// Initializes an affine cipher working on the given alphabet, whose size a must be coprime
+    /// with.
+    pub fn with_alphabet(key: AffineKey, alphabet: Alphabet) -> Result<Self, CipherError> {
+        let inverse = AffineCipher::inverse(&key, alphabet.len())?;
+
+        Ok(AffineCipher {
+            key,
+            alphabet,
+            inverse,
+            digit_inverse: 0,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        })
+    }
+
+    /// Sets how the characters that are not part of the alphabet are treated.
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets what happens to the digits.
+    ///
+    /// With `DigitPolicy::Combined` the digits are appended to the alphabet and with
+    /// `DigitPolicy::SeparateRing` they are encrypted modulo 10, so a must be coprime with the
+    /// size of that ring as well.
+    pub fn with_digits(self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        let mut cipher = match digits {
+            DigitPolicy::Combined => {
+                let alphabet = self.alphabet.with_digits();
+
+                AffineCipher::with_alphabet(self.key, alphabet)?.with_mode(self.mode)
+            }
+            DigitPolicy::SeparateRing => AffineCipher {
+                digit_inverse: AffineCipher::inverse(&self.key, DIGITS)?,
+                ..self
+            },
+            _ => self,
+        };
+        cipher.digits = digits;
+
+        Ok(cipher)
+    }
+
+    pub fn key(&self) -> &AffineKey {
+        &self.key
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The inverse of a modulo m, failing if a isn't coprime with m.
+    fn inverse(key: &AffineKey, m: usize) -> Result<usize, CipherError> {
+        mod_inverse(key.a(), m).ok_or_else(|| {
+            CipherError::InvalidKey(format!(
+                "the multiplier of an affine cipher must be coprime with the size of the alphabet ({}), got {}",
+                m,
+                key.a()
+            ))
+        })
+    }
+
+    /// The size of the ring and the key reduced to it.
+    fn reduced(&self, ring: Ring) -> (usize, usize, usize) {
+        let m = match ring {
+            Ring::Symbols => self.alphabet.len(),
+            Ring::Digits => DIGITS,
+        };
+        let reduce = |n: i64| n.rem_euclid(m as i64) as usize;
+
+        (m, reduce(self.key.a()), reduce(self.key.b()))
+    }
+}
+
+impl Cipher for AffineCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+
+        substitute(
+            &clean_plaintext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, _, ring| {
+                let (m, a, b) = self.reduced(ring);
+
+                (a * idx + b) % m
+            },
+        )
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+
+        substitute(
+            &clean_ciphertext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, _, ring| {
+                let (m, _, b) = self.reduced(ring);
+                let inverse = match ring {
+                    Ring::Symbols => self.inverse,
+                    Ring::Digits => self.digit_inverse,
+                };
+
+                inverse * (idx + m - b) % m
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_pairs() {
+        // from https://cryptii.com
+        let cipher = AffineCipher::new(5, 8).unwrap();
+        let plaintext = "affinecipher";
+        let ciphertext = "IHHWVCSWFRCP";
+
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(
+            cipher.clean_input(plaintext),
+            cipher.decrypt(ciphertext).unwrap()
+        );
+
+        // the key is reduced modulo 26
+        let cipher = AffineCipher::new(31, -18).unwrap();
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_multipliers() {
+        for a in [0, 2, 13, 26, -2] {
+            assert!(matches!(
+                AffineCipher::new(a, 1),
+                Err(CipherError::InvalidKey(_))
+            ));
+        }
+
+        // 3 is coprime with 26, but not with the 21 letters of the Italian alphabet
+        assert!(AffineCipher::with_alphabet(AffineKey::new(3, 1), Alphabet::italian()).is_err());
+        assert!(AffineCipher::with_alphabet(AffineKey::new(2, 1), Alphabet::italian()).is_ok());
+    }
+
+    #[test]
+    fn test_special_cases() {
+        let cipher = AffineCipher::atbash();
+        assert_eq!("ZBXYZ", cipher.encrypt("Aycba").unwrap());
+        assert_eq!("AYCBA", cipher.decrypt("ZBXYZ").unwrap());
+
+        // Atbash reverses any alphabet
+        let cipher = AffineCipher::with_alphabet(AffineKey::atbash(), Alphabet::latin()).unwrap();
+        assert_eq!("ZYXA", cipher.encrypt("abcz").unwrap());
+
+        // the multiplicative cipher leaves A in place
+        let cipher = AffineCipher::multiplicative(3).unwrap();
+        assert_eq!("ADGJ", cipher.encrypt("abcd").unwrap());
+        assert_eq!("ABCD", cipher.decrypt("ADGJ").unwrap());
+        assert!(AffineCipher::multiplicative(4).is_err());
+
+        // and a multiplier of 1 gives a shift cipher
+        let cipher = AffineCipher::new(1, 3).unwrap();
+        assert_eq!(
+            crate::ShiftCipher::new(3)
+                .unwrap()
+                .encrypt("Caesar")
+                .unwrap(),
+            cipher.encrypt("Caesar").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let cipher = AffineCipher::new(5, 8)
+            .unwrap()
+            .with_mode(TextMode::preserve_format());
+        let plaintext = "Affine cipher, 1 key!";
+        let ciphertext = "Ihhwvc swfrcp, 1 gcy!";
+
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(plaintext, cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let plaintext = "Code 1234";
+
+        let cipher = AffineCipher::new(7, 3)
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .unwrap();
+        assert_eq!("RXYF0741", cipher.encrypt(plaintext).unwrap());
+        assert_eq!("CODE1234", cipher.decrypt("RXYF0741").unwrap());
+
+        // 5 is coprime with 26 but not with 10, and 3 is not coprime with 36
+        assert!(AffineCipher::new(5, 8)
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .is_err());
+        assert!(AffineCipher::new(3, 8)
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .is_err());
+
+        let cipher = AffineCipher::new(7, 3)
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .unwrap();
+        assert_eq!(36, cipher.alphabet().len());
+        assert_eq!(
+            "CODE1234",
+            cipher.decrypt(&cipher.encrypt(plaintext).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let plaintext = String::from("affine");
+
+        for a in (1..26).filter(|a| a % 2 == 1 && *a != 13) {
+            for b in 0..26 {
+                let cipher = AffineCipher::new(a, b).unwrap();
+
+                assert_eq!(
+                    plaintext.to_uppercase(),
+                    cipher
+                        .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                        .unwrap()
+                );
+            }
+        }
+    }
+}
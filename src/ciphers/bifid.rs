@@ -0,0 +1,187 @@
+use super::polybius::{fractionate, unfractionate, PolybiusSquare};
+use super::utils::clean;
+use crate::keys::BifidKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+/// The Bifid cipher, which writes the coordinates of every letter in a Polybius square below it,
+/// reads them back row by row and turns every pair into a letter again.
+///
+/// The text is split into groups of the given period first, if any, and each group is
+/// fractionated on its own.
+pub struct BifidCipher {
+    key: BifidKey,
+    square: PolybiusSquare,
+    digits: DigitPolicy,
+}
+
+impl BifidCipher {
+    /// Initializes a Bifid cipher given the key (see BifidKey).
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(BifidCipher::from_key(key.parse()?))
+    }
+
+    /// Initializes a Bifid cipher with an already validated key.
+    pub fn from_key(key: BifidKey) -> Self {
+        BifidCipher {
+            square: PolybiusSquare::five(key.keyword(), key.letters()),
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets what happens to the digits.
+    ///
+    /// With `DigitPolicy::Combined` the square becomes a 6×6 one holding all 26 letters and the 10
+    /// digits, while `DigitPolicy::SeparateRing` isn't supported.
+    pub fn with_digits(self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        let mut cipher = match digits {
+            DigitPolicy::Combined => BifidCipher {
+                square: PolybiusSquare::six(self.key.keyword()),
+                ..self
+            },
+            DigitPolicy::SeparateRing => {
+                return Err(CipherError::InvalidKey(
+                    "the Bifid cipher can't encrypt the digits in a ring of their own".to_string(),
+                ))
+            }
+            _ => self,
+        };
+        cipher.digits = digits;
+
+        Ok(cipher)
+    }
+
+    pub fn key(&self) -> &BifidKey {
+        &self.key
+    }
+
+    pub fn square(&self) -> &PolybiusSquare {
+        &self.square
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        self.square.alphabet()
+    }
+
+    /// Replaces the symbols of the text, a group at a time, with those found at the coordinates
+    /// returned by f (leaving the digits passed through where they are).
+    fn transform(&self, text: &str, f: fn(&[Vec<usize>]) -> Vec<Vec<usize>>) -> String {
+        let mut chars = text.chars().collect::<Vec<char>>();
+        let positions = (0..chars.len())
+            .filter(|pos| self.alphabet().contains(chars[*pos]))
+            .collect::<Vec<usize>>();
+        let period = self.key.period().unwrap_or(positions.len()).max(1);
+
+        for group in positions.chunks(period) {
+            let coordinates = group
+                .iter()
+                .map(|pos| {
+                    let (row, col) = self.square.coordinates(chars[*pos]).unwrap();
+                    vec![row, col]
+                })
+                .collect::<Vec<Vec<usize>>>();
+
+            for (pos, cell) in group.iter().zip(f(&coordinates)) {
+                chars[*pos] = self.square.symbol_at(cell[0], cell[1]);
+            }
+        }
+
+        chars.into_iter().collect()
+    }
+}
+
+impl Cipher for BifidCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.alphabet(), TextMode::Clean, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(self.transform(&self.clean_input(plaintext), fractionate))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        Ok(self.transform(&self.clean_input(ciphertext), unfractionate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_text() {
+        // from https://en.wikipedia.org/wiki/Bifid_cipher
+        let cipher = BifidCipher::new("bgwkzqpndsioaxefclumthyvr").unwrap();
+
+        assert_eq!("UAEOLWRINS", cipher.encrypt("Flee at once").unwrap());
+        assert_eq!("FLEEATONCE", cipher.decrypt("UAEOLWRINS").unwrap());
+    }
+
+    #[test]
+    fn test_period() {
+        let cipher = BifidCipher::new("bgwkzqpndsioaxefclumthyvr:5").unwrap();
+        let ciphertext = cipher.encrypt("fleeatonce").unwrap();
+
+        // each group of 5 is fractionated on its own
+        assert_eq!(
+            BifidCipher::new("bgwkzqpndsioaxefclumthyvr")
+                .unwrap()
+                .encrypt("flee a")
+                .unwrap()[..5],
+            ciphertext[..5]
+        );
+        assert_ne!("UAEOLWRINS", ciphertext);
+        assert_eq!("FLEEATONCE", cipher.decrypt(&ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = BifidCipher::new("bgwkzqpndsioaxefclumthyvr").unwrap();
+        // digits stay where they are
+        assert_eq!("UAEOL1WRINS", cipher.encrypt("Flee a1t once").unwrap());
+
+        let cipher = BifidCipher::new("agent:3")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .unwrap();
+        assert_eq!(6, cipher.square().size());
+        assert_eq!(
+            "AGENT007",
+            cipher
+                .decrypt(&cipher.encrypt("Agent 007").unwrap())
+                .unwrap()
+        );
+
+        assert!(BifidCipher::new("agent")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let keys = [
+            "bgwkzqpndsioaxefclumthyvr",
+            "jumbo:noq",
+            "delastelle:1",
+            "x:7",
+        ];
+        let plaintext = "the quick brown fox jumps over the lazy dog";
+
+        for key in keys.iter() {
+            let cipher = BifidCipher::new(key).unwrap();
+
+            for len in 0..plaintext.len() {
+                let plaintext = &plaintext[..len];
+                let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+                assert_eq!(
+                    cipher.clean_input(plaintext),
+                    cipher.decrypt(&ciphertext).unwrap()
+                );
+            }
+        }
+    }
+}
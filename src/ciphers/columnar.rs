@@ -0,0 +1,201 @@
+use super::transposition::{clean, transpose, untranspose, Grid};
+use crate::keys::{ColumnarKey, DoubleColumnarKey};
+use crate::text::DigitPolicy;
+use crate::{Cipher, CipherError};
+
+pub struct ColumnarCipher {
+    key: ColumnarKey,
+    digits: DigitPolicy,
+}
+
+impl ColumnarCipher {
+    /// Creates a new columnar transposition given the key, a word whose length is the number of
+    /// columns of the grid and whose letters (ranked alphabetically) give the order in which the
+    /// columns are read.
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(ColumnarCipher::from_key(key.parse()?))
+    }
+
+    /// Creates a new columnar transposition with an already validated key.
+    pub fn from_key(key: ColumnarKey) -> Self {
+        ColumnarCipher {
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets what happens to the digits (either stripped, spelled out or transposed along with
+    /// the letters).
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    pub fn key(&self) -> &ColumnarKey {
+        &self.key
+    }
+}
+
+/// The order in which the characters of a text of len characters are read, given the key.
+fn reading_order(key: &ColumnarKey, len: usize) -> Vec<usize> {
+    Grid::new(len, key.columns()).by_columns(&key.order())
+}
+
+impl Cipher for ColumnarCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext).chars().collect::<Vec<char>>();
+        let order = reading_order(&self.key, clean_plaintext.len());
+
+        Ok(transpose(&clean_plaintext, &order))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext).chars().collect::<Vec<char>>();
+        let order = reading_order(&self.key, clean_ciphertext.len());
+
+        Ok(untranspose(&clean_ciphertext, &order))
+    }
+}
+
+pub struct DoubleColumnarCipher {
+    key: DoubleColumnarKey,
+    digits: DigitPolicy,
+}
+
+impl DoubleColumnarCipher {
+    /// Creates a new double transposition given the key, two words separated by a comma (one for
+    /// each of the columnar transpositions, applied one after the other).
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(DoubleColumnarCipher::from_key(key.parse()?))
+    }
+
+    /// Creates a new double transposition with an already validated key.
+    pub fn from_key(key: DoubleColumnarKey) -> Self {
+        DoubleColumnarCipher {
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets what happens to the digits (either stripped, spelled out or transposed along with
+    /// the letters).
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    pub fn key(&self) -> &DoubleColumnarKey {
+        &self.key
+    }
+}
+
+impl Cipher for DoubleColumnarCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext).chars().collect::<Vec<char>>();
+        let len = clean_plaintext.len();
+
+        let once = transpose(&clean_plaintext, &reading_order(self.key.first(), len));
+        let once = once.chars().collect::<Vec<char>>();
+
+        Ok(transpose(&once, &reading_order(self.key.second(), len)))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext).chars().collect::<Vec<char>>();
+        let len = clean_ciphertext.len();
+
+        // undo the transpositions in reverse order
+        let once = untranspose(&clean_ciphertext, &reading_order(self.key.second(), len));
+        let once = once.chars().collect::<Vec<char>>();
+
+        Ok(untranspose(&once, &reading_order(self.key.first(), len)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_pairs() {
+        // from https://en.wikipedia.org/wiki/Transposition_cipher
+        let cipher = ColumnarCipher::new("zebras").unwrap();
+        let plaintext = "We are discovered. Flee at once.";
+        let ciphertext = "EVLNACDTESEAROFODEECWIREE";
+
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(
+            cipher.clean_input(plaintext),
+            cipher.decrypt(ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_double_transposition() {
+        let plaintext = "We are discovered. Flee at once.";
+        let double = DoubleColumnarCipher::new("zebras,stripe").unwrap();
+
+        // same as transposing twice
+        let first = ColumnarCipher::new("zebras").unwrap();
+        let second = ColumnarCipher::new("stripe").unwrap();
+        let ciphertext = second.encrypt(&first.encrypt(plaintext).unwrap()).unwrap();
+
+        assert_eq!(ciphertext, double.encrypt(plaintext).unwrap());
+        assert_eq!(
+            double.clean_input(plaintext),
+            double.decrypt(&ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_correct() {
+        let plaintext = "Iamhurtverybadlyhelp";
+        let keys = [
+            "a",
+            "ba",
+            "zebras",
+            "aaaa",
+            "cryptography",
+            "longerthantheplaintext",
+        ];
+
+        for key in keys {
+            let cipher = ColumnarCipher::new(key).unwrap();
+            for len in 0..=plaintext.len() {
+                let text = &plaintext[..len];
+
+                assert_eq!(
+                    text.to_uppercase(),
+                    cipher.decrypt(&cipher.encrypt(text).unwrap()).unwrap()
+                );
+            }
+        }
+
+        let cipher = DoubleColumnarCipher::new("zebras,stripe").unwrap();
+        for len in 0..=plaintext.len() {
+            let text = &plaintext[..len];
+
+            assert_eq!(
+                text.to_uppercase(),
+                cipher.decrypt(&cipher.encrypt(text).unwrap()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = ColumnarCipher::new("ba").unwrap();
+        assert_eq!("C1AT", cipher.encrypt("act 1").unwrap());
+
+        let cipher = cipher.with_digits(DigitPolicy::Strip);
+        assert_eq!("CAT", cipher.encrypt("act 1").unwrap());
+    }
+}
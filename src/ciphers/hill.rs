@@ -0,0 +1,386 @@
+use super::utils::{clean, mod_inverse, pad};
+use crate::keys::HillKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+/// The Hill cipher: every block of n symbols, taken as a vector, is multiplied by the n×n key
+/// matrix (modulo the size of the alphabet), and brought back by multiplying by its inverse.
+///
+/// An incomplete last block is padded with the filler (X, unless stated otherwise).
+pub struct HillCipher {
+    key: HillKey,
+    alphabet: Alphabet,
+    /// The key matrix reduced modulo the size of the alphabet.
+    matrix: Vec<usize>,
+    inverse: Vec<usize>,
+    filler: char,
+    digits: DigitPolicy,
+}
+
+impl HillCipher {
+    /// Initializes a Hill cipher given the key (either the entries of the matrix or a keyword).
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        HillCipher::from_key(key.parse()?)
+    }
+
+    /// Initializes a Hill cipher with the given key, whose matrix still has to be checked against
+    /// the English alphabet.
+    pub fn from_key(key: HillKey) -> Result<Self, CipherError> {
+        HillCipher::with_alphabet(key, Alphabet::english())
+    }
+
+    /// Initializes a Hill cipher working on the given alphabet, modulo whose size the key matrix
+    /// must be invertible (i.e. its determinant must be coprime with the size).
+    pub fn with_alphabet(key: HillKey, alphabet: Alphabet) -> Result<Self, CipherError> {
+        let m = alphabet.len();
+        let matrix = key
+            .matrix()
+            .iter()
+            .map(|n| n.rem_euclid(m as i64) as usize)
+            .collect::<Vec<usize>>();
+
+        let (det, inverse) = reduce(&matrix, key.size(), m);
+        let inverse = inverse.ok_or_else(|| {
+            CipherError::InvalidKey(format!(
+                "the matrix of a Hill cipher must be invertible modulo the size of the alphabet ({}), but its determinant {} isn't coprime with it",
+                m, det
+            ))
+        })?;
+
+        Ok(HillCipher {
+            key,
+            alphabet,
+            matrix,
+            inverse,
+            filler: 'X',
+            digits: DigitPolicy::default(),
+        })
+    }
+
+    /// Sets the symbol used to pad the last block, which must be part of the alphabet.
+    pub fn with_filler(mut self, filler: char) -> Result<Self, CipherError> {
+        self.filler = self
+            .alphabet
+            .symbol_for(filler)
+            .ok_or(CipherError::InvalidSymbol(filler))?;
+
+        Ok(self)
+    }
+
+    /// Sets what happens to the digits.
+    ///
+    /// With `DigitPolicy::Combined` the digits are appended to the alphabet, so the matrix must
+    /// be invertible modulo the new size as well, while `DigitPolicy::SeparateRing` isn't
+    /// supported, since digits can't be mixed with the letters of their block.
+    pub fn with_digits(self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        let mut cipher = match digits {
+            DigitPolicy::Combined => {
+                let alphabet = self.alphabet.with_digits();
+
+                HillCipher::with_alphabet(self.key, alphabet)?.with_filler(self.filler)?
+            }
+            DigitPolicy::SeparateRing => {
+                return Err(CipherError::InvalidKey(
+                    "the Hill cipher can't encrypt the digits in a ring of their own".to_string(),
+                ))
+            }
+            _ => self,
+        };
+        cipher.digits = digits;
+
+        Ok(cipher)
+    }
+
+    pub fn key(&self) -> &HillKey {
+        &self.key
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The inverse of the key matrix modulo the size of the alphabet, row by row.
+    pub fn inverse(&self) -> &[usize] {
+        &self.inverse
+    }
+
+    /// Multiplies every block of symbols of the text by the matrix, leaving anything else (i.e.
+    /// the digits passed through) where it is.
+    fn transform(&self, text: &str, matrix: &[usize]) -> Result<String, CipherError> {
+        let n = self.key.size();
+        let m = self.alphabet.len();
+        let mut chars = text.chars().collect::<Vec<char>>();
+        let positions = (0..chars.len())
+            .filter(|pos| self.alphabet.contains(chars[*pos]))
+            .collect::<Vec<usize>>();
+
+        if !positions.len().is_multiple_of(n) {
+            return Err(CipherError::InvalidLength(format!(
+                "the Hill cipher works on blocks of {} symbols, got {} symbols",
+                n,
+                positions.len()
+            )));
+        }
+
+        for block in positions.chunks(n) {
+            let vector = block
+                .iter()
+                .map(|pos| self.alphabet.index_of(chars[*pos]).unwrap())
+                .collect::<Vec<usize>>();
+
+            for (row, pos) in block.iter().enumerate() {
+                let idx = (0..n)
+                    .map(|col| matrix[row * n + col] * vector[col])
+                    .sum::<usize>();
+
+                chars[*pos] = self.alphabet.symbol_at(idx % m);
+            }
+        }
+
+        Ok(chars.into_iter().collect())
+    }
+}
+
+impl Cipher for HillCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, TextMode::Clean, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+        let padded = pad(
+            &clean_plaintext,
+            &self.alphabet,
+            self.key.size(),
+            self.filler,
+        );
+
+        self.transform(&padded, &self.matrix)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+
+        self.transform(&clean_ciphertext, &self.inverse)
+    }
+}
+
+/// The inverse of an n×n matrix (row by row) modulo m, if its determinant is coprime with m.
+pub(crate) fn invert(matrix: &[usize], n: usize, m: usize) -> Option<Vec<usize>> {
+    reduce(matrix, n, m).1
+}
+
+/// The product of two n×n matrices (row by row) modulo m.
+pub(crate) fn multiply(a: &[usize], b: &[usize], n: usize, m: usize) -> Vec<usize> {
+    (0..n * n)
+        .map(|cell| {
+            let (row, col) = (cell / n, cell % n);
+
+            (0..n)
+                .map(|k| a[row * n + k] * b[k * n + col])
+                .sum::<usize>()
+                % m
+        })
+        .collect()
+}
+
+/// Computes the determinant of an n×n matrix modulo m and, if it is coprime with m, the inverse
+/// of the matrix.
+///
+/// Since m needn't be prime, there's no division to rely on: the matrix (with the identity on its
+/// side) is brought to triangular form by running Euclid's algorithm down each column, which only
+/// adds multiples of rows to each other, and the pivots left on the diagonal are all invertible
+/// exactly when the determinant is.
+fn reduce(matrix: &[usize], n: usize, m: usize) -> (usize, Option<Vec<usize>>) {
+    let mut rows = (0..n)
+        .map(|row| {
+            let mut augmented = matrix[row * n..(row + 1) * n].to_vec();
+            augmented.extend((0..n).map(|col| usize::from(row == col)));
+            augmented
+        })
+        .collect::<Vec<Vec<usize>>>();
+    // adds -q times row `from` to row `to`
+    let subtract = |rows: &mut [Vec<usize>], to: usize, from: usize, q: usize| {
+        let from = rows[from].clone();
+
+        for (entry, x) in rows[to].iter_mut().zip(from) {
+            *entry = (*entry + m - q * x % m) % m;
+        }
+    };
+    let mut negated = false;
+
+    for col in 0..n {
+        loop {
+            let pivot = (col..n)
+                .filter(|row| rows[*row][col] != 0)
+                .min_by_key(|row| rows[*row][col]);
+            let pivot = match pivot {
+                Some(pivot) => pivot,
+                None => return (0, None),
+            };
+            if pivot != col {
+                rows.swap(pivot, col);
+                negated = !negated;
+            }
+
+            let mut done = true;
+            for row in col + 1..n {
+                let q = rows[row][col] / rows[col][col];
+                subtract(&mut rows, row, col, q);
+                done &= rows[row][col] == 0;
+            }
+            if done {
+                break;
+            }
+        }
+    }
+
+    let product = (0..n).fold(1, |det, i| det * rows[i][i] % m);
+    let det = if negated { (m - product) % m } else { product };
+
+    if mod_inverse(det as i64, m).is_none() {
+        return (det, None);
+    }
+
+    // every pivot is invertible now, so the rest is plain Gauss-Jordan
+    for col in (0..n).rev() {
+        let inverse = mod_inverse(rows[col][col] as i64, m).unwrap();
+        for entry in rows[col].iter_mut() {
+            *entry = *entry * inverse % m;
+        }
+        for row in 0..col {
+            let q = rows[row][col];
+            subtract(&mut rows, row, col, q);
+        }
+    }
+
+    let inverse = rows.into_iter().flat_map(|row| row[n..].to_vec()).collect();
+
+    (det, Some(inverse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_pairs() {
+        // from https://en.wikipedia.org/wiki/Hill_cipher
+        let cipher = HillCipher::new("GYBNQKURP").unwrap();
+        assert_eq!("POH", cipher.encrypt("act").unwrap());
+        assert_eq!("FIN", cipher.encrypt("cat").unwrap());
+        assert_eq!("ACT", cipher.decrypt("POH").unwrap());
+        assert_eq!(&[8, 5, 10, 21, 8, 21, 21, 12, 8], cipher.inverse());
+
+        let cipher = HillCipher::new("hill").unwrap();
+        assert_eq!("APADJTFTWLFJ", cipher.encrypt("Short example").unwrap());
+        assert_eq!("SHORTEXAMPLE", cipher.decrypt("APADJTFTWLFJ").unwrap());
+    }
+
+    #[test]
+    fn test_matrices() {
+        let matrix = [3, 3, 2, 5];
+        assert_eq!(9, reduce(&matrix, 2, 26).0);
+        let inverse = invert(&matrix, 2, 26).unwrap();
+        assert_eq!(vec![15, 17, 20, 9], inverse);
+        assert_eq!(vec![1, 0, 0, 1], multiply(&matrix, &inverse, 2, 26));
+
+        // 2 and 13 are both zero divisors, but the determinant (-1) isn't
+        let matrix = [2, 13, 1, 6];
+        assert_eq!(25, reduce(&matrix, 2, 26).0);
+        let inverse = invert(&matrix, 2, 26).unwrap();
+        assert_eq!(vec![1, 0, 0, 1], multiply(&matrix, &inverse, 2, 26));
+
+        assert_eq!(None, invert(&[2, 4, 1, 3], 2, 26));
+        assert_eq!(None, invert(&[1, 2, 2, 4], 2, 26));
+        assert_eq!(0, reduce(&[1, 2, 2, 4], 2, 26).0);
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        // the determinant is -2
+        assert!(matches!(
+            HillCipher::new("0,1,2,3"),
+            Err(CipherError::InvalidKey(_))
+        ));
+        // and here it's 13
+        assert!(HillCipher::new("1,0,0,13").is_err());
+
+        // 3,3,2,5 has determinant 9, which is coprime with 26 but not with 36
+        let key = "3,3,2,5".parse::<HillKey>().unwrap();
+        assert!(HillCipher::with_alphabet(key.clone(), Alphabet::english()).is_ok());
+        assert!(HillCipher::with_alphabet(key, Alphabet::alphanumeric()).is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        let cipher = HillCipher::new("hill").unwrap();
+
+        assert_eq!(
+            cipher.encrypt("oddx").unwrap(),
+            cipher.encrypt("odd").unwrap()
+        );
+        assert_eq!(
+            cipher.encrypt("oddz").unwrap(),
+            cipher.with_filler('z').unwrap().encrypt("odd").unwrap()
+        );
+
+        let cipher = HillCipher::new("hill").unwrap();
+        assert!(matches!(
+            cipher.decrypt("APA"),
+            Err(CipherError::InvalidLength(_))
+        ));
+        assert!(HillCipher::new("hill").unwrap().with_filler('1').is_err());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = HillCipher::new("hill").unwrap();
+        // digits stay where they are, and blocks are made across them
+        let ciphertext = cipher.encrypt("sh0rt").unwrap();
+        assert!(ciphertext.starts_with("AP0"));
+        assert_eq!("SH0RT", cipher.decrypt(&ciphertext).unwrap());
+
+        let cipher = HillCipher::new("hill")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .unwrap();
+        assert_eq!(36, cipher.alphabet().len());
+        assert_eq!(
+            "AGENT007",
+            cipher
+                .decrypt(&cipher.encrypt("Agent 007").unwrap())
+                .unwrap()
+        );
+
+        assert!(HillCipher::new("hill")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let keys = [
+            "GYBNQKURP",
+            "hill",
+            "3,3,2,5",
+            "1,2,3,4,0,3,5,7,0,0,5,9,0,0,0,7",
+        ];
+        let plaintext = String::from("linearalgebra");
+
+        for key in keys.iter() {
+            let cipher = HillCipher::new(key).unwrap();
+            let n = cipher.key().size();
+            let padded = pad(&cipher.clean_input(&plaintext), cipher.alphabet(), n, 'X');
+
+            assert_eq!(
+                padded,
+                cipher
+                    .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                    .unwrap()
+            );
+        }
+    }
+}
@@ -1,9 +1,38 @@
-// public, accessible by the whole crate
+// public, part of the crate's API
+pub mod adfgvx;
+pub mod affine;
+pub mod bifid;
+pub mod columnar;
+pub mod hill;
 pub mod monoalphabetic;
+pub mod playfair;
+pub mod polyalphabetic;
+pub mod polybius;
+pub mod quagmire;
+pub mod route;
 pub mod scytale;
 pub mod shift;
 pub mod solitaire;
+pub mod transposition;
+pub mod trifid;
 pub mod vigenere;
 
+pub use adfgvx::AdfgvxCipher;
+pub use affine::AffineCipher;
+pub use bifid::BifidCipher;
+pub use columnar::{ColumnarCipher, DoubleColumnarCipher};
+pub use hill::HillCipher;
+pub use monoalphabetic::MonoalphaCipher;
+pub use playfair::PlayfairCipher;
+pub use polyalphabetic::PolyalphabeticCipher;
+pub use polybius::PolybiusSquare;
+pub use quagmire::QuagmireCipher;
+pub use route::RouteCipher;
+pub use scytale::Scytale;
+pub use shift::ShiftCipher;
+pub use solitaire::{SolitaireCipher, SolitaireKeystream};
+pub use trifid::TrifidCipher;
+pub use vigenere::VigenereCipher;
+
 // accessible only by modules within ciphers/
 mod utils;
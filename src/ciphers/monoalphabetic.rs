@@ -1,113 +1,189 @@
-use crate::Cipher;
-use std::collections::{HashMap, HashSet};
+use super::utils::{clean, substitute, Ring, DIGITS};
+use crate::keys::{KeywordAlphabet, SubstitutionAlphabet};
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
 
 pub struct MonoalphaCipher {
-    key: String,
+    key: SubstitutionAlphabet,
+    alphabet: Alphabet,
+    map: Vec<usize>,
+    inverse_map: Vec<usize>,
+    digit_map: Vec<usize>,
+    inverse_digit_map: Vec<usize>,
+    mode: TextMode,
+    digits: DigitPolicy,
 }
 
 impl MonoalphaCipher {
     /// Initializes a monoalphabetic cipher with the given alphabet (if valid).
-    pub fn new(key: &str) -> Self {
-        if MonoalphaCipher::is_alphabet(key) {
-            MonoalphaCipher {
-                key: key.to_string(),
-            }
-        } else {
-            panic!("Invalid key: the key used in a monoalphabetic cipher must be a permutation of the English alphabet.");
-        }
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(MonoalphaCipher::from_key(key.parse()?))
     }
 
-    /// Maps digits to themselves (to simplify their encoding in the
-    /// encryption/decryption procedure)
-    fn self_map_digits(map: &mut HashMap<char, char>) {
-        for d in 0..=9 {
-            let ch = std::char::from_digit(d, 10).unwrap();
-            map.insert(ch, ch);
+    /// Initializes a monoalphabetic cipher with an already validated alphabet.
+    pub fn from_key(key: SubstitutionAlphabet) -> Self {
+        let alphabet = Alphabet::english();
+        let map = MonoalphaCipher::build_map(&key, &alphabet);
+        let inverse_map = MonoalphaCipher::build_inverse_map(&map);
+        let digit_map = MonoalphaCipher::build_digit_map(&map);
+        let inverse_digit_map = MonoalphaCipher::build_inverse_map(&digit_map);
+
+        MonoalphaCipher {
+            key,
+            alphabet,
+            map,
+            inverse_map,
+            digit_map,
+            inverse_digit_map,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
         }
     }
 
-    /// Builds the HashMap that holds the correspondence between the key alphabet
-    /// and the original alphabet (only English in our case), used for encryption.
-    fn build_map(key: &str) -> HashMap<char, char> {
-        let keys = ('A'..='Z').collect::<Vec<char>>();
-        let values = key.to_uppercase().chars().collect::<Vec<char>>();
-        let mut map: HashMap<char, char> = HashMap::new();
+    /// Initializes a monoalphabetic cipher whose alphabet is derived from a keyword (see
+    /// KeywordAlphabet), e.g. "kangaroo" or "kangaroo:last".
+    pub fn from_keyword(key: &str) -> Result<Self, CipherError> {
+        let key = key.parse::<KeywordAlphabet>()?;
+
+        Ok(MonoalphaCipher::from_key(
+            key.substitution(&Alphabet::english()),
+        ))
+    }
+
+    /// Initializes a monoalphabetic cipher working on the given alphabet, of which the key must
+    /// be a permutation.
+    pub fn with_alphabet(
+        key: SubstitutionAlphabet,
+        alphabet: Alphabet,
+    ) -> Result<Self, CipherError> {
+        let key = SubstitutionAlphabet::parse_for(key.letters(), &alphabet)?;
+        let map = MonoalphaCipher::build_map(&key, &alphabet);
+        let inverse_map = MonoalphaCipher::build_inverse_map(&map);
+        let digit_map = MonoalphaCipher::build_digit_map(&map);
+        let inverse_digit_map = MonoalphaCipher::build_inverse_map(&digit_map);
+
+        Ok(MonoalphaCipher {
+            key,
+            alphabet,
+            map,
+            inverse_map,
+            digit_map,
+            inverse_digit_map,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        })
+    }
+
+    /// Sets how the characters that are not part of the alphabet are treated.
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets what happens to the digits.
+    ///
+    /// With `DigitPolicy::Combined` the digits are appended to the alphabet, so the key must be a
+    /// permutation of the combined alphabet, while with `DigitPolicy::SeparateRing` the digits
+    /// are permuted according to the order of the first ten symbols of the key.
+    pub fn with_digits(self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        let mut cipher = if digits == DigitPolicy::Combined {
+            let alphabet = self.alphabet.with_digits();
+
+            MonoalphaCipher::with_alphabet(self.key, alphabet)?.with_mode(self.mode)
+        } else {
+            self
+        };
+        cipher.digits = digits;
+
+        Ok(cipher)
+    }
 
-        for (idx, k) in keys.iter().enumerate() {
-            map.insert(*k, values[idx]);
+    pub fn key(&self) -> &SubstitutionAlphabet {
+        &self.key
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Builds the table that holds the correspondence between the original alphabet and the key
+    /// alphabet (by index), used for encryption.
+    fn build_map(key: &SubstitutionAlphabet, alphabet: &Alphabet) -> Vec<usize> {
+        key.letters()
+            .chars()
+            .map(|ch| alphabet.index_of(ch).unwrap())
+            .collect()
+    }
+
+    /// Builds the permutation of the digits used by the separate digit ring: the first ten
+    /// symbols of the key, ranked by their position in the alphabet (so a key starting with
+    /// "DBC..." moves 0 onto 2, 1 onto 0 and 2 onto 1, and so on).
+    ///
+    /// Keys shorter than ten symbols are repeated, with ties broken by position.
+    fn build_digit_map(map: &[usize]) -> Vec<usize> {
+        let mut order = (0..DIGITS).collect::<Vec<usize>>();
+        order.sort_by_key(|i| (map[i % map.len()], *i));
+
+        let mut digit_map = vec![0; DIGITS];
+        for (rank, i) in order.into_iter().enumerate() {
+            digit_map[i] = rank;
         }
-        MonoalphaCipher::self_map_digits(&mut map);
 
-        map
+        digit_map
     }
 
-    /// Builds the HashMap that holds the inverse correspondence between the key alphabet
-    /// and the original alphabet (only English in our case), used for decryption.
-    fn build_inverse_map(key: &str) -> HashMap<char, char> {
-        let keys = key.to_uppercase().chars().collect::<Vec<char>>();
-        let values = ('A'..='Z').collect::<Vec<char>>();
-        let mut inverse_map: HashMap<char, char> = HashMap::new();
+    /// Builds the table that holds the inverse correspondence between the key alphabet and the
+    /// original alphabet (by index), used for decryption.
+    fn build_inverse_map(map: &[usize]) -> Vec<usize> {
+        let mut inverse_map = vec![0; map.len()];
 
-        for (idx, k) in keys.iter().enumerate() {
-            inverse_map.insert(*k, values[idx]);
+        for (idx, mapped) in map.iter().enumerate() {
+            inverse_map[*mapped] = idx;
         }
-        MonoalphaCipher::self_map_digits(&mut inverse_map);
 
         inverse_map
     }
+
     /// Checks whether a given string constitutes a valid permutation of an alphabet (in our case just
     /// the English alphabet is available)
-    fn is_alphabet(s: &str) -> bool {
-        let available_letters: HashSet<char> = ('A'..='Z').collect();
-
-        let mut s_letter_count: HashMap<char, u32> = HashMap::new();
-        // from https://stackoverflow.com/questions/64178272/what-is-the-idiomatic-rust-way-to-build-a-hashmap-of-character-counts
-        let s_vec = s.to_uppercase().chars().collect::<Vec<char>>();
-        for ch in s_vec {
-            *s_letter_count.entry(ch).or_insert(0) += 1;
-        }
-
-        // this allows for duplicates
-        let all_keys_are_valid = s_letter_count
-            .keys()
-            .collect::<Vec<_>>()
-            .iter()
-            .all(|ch| available_letters.contains(&ch));
-        // this returns false if there are duplicates
-        let all_letters_appear_once = s_letter_count
-            .values()
-            .collect::<Vec<_>>()
-            .iter()
-            .all(|&count| *count == 1);
-        let has_26_letters = s.len() == 26;
-
-        if has_26_letters && all_keys_are_valid && all_letters_appear_once {
-            true
-        } else {
-            false
-        }
+    pub fn is_alphabet(s: &str) -> bool {
+        s.parse::<SubstitutionAlphabet>().is_ok()
     }
 }
 
 impl Cipher for MonoalphaCipher {
-    fn encrypt(&self, plaintext: &str) -> String {
-        let clean_plaintext = <MonoalphaCipher as Cipher>::clean_input(plaintext);
-        let map = MonoalphaCipher::build_map(&self.key);
-
-        clean_plaintext
-            .chars()
-            .map(|c| *map.get(&c).unwrap())
-            .collect::<String>()
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
     }
 
-    fn decrypt(&self, ciphertext: &str) -> String {
-        let clean_ciphertext = <MonoalphaCipher as Cipher>::clean_input(ciphertext);
-        let inverse_map = MonoalphaCipher::build_inverse_map(&self.key);
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+
+        substitute(
+            &clean_plaintext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, _, ring| match ring {
+                Ring::Symbols => self.map[idx],
+                Ring::Digits => self.digit_map[idx],
+            },
+        )
+    }
 
-        clean_ciphertext
-            .chars()
-            .map(|c| *inverse_map.get(&c).unwrap())
-            .collect::<String>()
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+
+        substitute(
+            &clean_ciphertext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, _, ring| match ring {
+                Ring::Symbols => self.inverse_map[idx],
+                Ring::Digits => self.inverse_digit_map[idx],
+            },
+        )
     }
 }
 
@@ -117,19 +193,21 @@ mod tests {
     use rand::seq::SliceRandom;
 
     #[test]
-    #[should_panic]
     fn test_empty_key() {
         let cipher = MonoalphaCipher::new("");
 
-        cipher.encrypt("Good Kid maad CITY");
+        assert!(matches!(cipher, Err(CipherError::InvalidKey(_))));
     }
 
     #[test]
     fn test_default_alphabet() {
-        let cipher = MonoalphaCipher::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+        let cipher = MonoalphaCipher::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap();
         let plaintext = String::from("Hello");
 
-        assert_eq!(plaintext.to_uppercase(), cipher.encrypt(&plaintext));
+        assert_eq!(
+            plaintext.to_uppercase(),
+            cipher.encrypt(&plaintext).unwrap()
+        );
     }
 
     #[test]
@@ -157,39 +235,121 @@ mod tests {
 
     #[test]
     fn test_numeric_input() {
-        let cipher = MonoalphaCipher::new("QHJWOTYRXBKMPIAZEVNULSGDCF");
+        let cipher = MonoalphaCipher::new("QHJWOTYRXBKMPIAZEVNULSGDCF").unwrap();
         let plaintext = "9872465";
 
-        assert_eq!(plaintext.to_string(), cipher.encrypt(plaintext));
+        assert_eq!(plaintext.to_string(), cipher.encrypt(plaintext).unwrap());
     }
 
     #[test]
     fn test_known_pairs() {
         // from https://cryptii.com
-        let cipher = MonoalphaCipher::new("zyxwvutsrqponmlkjihgfedcba");
+        let cipher = MonoalphaCipher::new("zyxwvutsrqponmlkjihgfedcba").unwrap();
         let plaintext = "attackatdawn";
         let ciphertext = String::from("zggzxpzgwzdm");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <MonoalphaCipher as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
 
         // same source as above
-        let cipher = MonoalphaCipher::new("myxnvestrqpowzlkjihgfudabc");
+        let cipher = MonoalphaCipher::new("myxnvestrqpowzlkjihgfudabc").unwrap();
         let plaintext = "firstman";
         let ciphertext = String::from("erihgwmz");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <MonoalphaCipher as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
     }
 
+    #[test]
+    fn test_keyword() {
+        let cipher = MonoalphaCipher::from_keyword("kangaroo").unwrap();
+        assert_eq!("KANGROBCDEFHIJLMPQSTUVWXYZ", cipher.key().letters());
+        assert_eq!("CRHHL", cipher.encrypt("Hello").unwrap());
+        assert_eq!("HELLO", cipher.decrypt("CRHHL").unwrap());
+
+        let cipher = MonoalphaCipher::from_keyword("kangaroo:columnar").unwrap();
+        assert_eq!("ACJTZGEMVKBISYNDLUOHQXRFPW", cipher.key().letters());
+
+        assert!(MonoalphaCipher::from_keyword("kangaroo:sideways").is_err());
+    }
+
+    #[test]
+    fn test_other_alphabets() {
+        let latin = Alphabet::latin();
+        let key = SubstitutionAlphabet::parse_for("zyxutsrqponmlkihgfedcba", &latin).unwrap();
+        let cipher = MonoalphaCipher::with_alphabet(key, latin).unwrap();
+
+        // J is folded onto I, V onto U
+        assert_eq!("PDNPDF", cipher.encrypt("Julius").unwrap());
+        assert_eq!("IULIUS", cipher.decrypt("PDNPDF").unwrap());
+
+        // a valid English key isn't necessarily a valid Latin one
+        let key = "zyxwvutsrqponmlkjihgfedcba".parse().unwrap();
+        assert!(MonoalphaCipher::with_alphabet(key, Alphabet::latin()).is_err());
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let cipher = MonoalphaCipher::new("zyxwvutsrqponmlkjihgfedcba")
+            .unwrap()
+            .with_mode(TextMode::preserve_format());
+        let plaintext = "Attack at dawn, 5 o'clock!";
+        let ciphertext = "Zggzxp zg wzdm, 5 l'xolxp!";
+
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(plaintext, cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        // the first ten symbols of the key are in reverse order, and so are the digits
+        let cipher = MonoalphaCipher::new("zyxwvutsrqponmlkjihgfedcba")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .unwrap();
+        assert_eq!("ZGGZXP7975", cipher.encrypt("attack 2024").unwrap());
+        assert_eq!("ATTACK2024", cipher.decrypt("ZGGZXP7975").unwrap());
+
+        let cipher = MonoalphaCipher::new("QHJWOTYRXBKMPIAZEVNULSGDCF")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .unwrap();
+        let plaintext = "0123456789";
+        let ciphertext = cipher.encrypt(plaintext).unwrap();
+        assert_ne!(plaintext, ciphertext);
+        assert_eq!(plaintext, cipher.decrypt(&ciphertext).unwrap());
+
+        // the key must cover the digits as well
+        let cipher = MonoalphaCipher::new("zyxwvutsrqponmlkjihgfedcba").unwrap();
+        assert!(cipher.with_digits(DigitPolicy::Combined).is_err());
+
+        let key = SubstitutionAlphabet::parse_for(
+            "9876543210zyxwvutsrqponmlkjihgfedcba",
+            &Alphabet::alphanumeric(),
+        )
+        .unwrap();
+        let cipher = MonoalphaCipher::with_alphabet(key, Alphabet::alphanumeric())
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .unwrap();
+        assert_eq!("98J", cipher.encrypt("ab0").unwrap());
+        assert_eq!("AB0", cipher.decrypt("98J").unwrap());
+    }
+
     #[test]
     #[ignore]
     fn test_correct() {
@@ -204,10 +364,12 @@ mod tests {
         for _ in 0..1000 {
             let choice = alphabets.choose(&mut rand::thread_rng()).unwrap();
 
-            let cipher = MonoalphaCipher::new(*choice);
+            let cipher = MonoalphaCipher::new(choice).unwrap();
             assert_eq!(
                 plaintext.to_uppercase(),
-                cipher.decrypt(&cipher.encrypt(&plaintext))
+                cipher
+                    .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                    .unwrap()
             );
         }
     }
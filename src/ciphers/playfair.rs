@@ -0,0 +1,335 @@
+use super::polybius::PolybiusSquare;
+use super::utils::clean;
+use crate::keys::PlayfairKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+/// The Playfair cipher, which encrypts pairs of letters using a square filled with a keyword
+/// followed by the rest of the alphabet.
+///
+/// Doubled letters within a pair are split by the filler letter, which also pads a text of odd
+/// length.
+pub struct PlayfairCipher {
+    key: PlayfairKey,
+    square: PolybiusSquare,
+    digits: DigitPolicy,
+    remove_fillers: bool,
+}
+
+impl PlayfairCipher {
+    /// Initializes a Playfair cipher given the keyword (I and J are merged and the filler is X).
+    pub fn new(keyword: &str) -> Result<Self, CipherError> {
+        Ok(PlayfairCipher::from_key(keyword.parse()?))
+    }
+
+    /// Initializes a Playfair cipher with an already validated key.
+    pub fn from_key(key: PlayfairKey) -> Self {
+        PlayfairCipher {
+            square: PolybiusSquare::five(key.keyword(), key.letters()),
+            key,
+            digits: DigitPolicy::default(),
+            remove_fillers: false,
+        }
+    }
+
+    /// Sets whether decryption removes the letters that look like fillers: one between two equal
+    /// letters, or one at the very end.
+    ///
+    /// This is a guess, so every now and then a genuine letter goes as well (e.g. the X of "AX").
+    pub fn with_filler_removal(mut self, remove_fillers: bool) -> Self {
+        self.remove_fillers = remove_fillers;
+        self
+    }
+
+    /// Sets what happens to the digits.
+    ///
+    /// With `DigitPolicy::Combined` the square becomes a 6×6 one holding all 26 letters and the 10
+    /// digits (so nothing gets merged), while `DigitPolicy::SeparateRing` isn't supported, since
+    /// digits can't be paired up among themselves.
+    pub fn with_digits(self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        let mut cipher = match digits {
+            DigitPolicy::Combined => PlayfairCipher {
+                square: PolybiusSquare::six(self.key.keyword()),
+                ..self
+            },
+            DigitPolicy::SeparateRing => {
+                return Err(CipherError::InvalidKey(
+                    "the Playfair cipher can't encrypt the digits in a ring of their own"
+                        .to_string(),
+                ))
+            }
+            _ => self,
+        };
+        cipher.digits = digits;
+
+        Ok(cipher)
+    }
+
+    pub fn key(&self) -> &PlayfairKey {
+        &self.key
+    }
+
+    pub fn square(&self) -> &PolybiusSquare {
+        &self.square
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        self.square.alphabet()
+    }
+
+    /// The letter that splits a doubled ch: the filler, unless ch is the filler itself, in which
+    /// case it's the symbol following it in the alphabet.
+    fn filler_for(&self, ch: char) -> char {
+        let filler = self.key.filler();
+
+        if ch == filler {
+            let idx = self.alphabet().index_of(filler).unwrap();
+            self.alphabet().symbol_at(idx + 1)
+        } else {
+            filler
+        }
+    }
+
+    /// Splits the doubled letters within a pair and pads the text to an even number of letters,
+    /// leaving anything else (i.e. the digits passed through) where it is.
+    fn split_pairs(&self, text: &str) -> String {
+        let mut split = String::with_capacity(text.len() + text.len() / 2);
+        let mut open = None;
+
+        for ch in text.chars() {
+            if !self.alphabet().contains(ch) {
+                split.push(ch);
+                continue;
+            }
+
+            match open {
+                Some(first) if first == ch => split.push(self.filler_for(ch)),
+                Some(_) => {
+                    split.push(ch);
+                    open = None;
+                    continue;
+                }
+                None => (),
+            }
+            split.push(ch);
+            open = Some(ch);
+        }
+        if let Some(last) = open {
+            split.push(self.filler_for(last));
+        }
+
+        split
+    }
+
+    /// Replaces every pair of letters of the text by the one found moving by step along the rows
+    /// or the columns of the square (or by swapping the corners of the rectangle they lie on).
+    fn transform(&self, text: &str, step: usize) -> Result<String, CipherError> {
+        let mut chars = text.chars().collect::<Vec<char>>();
+        let positions = (0..chars.len())
+            .filter(|pos| self.alphabet().contains(chars[*pos]))
+            .collect::<Vec<usize>>();
+
+        if !positions.len().is_multiple_of(2) {
+            return Err(CipherError::InvalidLength(format!(
+                "the Playfair cipher works on pairs of letters, got {} letters",
+                positions.len()
+            )));
+        }
+
+        for pair in positions.chunks(2) {
+            let (r1, c1) = self.square.coordinates(chars[pair[0]]).unwrap();
+            let (r2, c2) = self.square.coordinates(chars[pair[1]]).unwrap();
+
+            let ((r1, c1), (r2, c2)) = if r1 == r2 {
+                ((r1, c1 + step), (r2, c2 + step))
+            } else if c1 == c2 {
+                ((r1 + step, c1), (r2 + step, c2))
+            } else {
+                ((r1, c2), (r2, c1))
+            };
+
+            chars[pair[0]] = self.square.symbol_at(r1, c1);
+            chars[pair[1]] = self.square.symbol_at(r2, c2);
+        }
+
+        Ok(chars.into_iter().collect())
+    }
+
+    /// Removes the fillers split_pairs would have added.
+    fn remove_fillers(&self, text: &str) -> String {
+        let chars = text.chars().collect::<Vec<char>>();
+        let letters = (0..chars.len())
+            .filter(|pos| self.alphabet().contains(chars[*pos]))
+            .collect::<Vec<usize>>();
+        let mut fillers = Vec::new();
+
+        // fillers are always the second letter of a pair
+        for i in (1..letters.len()).step_by(2) {
+            let (prev, ch) = (chars[letters[i - 1]], chars[letters[i]]);
+            let is_last = i == letters.len() - 1;
+
+            if ch == self.filler_for(prev) && (is_last || chars[letters[i + 1]] == prev) {
+                fillers.push(letters[i]);
+            }
+        }
+
+        chars
+            .iter()
+            .enumerate()
+            .filter(|(pos, _)| !fillers.contains(pos))
+            .map(|(_, ch)| ch)
+            .collect()
+    }
+}
+
+impl Cipher for PlayfairCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.alphabet(), TextMode::Clean, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+
+        self.transform(&self.split_pairs(&clean_plaintext), 1)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+        let decrypted = self.transform(&clean_ciphertext, self.square.size() - 1)?;
+
+        if self.remove_fillers {
+            Ok(self.remove_fillers(&decrypted))
+        } else {
+            Ok(decrypted)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let cipher = PlayfairCipher::new("playfair example").unwrap();
+        assert_eq!(
+            "PLAYFIREXMBCDGHKNOQSTUVWZ",
+            cipher.square().symbols().iter().collect::<String>()
+        );
+
+        let cipher = PlayfairCipher::new("jumbo:noq").unwrap();
+        assert_eq!(
+            "JUMBOACDEFGHIKLNPRSTVWXYZ",
+            cipher.square().symbols().iter().collect::<String>()
+        );
+    }
+
+    #[test]
+    fn test_known_pairs() {
+        // from https://en.wikipedia.org/wiki/Playfair_cipher
+        let cipher = PlayfairCipher::new("playfair example").unwrap();
+        let plaintext = "Hide the gold in the tree stump";
+        let ciphertext = "BMODZBXDNABEKUDMUIXMMOUVIF";
+
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(
+            "HIDETHEGOLDINTHETREXESTUMP",
+            cipher.decrypt(ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fillers() {
+        let cipher = PlayfairCipher::new("monarchy").unwrap();
+        assert_eq!("BALXLOON", cipher.split_pairs("BALLOON"));
+        // a doubled filler is split by the letter following it
+        assert_eq!("XYXY", cipher.split_pairs("XX"));
+
+        let cipher = PlayfairCipher::new("monarchy:q").unwrap();
+        assert_eq!("BALQLOON", cipher.split_pairs("BALLOON"));
+
+        // pairs are only checked for doubled letters within them
+        assert_eq!("ABBA", cipher.split_pairs("ABBA"));
+    }
+
+    #[test]
+    fn test_filler_removal() {
+        let cipher = PlayfairCipher::new("monarchy")
+            .unwrap()
+            .with_filler_removal(true);
+
+        for plaintext in ["balloon", "attack", "tree", "axxe", "quiz"] {
+            let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+            assert_eq!(
+                plaintext.to_uppercase(),
+                cipher.decrypt(&ciphertext).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_omitted_q() {
+        let cipher = PlayfairCipher::new("playfair example:noq").unwrap();
+
+        // Q is dropped, while J gets a cell of its own
+        assert_eq!("UIZJ", cipher.clean_input("Quiz J"));
+        assert_eq!(
+            "UIZJ",
+            cipher.decrypt(&cipher.encrypt("quiz j").unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_odd_ciphertext() {
+        let cipher = PlayfairCipher::new("playfair example").unwrap();
+
+        assert!(matches!(
+            cipher.decrypt("BMO"),
+            Err(CipherError::InvalidLength(_))
+        ));
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = PlayfairCipher::new("playfair example").unwrap();
+        // digits stay where they are, and letters are paired across them
+        assert_eq!("BM1OD", cipher.encrypt("hi1de").unwrap());
+        assert_eq!("HI1DE", cipher.decrypt("BM1OD").unwrap());
+
+        let cipher = PlayfairCipher::new("playfair example")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .unwrap();
+        assert_eq!(6, cipher.square().size());
+        assert_eq!(
+            "AGENT007",
+            cipher
+                .decrypt(&cipher.encrypt("Agent 007").unwrap())
+                .unwrap()
+        );
+
+        assert!(PlayfairCipher::new("playfair example")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let keywords = ["playfair example", "monarchy", "charles", "jumbo:noq:z"];
+        let plaintext = String::from("playfairx");
+
+        for keyword in keywords {
+            let cipher = PlayfairCipher::new(keyword).unwrap();
+
+            for len in 1..plaintext.len() {
+                let plaintext = &plaintext[..len];
+                let decrypted = cipher.decrypt(&cipher.encrypt(plaintext).unwrap()).unwrap();
+
+                assert!(decrypted.starts_with(&cipher.clean_input(plaintext)));
+            }
+        }
+    }
+}
@@ -0,0 +1,544 @@
+/// The Vigenère family: ciphers that encrypt every symbol of the text with a symbol of a key
+/// stream, differing only in the table used to combine the two (the tableau) and in where the key
+/// stream comes from.
+use super::utils::{clean, substitute, Ring, DIGITS};
+use crate::keys::{GronsfeldKey, VigenereKeyword};
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+/// How a symbol of the text and a symbol of the key are combined (given their indexes p and k, in
+/// a ring of size m).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tableau {
+    /// The tabula recta, p + k.
+    Vigenere,
+    /// k - p, which is its own inverse.
+    Beaufort,
+    /// p - k, i.e. Vigenère decryption.
+    VariantBeaufort,
+    /// Della Porta's m/2 reciprocal alphabets, one for every pair of key symbols, each swapping
+    /// the first half of the alphabet with the second one.
+    Porta,
+}
+
+impl Tableau {
+    /// Encrypts the symbol at index p with the key symbol at index k (both below m).
+    pub fn encrypt(&self, p: usize, k: usize, m: usize) -> usize {
+        match self {
+            Tableau::Vigenere => (p + k) % m,
+            Tableau::Beaufort => (k + m - p) % m,
+            Tableau::VariantBeaufort => (p + m - k) % m,
+            Tableau::Porta => {
+                let (half, shift) = (m / 2, k / 2);
+
+                if p < half {
+                    half + (p + shift) % half
+                } else {
+                    (p - half + half - shift) % half
+                }
+            }
+        }
+    }
+
+    /// Decrypts the symbol at index c with the key symbol at index k (both below m).
+    pub fn decrypt(&self, c: usize, k: usize, m: usize) -> usize {
+        match self {
+            Tableau::Vigenere => Tableau::VariantBeaufort.encrypt(c, k, m),
+            Tableau::VariantBeaufort => Tableau::Vigenere.encrypt(c, k, m),
+            Tableau::Beaufort | Tableau::Porta => self.encrypt(c, k, m),
+        }
+    }
+
+    /// The name of the cipher using the tableau with a repeating keyword.
+    fn name(&self) -> &'static str {
+        match self {
+            Tableau::Vigenere => "Vigenere",
+            Tableau::Beaufort => "Beaufort",
+            Tableau::VariantBeaufort => "variant Beaufort",
+            Tableau::Porta => "Porta",
+        }
+    }
+
+    /// Fails if the tableau can't be built for an alphabet of m symbols.
+    fn check(&self, m: usize) -> Result<(), CipherError> {
+        if *self == Tableau::Porta && !m.is_multiple_of(2) {
+            return Err(CipherError::InvalidKey(format!(
+                "the Porta cipher splits the alphabet in two halves, so it must have an even number of symbols, got {}",
+                m
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Where the key symbols come from, one for every symbol of the text (as indexes in the alphabet).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Keystream {
+    /// A keyword repeated over and over.
+    Repeating(Vec<usize>),
+    /// A primer followed by the plaintext itself.
+    PlaintextAutokey(Vec<usize>),
+    /// A primer followed by the ciphertext.
+    CiphertextAutokey(Vec<usize>),
+    /// A text at least as long as the message (e.g. a page of a book), which is never repeated.
+    Running(Vec<usize>),
+}
+
+impl Keystream {
+    /// The symbols of the keyword, repeated.
+    pub fn repeating(key: &VigenereKeyword, alphabet: &Alphabet) -> Self {
+        Keystream::Repeating(indexes(key.letters(), alphabet))
+    }
+
+    /// The digits of a Gronsfeld key, repeated.
+    pub fn gronsfeld(key: &GronsfeldKey) -> Self {
+        Keystream::Repeating(key.shifts().to_vec())
+    }
+
+    /// The keyword followed by the plaintext.
+    pub fn plaintext_autokey(key: &VigenereKeyword, alphabet: &Alphabet) -> Self {
+        Keystream::PlaintextAutokey(indexes(key.letters(), alphabet))
+    }
+
+    /// The keyword followed by the ciphertext.
+    pub fn ciphertext_autokey(key: &VigenereKeyword, alphabet: &Alphabet) -> Self {
+        Keystream::CiphertextAutokey(indexes(key.letters(), alphabet))
+    }
+
+    /// The symbols of the given text (after folding, skipping anything that isn't part of the
+    /// alphabet), which must contain at least one.
+    pub fn running(text: &str, alphabet: &Alphabet) -> Result<Self, CipherError> {
+        let key = indexes(&alphabet.clean(text), alphabet);
+
+        if key.is_empty() {
+            return Err(CipherError::InvalidKey(
+                "the text of a running key must contain at least one symbol of the alphabet"
+                    .to_string(),
+            ));
+        }
+
+        Ok(Keystream::Running(key))
+    }
+
+    /// The symbols the key stream starts with.
+    fn primer(&self) -> &[usize] {
+        match self {
+            Keystream::Repeating(key)
+            | Keystream::PlaintextAutokey(key)
+            | Keystream::CiphertextAutokey(key)
+            | Keystream::Running(key) => key,
+        }
+    }
+
+    /// Encrypts (or decrypts) the clean text with the tableau, taking a key symbol for every
+    /// symbol of the text.
+    ///
+    /// Repeating and running keys follow the position of the symbol (so they advance over
+    /// whatever the text mode says), while autokeys only ever advance over the symbols they are
+    /// fed.
+    pub(crate) fn run(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+        mode: TextMode,
+        digits: DigitPolicy,
+        tableau: Tableau,
+        decrypt: bool,
+    ) -> Result<String, CipherError> {
+        let primer = self.primer();
+        // the plaintext or the ciphertext fed back into an autokey
+        let mut fed = Vec::new();
+        let mut too_short = false;
+
+        let substituted = substitute(text, alphabet, mode, digits, |idx, pos, ring| {
+            let m = match ring {
+                Ring::Symbols => alphabet.len(),
+                Ring::Digits => DIGITS,
+            };
+            let key = match self {
+                Keystream::Repeating(key) => key[pos % key.len()],
+                Keystream::Running(key) => key.get(pos).copied().unwrap_or_else(|| {
+                    too_short = true;
+                    0
+                }),
+                _ if fed.len() < primer.len() => primer[fed.len()],
+                _ => fed[fed.len() - primer.len()],
+            } % m;
+
+            let out = if decrypt {
+                tableau.decrypt(idx, key, m)
+            } else {
+                tableau.encrypt(idx, key, m)
+            };
+            match (self, decrypt) {
+                (Keystream::PlaintextAutokey(_), false)
+                | (Keystream::CiphertextAutokey(_), true) => fed.push(idx),
+                (Keystream::PlaintextAutokey(_), true)
+                | (Keystream::CiphertextAutokey(_), false) => fed.push(out),
+                _ => (),
+            }
+
+            out
+        })?;
+
+        if too_short {
+            return Err(CipherError::InvalidLength(format!(
+                "the running key ({} symbols) is shorter than the text",
+                primer.len()
+            )));
+        }
+
+        Ok(substituted)
+    }
+}
+
+/// The indexes in the alphabet of the symbols of the text, skipping anything else.
+fn indexes(text: &str, alphabet: &Alphabet) -> Vec<usize> {
+    text.chars()
+        .filter_map(|ch| alphabet.index_of(ch))
+        .collect()
+}
+
+/// A member of the Vigenère family: a tableau and a key stream.
+///
+/// The plain Vigenère cipher is `VigenereCipher`, the others are built with the constructor named
+/// after them.
+pub struct PolyalphabeticCipher {
+    tableau: Tableau,
+    keystream: Keystream,
+    alphabet: Alphabet,
+    mode: TextMode,
+    digits: DigitPolicy,
+}
+
+impl PolyalphabeticCipher {
+    /// Initializes a cipher working on the English alphabet.
+    pub fn new(tableau: Tableau, keystream: Keystream) -> Result<Self, CipherError> {
+        PolyalphabeticCipher::with_alphabet(tableau, keystream, Alphabet::english())
+    }
+
+    /// Initializes a cipher working on the given alphabet, which the key stream must have been
+    /// built for.
+    pub fn with_alphabet(
+        tableau: Tableau,
+        keystream: Keystream,
+        alphabet: Alphabet,
+    ) -> Result<Self, CipherError> {
+        tableau.check(alphabet.len())?;
+        if keystream.primer().is_empty() {
+            return Err(CipherError::InvalidKey(
+                "the key stream can't start out empty".to_string(),
+            ));
+        }
+
+        Ok(PolyalphabeticCipher {
+            tableau,
+            keystream,
+            alphabet,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        })
+    }
+
+    /// The Beaufort cipher, with the given keyword.
+    pub fn beaufort(key: &str) -> Result<Self, CipherError> {
+        PolyalphabeticCipher::keyword(Tableau::Beaufort, key)
+    }
+
+    /// The variant Beaufort cipher, with the given keyword.
+    pub fn variant_beaufort(key: &str) -> Result<Self, CipherError> {
+        PolyalphabeticCipher::keyword(Tableau::VariantBeaufort, key)
+    }
+
+    /// The Porta cipher, with the given keyword.
+    pub fn porta(key: &str) -> Result<Self, CipherError> {
+        PolyalphabeticCipher::keyword(Tableau::Porta, key)
+    }
+
+    /// The Gronsfeld cipher, i.e. Vigenère with a key of digits.
+    pub fn gronsfeld(key: &str) -> Result<Self, CipherError> {
+        PolyalphabeticCipher::new(Tableau::Vigenere, Keystream::gronsfeld(&key.parse()?))
+    }
+
+    /// The (plaintext) autokey cipher, with the given primer.
+    pub fn autokey(key: &str) -> Result<Self, CipherError> {
+        let alphabet = Alphabet::english();
+        let primer = VigenereKeyword::parse_named(key, &alphabet, "plaintext autokey")?;
+        let keystream = Keystream::plaintext_autokey(&primer, &alphabet);
+
+        PolyalphabeticCipher::new(Tableau::Vigenere, keystream)
+    }
+
+    /// The ciphertext autokey cipher, with the given primer.
+    pub fn ciphertext_autokey(key: &str) -> Result<Self, CipherError> {
+        let alphabet = Alphabet::english();
+        let primer = VigenereKeyword::parse_named(key, &alphabet, "ciphertext autokey")?;
+        let keystream = Keystream::ciphertext_autokey(&primer, &alphabet);
+
+        PolyalphabeticCipher::new(Tableau::Vigenere, keystream)
+    }
+
+    /// The running key cipher, whose key is the given text.
+    pub fn running_key(text: &str) -> Result<Self, CipherError> {
+        let keystream = Keystream::running(text, &Alphabet::english())?;
+
+        PolyalphabeticCipher::new(Tableau::Vigenere, keystream)
+    }
+
+    fn keyword(tableau: Tableau, key: &str) -> Result<Self, CipherError> {
+        let alphabet = Alphabet::english();
+        let keyword = VigenereKeyword::parse_named(key, &alphabet, tableau.name())?;
+        let keystream = Keystream::repeating(&keyword, &alphabet);
+
+        PolyalphabeticCipher::new(tableau, keystream)
+    }
+
+    /// Sets how the characters that are not part of the alphabet are treated (and whether the key
+    /// advances over them).
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets what happens to the digits (with `DigitPolicy::Combined` they are appended to the
+    /// alphabet, with `DigitPolicy::SeparateRing` they are combined with the key modulo 10).
+    ///
+    /// Fails if the Porta cipher would end up with an alphabet of odd size.
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        // the digits go after the existing symbols, so the key stream doesn't change
+        if digits == DigitPolicy::Combined {
+            self.alphabet = self.alphabet.with_digits();
+            self.tableau.check(self.alphabet.len())?;
+        }
+        self.digits = digits;
+
+        Ok(self)
+    }
+
+    pub fn tableau(&self) -> Tableau {
+        self.tableau
+    }
+
+    pub fn keystream(&self) -> &Keystream {
+        &self.keystream
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+}
+
+impl Cipher for PolyalphabeticCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+
+        self.keystream.run(
+            &clean_plaintext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            self.tableau,
+            false,
+        )
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+
+        self.keystream.run(
+            &clean_ciphertext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            self.tableau,
+            true,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VigenereCipher;
+
+    const PLAINTEXT: &str = "Defend the east wall of the castle";
+
+    #[test]
+    fn test_beaufort() {
+        // from http://practicalcryptography.com/ciphers/beaufort-cipher/
+        let cipher = PolyalphabeticCipher::beaufort("fortification").unwrap();
+        let ciphertext = "CKMPVCPVWPIWUJOGIUAPVWRIWUUK";
+
+        assert_eq!(ciphertext, cipher.encrypt(PLAINTEXT).unwrap());
+        assert_eq!(
+            cipher.clean_input(PLAINTEXT),
+            cipher.decrypt(ciphertext).unwrap()
+        );
+        // Beaufort is its own inverse
+        assert_eq!(
+            cipher.clean_input(PLAINTEXT),
+            cipher.encrypt(ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_variant_beaufort() {
+        let cipher = PolyalphabeticCipher::variant_beaufort("lemon").unwrap();
+        let vigenere = VigenereCipher::new("lemon").unwrap();
+
+        assert_eq!("PPHMPZWHPNLJ", cipher.encrypt("attackatdawn").unwrap());
+        assert_eq!(
+            vigenere.decrypt("attackatdawn").unwrap(),
+            cipher.encrypt("attackatdawn").unwrap()
+        );
+        assert_eq!("ATTACKATDAWN", cipher.decrypt("PPHMPZWHPNLJ").unwrap());
+    }
+
+    #[test]
+    fn test_gronsfeld() {
+        let cipher = PolyalphabeticCipher::gronsfeld("31415").unwrap();
+
+        assert_eq!("KFPMTZPVMI", cipher.encrypt("Hello world").unwrap());
+        assert_eq!("HELLOWORLD", cipher.decrypt("KFPMTZPVMI").unwrap());
+        assert!(PolyalphabeticCipher::gronsfeld("pi").is_err());
+    }
+
+    #[test]
+    fn test_porta() {
+        // from http://practicalcryptography.com/ciphers/porta-cipher/
+        let cipher = PolyalphabeticCipher::porta("fortification").unwrap();
+        let ciphertext = "SYNNJSCVRNRLAHUTUKUCVRYRLANY";
+
+        assert_eq!(ciphertext, cipher.encrypt(PLAINTEXT).unwrap());
+        // every alphabet is reciprocal
+        assert_eq!(
+            cipher.clean_input(PLAINTEXT),
+            cipher.decrypt(ciphertext).unwrap()
+        );
+        assert_eq!(
+            cipher.clean_input(PLAINTEXT),
+            cipher.encrypt(ciphertext).unwrap()
+        );
+
+        let odd = Alphabet::new("ABC").unwrap();
+        let keystream = Keystream::Repeating(vec![0]);
+        assert!(PolyalphabeticCipher::with_alphabet(Tableau::Porta, keystream, odd).is_err());
+    }
+
+    #[test]
+    fn test_autokey() {
+        // from https://en.wikipedia.org/wiki/Autokey_cipher
+        let cipher = PolyalphabeticCipher::autokey("queenly").unwrap();
+        let ciphertext = "QNXEPVYTWTWP";
+
+        assert_eq!(ciphertext, cipher.encrypt("attack at dawn").unwrap());
+        assert_eq!("ATTACKATDAWN", cipher.decrypt(ciphertext).unwrap());
+
+        // the ciphertext takes over from the primer
+        let cipher = PolyalphabeticCipher::ciphertext_autokey("queenly").unwrap();
+        let ciphertext = "QNXEPVYJQXAC";
+
+        assert_eq!(ciphertext, cipher.encrypt("attack at dawn").unwrap());
+        assert_eq!("ATTACKATDAWN", cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_key_errors() {
+        // every cipher speaks for itself
+        let error = |cipher: Result<PolyalphabeticCipher, CipherError>| {
+            cipher.err().map(|err| err.to_string()).unwrap_or_default()
+        };
+
+        assert!(error(PolyalphabeticCipher::beaufort("")).contains("a Beaufort cipher"));
+        assert!(error(PolyalphabeticCipher::variant_beaufort(" ")).contains("variant Beaufort"));
+        assert!(error(PolyalphabeticCipher::porta("l3mon")).contains("a Porta cipher"));
+        assert!(error(PolyalphabeticCipher::autokey("")).contains("plaintext autokey"));
+        assert!(error(PolyalphabeticCipher::ciphertext_autokey("")).contains("ciphertext autokey"));
+    }
+
+    #[test]
+    fn test_running_key() {
+        let key = "How vexingly quick daft zebras jump!";
+        let cipher = PolyalphabeticCipher::running_key(key).unwrap();
+        let ciphertext = cipher.encrypt("attack at dawn").unwrap();
+
+        assert_eq!(
+            VigenereCipher::new("howvexinglyq")
+                .unwrap()
+                .encrypt("attack at dawn")
+                .unwrap(),
+            ciphertext
+        );
+        assert_eq!("ATTACKATDAWN", cipher.decrypt(&ciphertext).unwrap());
+
+        let cipher = PolyalphabeticCipher::running_key("short").unwrap();
+        assert!(matches!(
+            cipher.encrypt("attack at dawn"),
+            Err(CipherError::InvalidLength(_))
+        ));
+        assert!(PolyalphabeticCipher::running_key("123 !").is_err());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = PolyalphabeticCipher::beaufort("lemon")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .unwrap();
+        // L, E, M give 1, 4 and 2 modulo 10
+        assert_eq!("LYIBU145", cipher.encrypt("Agent 007").unwrap());
+        assert_eq!("AGENT007", cipher.decrypt("LYIBU145").unwrap());
+
+        let cipher = PolyalphabeticCipher::porta("lemon")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .unwrap();
+        let ciphertext = cipher.encrypt("Agent 007").unwrap();
+        assert_eq!("AGENT007", cipher.decrypt(&ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let cipher = PolyalphabeticCipher::autokey("queenly")
+            .unwrap()
+            .with_mode(TextMode::preserve_format());
+        let ciphertext = "Qnxepv yt wtwp!";
+
+        assert_eq!(ciphertext, cipher.encrypt("Attack at dawn!").unwrap());
+        assert_eq!("Attack at dawn!", cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let plaintext = "the quick brown fox jumps over the lazy dog";
+        let ciphers = [
+            PolyalphabeticCipher::beaufort("lemon"),
+            PolyalphabeticCipher::variant_beaufort("x"),
+            PolyalphabeticCipher::porta("anincrediblylongkey"),
+            PolyalphabeticCipher::gronsfeld("0987654321"),
+            PolyalphabeticCipher::autokey("k"),
+            PolyalphabeticCipher::ciphertext_autokey("kryptos"),
+            PolyalphabeticCipher::running_key(plaintext),
+        ];
+
+        for cipher in ciphers.iter() {
+            let cipher = cipher.as_ref().unwrap();
+
+            for len in 0..plaintext.len() {
+                let plaintext = &plaintext[..len];
+                let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+                assert_eq!(
+                    cipher.clean_input(plaintext),
+                    cipher.decrypt(&ciphertext).unwrap()
+                );
+            }
+        }
+    }
+}
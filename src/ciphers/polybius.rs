@@ -0,0 +1,238 @@
+use crate::{Alphabet, CipherError};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// How the 26 letters are squeezed into the 25 cells of a 5×5 square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SquareLetters {
+    /// J is written as I.
+    #[default]
+    MergeIJ,
+    /// Q is left out altogether.
+    OmitQ,
+}
+
+impl SquareLetters {
+    /// The 25 letters that end up in the square, with the left out one folded away.
+    pub fn alphabet(&self) -> Alphabet {
+        match self {
+            SquareLetters::MergeIJ => Alphabet::new("ABCDEFGHIKLMNOPQRSTUVWXYZ")
+                .and_then(|a| a.with_fold('J', "I"))
+                .unwrap(),
+            SquareLetters::OmitQ => Alphabet::new("ABCDEFGHIJKLMNOPRSTUVWXYZ")
+                .and_then(|a| a.with_fold('Q', ""))
+                .unwrap(),
+        }
+    }
+}
+
+impl FromStr for SquareLetters {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ij" => Ok(SquareLetters::MergeIJ),
+            "noq" => Ok(SquareLetters::OmitQ),
+            _ => Err(CipherError::InvalidKey(format!(
+                "unknown 5×5 square '{}', expected either ij or noq",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SquareLetters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SquareLetters::MergeIJ => "ij",
+            SquareLetters::OmitQ => "noq",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A Polybius square: the symbols of an alphabet written row by row in a square grid, those of a
+/// keyword first (without repetitions) and then all the others in order, so that each symbol can
+/// be told by its row and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolybiusSquare {
+    alphabet: Alphabet,
+    /// The symbols, row by row.
+    symbols: Vec<char>,
+    /// The cell holding each symbol of the alphabet.
+    cells: Vec<usize>,
+    size: usize,
+}
+
+impl PolybiusSquare {
+    /// A 5×5 square of the 25 letters left by squeezing the alphabet as stated.
+    pub fn five(keyword: &str, letters: SquareLetters) -> Self {
+        PolybiusSquare::keyed(keyword, letters.alphabet()).unwrap()
+    }
+
+    /// A 6×6 square of the 26 letters and the 10 digits.
+    pub fn six(keyword: &str) -> Self {
+        PolybiusSquare::keyed(keyword, Alphabet::alphanumeric()).unwrap()
+    }
+
+    /// A square of the symbols of the given alphabet, whose size must be a perfect square.
+    ///
+    /// The keyword is folded onto the alphabet, and whatever isn't part of it is ignored.
+    pub fn keyed(keyword: &str, alphabet: Alphabet) -> Result<Self, CipherError> {
+        let size = (1..)
+            .find(|n| n * n >= alphabet.len())
+            .filter(|n| n * n == alphabet.len())
+            .ok_or_else(|| {
+                CipherError::InvalidKey(format!(
+                    "a Polybius square can't be made out of {} symbols",
+                    alphabet.len()
+                ))
+            })?;
+
+        let symbols = keyed_symbols(keyword, &alphabet);
+        let mut cells = vec![0; alphabet.len()];
+        for (cell, ch) in symbols.iter().enumerate() {
+            cells[alphabet.index_of(*ch).unwrap()] = cell;
+        }
+
+        Ok(PolybiusSquare {
+            alphabet,
+            symbols,
+            cells,
+            size,
+        })
+    }
+
+    /// The number of rows (and columns).
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The alphabet the symbols of the square come from, which tells how the text is folded.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The symbols of the square, row by row.
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    /// The row and the column of the given symbol, if it is in the square.
+    pub fn coordinates(&self, ch: char) -> Option<(usize, usize)> {
+        let cell = self.cells[self.alphabet.index_of(ch)?];
+
+        Some((cell / self.size, cell % self.size))
+    }
+
+    /// The symbol at the given row and column (wrapping around the edges).
+    pub fn symbol_at(&self, row: usize, col: usize) -> char {
+        self.symbols[(row % self.size) * self.size + col % self.size]
+    }
+}
+
+impl fmt::Display for PolybiusSquare {
+    /// Prints the square, one row per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.symbols.chunks(self.size) {
+            let row = row.iter().map(|ch| ch.to_string()).collect::<Vec<String>>();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The symbols of the alphabet starting with those of the keyword (after folding, skipping
+/// repetitions and anything that isn't part of the alphabet).
+pub fn keyed_symbols(keyword: &str, alphabet: &Alphabet) -> Vec<char> {
+    let mut symbols: Vec<char> = Vec::with_capacity(alphabet.len());
+
+    for ch in alphabet
+        .fold(keyword)
+        .chars()
+        .chain(alphabet.symbols().iter().copied())
+    {
+        if alphabet.contains(ch) && !symbols.contains(&ch) {
+            symbols.push(ch);
+        }
+    }
+
+    symbols
+}
+
+/// Fractionates a group of symbols, given their coordinates: the coordinates are written one
+/// symbol per column and read back row by row, as many at a time as each symbol has.
+///
+/// E.g. the coordinates (1, 2), (3, 4), (5, 6) are written as 1 3 5 / 2 4 6 and read back as
+/// (1, 3), (5, 2), (4, 6).
+pub fn fractionate(group: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let d = group.first().map_or(0, |coordinates| coordinates.len());
+    let spread = (0..d)
+        .flat_map(|i| group.iter().map(move |coordinates| coordinates[i]))
+        .collect::<Vec<usize>>();
+
+    spread
+        .chunks(d.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+/// Undoes fractionate.
+pub fn unfractionate(group: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let (k, d) = (group.len(), group.first().map_or(0, |c| c.len()));
+    let spread = group.iter().flatten().copied().collect::<Vec<usize>>();
+
+    (0..k)
+        .map(|j| (0..d).map(|i| spread[i * k + j]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let square = PolybiusSquare::five("playfair example", SquareLetters::MergeIJ);
+        assert_eq!(
+            "PLAYFIREXMBCDGHKNOQSTUVWZ",
+            square.symbols().iter().collect::<String>()
+        );
+        assert_eq!(Some((1, 0)), square.coordinates('I'));
+        assert_eq!(None, square.coordinates('J'));
+        assert_eq!('Z', square.symbol_at(4, 4));
+        assert_eq!('P', square.symbol_at(5, 5));
+
+        let square = PolybiusSquare::five("jumbo", SquareLetters::OmitQ);
+        assert_eq!(
+            "JUMBOACDEFGHIKLNPRSTVWXYZ",
+            square.symbols().iter().collect::<String>()
+        );
+
+        let square = PolybiusSquare::six("n4 1c3");
+        assert_eq!(6, square.size());
+        assert_eq!(
+            "N41C3ABDEFGHIJKLMOPQRSTUVWXYZ0256789",
+            square.symbols().iter().collect::<String>()
+        );
+        assert_eq!("N 4 1 C 3 A\n", &square.to_string()[..12]);
+
+        assert!(PolybiusSquare::keyed("", Alphabet::english()).is_err());
+    }
+
+    #[test]
+    fn test_fractionate() {
+        let group = vec![vec![1, 2], vec![3, 4], vec![5, 6]];
+        let fractionated = fractionate(&group);
+
+        assert_eq!(vec![vec![1, 3], vec![5, 2], vec![4, 6]], fractionated);
+        assert_eq!(group, unfractionate(&fractionated));
+
+        let group = vec![vec![0, 1, 2], vec![2, 1, 0]];
+        assert_eq!(group, unfractionate(&fractionate(&group)));
+        assert!(fractionate(&[]).is_empty());
+    }
+}
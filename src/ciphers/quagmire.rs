@@ -0,0 +1,347 @@
+use super::utils::{clean, substitute, Ring, DIGITS};
+use crate::keys::{KeywordAlphabet, KeywordOrder, QuagmireKey};
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The four Quagmire ciphers of the ACA, which differ in which of the alphabets are keyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quagmire {
+    /// Keyed plaintext alphabet, straight ciphertext alphabet (the indicator goes under the
+    /// plaintext A).
+    I,
+    /// Straight plaintext alphabet, keyed ciphertext alphabet.
+    II,
+    /// The same keyed alphabet for both.
+    III,
+    /// Two keyed alphabets, with keywords of their own.
+    IV,
+}
+
+impl FromStr for Quagmire {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1" | "i" => Ok(Quagmire::I),
+            "2" | "ii" => Ok(Quagmire::II),
+            "3" | "iii" => Ok(Quagmire::III),
+            "4" | "iv" => Ok(Quagmire::IV),
+            _ => Err(CipherError::InvalidKey(format!(
+                "unknown Quagmire cipher '{}', expected one of I, II, III or IV",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Quagmire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Quagmire::I => "I",
+            Quagmire::II => "II",
+            Quagmire::III => "III",
+            Quagmire::IV => "IV",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A Quagmire cipher: a periodic cipher where the ciphertext alphabet slides along the plaintext
+/// one, so that each letter of the indicator in turn sits under the first letter of the
+/// plaintext alphabet (or under A, for Quagmire I).
+pub struct QuagmireCipher {
+    variant: Quagmire,
+    key: QuagmireKey,
+    alphabet: Alphabet,
+    /// The position of every symbol of the alphabet in the plaintext alphabet.
+    plain: Vec<usize>,
+    /// The ciphertext alphabet (as indexes in the alphabet).
+    cipher: Vec<usize>,
+    /// The position of every symbol of the alphabet in the ciphertext alphabet.
+    cipher_positions: Vec<usize>,
+    /// How far the ciphertext alphabet slides for each letter of the indicator.
+    offsets: Vec<usize>,
+    mode: TextMode,
+    digits: DigitPolicy,
+}
+
+impl QuagmireCipher {
+    /// Initializes a Quagmire cipher given the key (see QuagmireKey).
+    pub fn new(variant: Quagmire, key: &str) -> Result<Self, CipherError> {
+        QuagmireCipher::from_key(variant, key.parse()?)
+    }
+
+    /// Initializes a Quagmire cipher with the given key, which still has to match the variant
+    /// (only Quagmire IV takes a keyword for the ciphertext alphabet).
+    pub fn from_key(variant: Quagmire, key: QuagmireKey) -> Result<Self, CipherError> {
+        QuagmireCipher::with_alphabet(variant, key, Alphabet::english())
+    }
+
+    /// Initializes a Quagmire cipher working on the given alphabet, to which the letters of the
+    /// indicator must belong (after folding).
+    pub fn with_alphabet(
+        variant: Quagmire,
+        key: QuagmireKey,
+        alphabet: Alphabet,
+    ) -> Result<Self, CipherError> {
+        if (variant == Quagmire::IV) != key.cipher_keyword().is_some() {
+            return Err(CipherError::InvalidKey(format!(
+                "Quagmire IV takes a keyword for each alphabet, the others a single one, got {} for Quagmire {}",
+                key.cipher_keyword().map_or(1, |_| 2),
+                variant
+            )));
+        }
+
+        let keyed = |keyword: &str| {
+            KeywordAlphabet::new(keyword, KeywordOrder::Plain)
+                .map(|keyword| keyword.symbols(&alphabet))
+        };
+        let straight = alphabet.symbols().to_vec();
+        let (plain, cipher) = match variant {
+            Quagmire::I => (keyed(key.keyword())?, straight),
+            Quagmire::II => (straight, keyed(key.keyword())?),
+            Quagmire::III => (keyed(key.keyword())?, keyed(key.keyword())?),
+            Quagmire::IV => (keyed(key.keyword())?, keyed(key.cipher_keyword().unwrap())?),
+        };
+        let plain = positions(&plain, &alphabet);
+        let cipher_positions = positions(&cipher, &alphabet);
+        let cipher = cipher
+            .iter()
+            .map(|ch| alphabet.index_of(*ch).unwrap())
+            .collect::<Vec<usize>>();
+
+        // the plaintext position the indicator goes under
+        let anchor = match variant {
+            Quagmire::I => alphabet.index_of('A').map_or(0, |a| plain[a]),
+            _ => 0,
+        };
+        let m = alphabet.len();
+        let offsets = alphabet
+            .fold(key.indicator())
+            .chars()
+            .map(|ch| match alphabet.index_of(ch) {
+                Some(idx) => Ok((cipher_positions[idx] + m - anchor) % m),
+                None => Err(CipherError::InvalidKey(format!(
+                    "the indicator of a Quagmire cipher may only contain symbols of the alphabet {}, found {:?}",
+                    alphabet, ch
+                ))),
+            })
+            .collect::<Result<Vec<usize>, CipherError>>()?;
+
+        Ok(QuagmireCipher {
+            variant,
+            key,
+            alphabet,
+            plain,
+            cipher,
+            cipher_positions,
+            offsets,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        })
+    }
+
+    /// Sets how the characters that are not part of the alphabet are treated (and whether the
+    /// indicator advances over them).
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets what happens to the digits: with `DigitPolicy::SeparateRing` each letter of the
+    /// indicator shifts them by its offset modulo 10, while `DigitPolicy::Combined` isn't
+    /// supported, since the keyed alphabets only hold the letters.
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        if digits == DigitPolicy::Combined {
+            return Err(CipherError::InvalidKey(
+                "the Quagmire ciphers can't mix the digits into their keyed alphabets".to_string(),
+            ));
+        }
+        self.digits = digits;
+
+        Ok(self)
+    }
+
+    pub fn variant(&self) -> Quagmire {
+        self.variant
+    }
+
+    pub fn key(&self) -> &QuagmireKey {
+        &self.key
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+}
+
+/// The position of every symbol of the alphabet in the given arrangement of it.
+fn positions(arrangement: &[char], alphabet: &Alphabet) -> Vec<usize> {
+    let mut positions = vec![0; alphabet.len()];
+
+    for (pos, ch) in arrangement.iter().enumerate() {
+        positions[alphabet.index_of(*ch).unwrap()] = pos;
+    }
+
+    positions
+}
+
+impl Cipher for QuagmireCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+        let (m, period) = (self.alphabet.len(), self.offsets.len());
+
+        substitute(
+            &clean_plaintext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, pos, ring| {
+                let offset = self.offsets[pos % period];
+
+                match ring {
+                    Ring::Symbols => self.cipher[(self.plain[idx] + offset) % m],
+                    Ring::Digits => idx + offset % DIGITS,
+                }
+            },
+        )
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+        let (m, period) = (self.alphabet.len(), self.offsets.len());
+        // the symbol at each position of the plaintext alphabet
+        let mut plain = vec![0; m];
+        for (idx, pos) in self.plain.iter().enumerate() {
+            plain[*pos] = idx;
+        }
+
+        substitute(
+            &clean_ciphertext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, pos, ring| {
+                let offset = self.offsets[pos % period];
+
+                match ring {
+                    Ring::Symbols => plain[(self.cipher_positions[idx] + m - offset) % m],
+                    Ring::Digits => idx + DIGITS - offset % DIGITS,
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quagmire_i() {
+        // SPRINGFEVABCDHJKLMOQTUWXYZ over a straight alphabet, with A under A
+        let cipher = QuagmireCipher::new(Quagmire::I, "springfever,a").unwrap();
+
+        assert_eq!("ARBCD", cipher.encrypt("asbcd").unwrap());
+        assert_eq!("ASBCD", cipher.decrypt("ARBCD").unwrap());
+    }
+
+    #[test]
+    fn test_quagmire_ii() {
+        // a straight alphabet over SPRINGFEVABCDHJKLMOQTUWXYZ, with A under A
+        let cipher = QuagmireCipher::new(Quagmire::II, "springfever,a").unwrap();
+
+        assert_eq!("ABCDH", cipher.encrypt("abcde").unwrap());
+        assert_eq!("ABCDE", cipher.decrypt("ABCDH").unwrap());
+    }
+
+    #[test]
+    fn test_quagmire_iii() {
+        let cipher = QuagmireCipher::new(Quagmire::III, "automobile,a").unwrap();
+        // A under A leaves everything where it is...
+        assert_eq!("QUAGMIRE", cipher.encrypt("quagmire").unwrap());
+
+        // ...while U under A moves every letter one place along AUTOMBILECDFGHJKNPQRSVWXYZ
+        let cipher = QuagmireCipher::new(Quagmire::III, "automobile,u").unwrap();
+        assert_eq!("UTA", cipher.encrypt("auz").unwrap());
+        assert_eq!("AUZ", cipher.decrypt("UTA").unwrap());
+    }
+
+    #[test]
+    fn test_quagmire_iv() {
+        // PERCTIVLABDFGHJKMNOQSUWXYZ under SENORYABCDFGHIJKLMPQTUVWXZ
+        let cipher = QuagmireCipher::new(Quagmire::IV, "senory,perctivl,p").unwrap();
+        assert_eq!("PERC", cipher.encrypt("seno").unwrap());
+        assert_eq!("SENO", cipher.decrypt("PERC").unwrap());
+
+        // one keyword too many or too few
+        assert!(QuagmireCipher::new(Quagmire::IV, "senory,extra").is_err());
+        assert!(QuagmireCipher::new(Quagmire::III, "senory,perctivl,extra").is_err());
+    }
+
+    #[test]
+    fn test_period() {
+        let cipher = QuagmireCipher::new(Quagmire::III, "automobile,au").unwrap();
+
+        assert_eq!("AUUU", cipher.encrypt("aaua").unwrap());
+        assert_eq!("AAUA", cipher.decrypt("AUUU").unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = QuagmireCipher::new(Quagmire::III, "automobile,au")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing)
+            .unwrap();
+        // U slides the alphabets by one place
+        assert_eq!("A1A1", cipher.encrypt("a0a0").unwrap());
+        assert_eq!("A0A0", cipher.decrypt("A1A1").unwrap());
+
+        assert!(QuagmireCipher::new(Quagmire::III, "automobile,au")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined)
+            .is_err());
+    }
+
+    #[test]
+    fn test_variant_names() {
+        assert_eq!(Quagmire::III, "iii".parse().unwrap());
+        assert_eq!(Quagmire::IV, "4".parse().unwrap());
+        assert!("v".parse::<Quagmire>().is_err());
+        assert_eq!("II", Quagmire::II.to_string());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let ciphers = [
+            (Quagmire::I, "springfever,flower"),
+            (Quagmire::II, "springfever,flower"),
+            (Quagmire::III, "automobile,highway"),
+            (Quagmire::IV, "senory,perctivl,extra"),
+        ];
+        let plaintext = "the quick brown fox jumps over the lazy dog";
+
+        for (variant, key) in ciphers.iter() {
+            let cipher = QuagmireCipher::new(*variant, key).unwrap();
+
+            for len in 0..plaintext.len() {
+                let plaintext = &plaintext[..len];
+                let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+                assert_eq!(
+                    cipher.clean_input(plaintext),
+                    cipher.decrypt(&ciphertext).unwrap()
+                );
+            }
+        }
+    }
+}
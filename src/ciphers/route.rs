@@ -0,0 +1,130 @@
+use super::transposition::{clean, transpose, untranspose, Grid, Route};
+use crate::keys::RouteKey;
+use crate::text::DigitPolicy;
+use crate::{Cipher, CipherError};
+
+pub struct RouteCipher {
+    key: RouteKey,
+    digits: DigitPolicy,
+}
+
+impl RouteCipher {
+    /// Creates a new route cipher given the key, e.g. "6:spiral" (the text is written in rows of
+    /// 6 characters and read clockwise from the top left corner inwards).
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(RouteCipher::from_key(key.parse()?))
+    }
+
+    /// Creates a new route cipher with an already validated key.
+    pub fn from_key(key: RouteKey) -> Self {
+        RouteCipher {
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets what happens to the digits (either stripped, spelled out or transposed along with
+    /// the letters).
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    pub fn key(&self) -> &RouteKey {
+        &self.key
+    }
+
+    pub fn route(&self) -> Route {
+        self.key.route()
+    }
+
+    /// The order in which the characters of a text of len characters are read.
+    fn reading_order(&self, len: usize) -> Vec<usize> {
+        Grid::new(len, self.key.columns()).by_route(self.key.route())
+    }
+}
+
+impl Cipher for RouteCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext).chars().collect::<Vec<char>>();
+        let order = self.reading_order(clean_plaintext.len());
+
+        Ok(transpose(&clean_plaintext, &order))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext).chars().collect::<Vec<char>>();
+        let order = self.reading_order(clean_ciphertext.len());
+
+        Ok(untranspose(&clean_ciphertext, &order))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_pairs() {
+        // WEARED
+        // ISCOVE
+        // REDFLE
+        // EATONC
+        // E
+        let plaintext = "We are discovered. Flee at once.";
+
+        let cipher = RouteCipher::new("6:spiral").unwrap();
+        let ciphertext = "WEAREDEECEERISCOVLNOTAEDF";
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(
+            cipher.clean_input(plaintext),
+            cipher.decrypt(ciphertext).unwrap()
+        );
+
+        let cipher = RouteCipher::new("6:snake").unwrap();
+        let ciphertext = "WIREEAESEACDTOFOREVLNCEED";
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+
+        let cipher = RouteCipher::new("6:diagonal").unwrap();
+        let ciphertext = "WIERSAEECREADOETFVDOLENEC";
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+    }
+
+    #[test]
+    fn test_huge_width() {
+        // a single row, whatever the route
+        for route in ["columns", "spiral", "snake", "diagonal"] {
+            for columns in ["1000000000", "9999999999999999999"] {
+                let cipher = RouteCipher::new(&format!("{}:{}", columns, route)).unwrap();
+
+                assert_eq!("HELLO", cipher.encrypt("hello").unwrap());
+                assert_eq!("HELLO", cipher.decrypt("hello").unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_correct() {
+        let plaintext = "Iamhurtverybadlyhelp";
+        let routes = [Route::Columns, Route::Spiral, Route::Snake, Route::Diagonal];
+
+        for route in routes {
+            for columns in 1..=plaintext.len() + 1 {
+                let cipher = RouteCipher::from_key(RouteKey::new(columns, route).unwrap());
+
+                for len in 0..=plaintext.len() {
+                    let text = &plaintext[..len];
+
+                    assert_eq!(
+                        text.to_uppercase(),
+                        cipher.decrypt(&cipher.encrypt(text).unwrap()).unwrap()
+                    );
+                }
+            }
+        }
+    }
+}
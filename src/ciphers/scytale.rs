@@ -1,8 +1,11 @@
-use super::utils::pad;
-use crate::Cipher;
+use super::transposition::{clean, transpose, untranspose, Grid, Route};
+use crate::keys::ScytaleLength;
+use crate::text::DigitPolicy;
+use crate::{Cipher, CipherError};
 
 pub struct Scytale {
-    key: usize, // the length
+    key: ScytaleLength,
+    digits: DigitPolicy,
 }
 
 impl Scytale {
@@ -14,48 +17,56 @@ impl Scytale {
     ///                                                                         | l m n o | is a scytale of
     ///                                                                                     length = 4 and
     ///                                                                                     diameter = 3
-    pub fn new(key: usize) -> Self {
-        Scytale { key }
+    ///
+    /// A scytale of length 0 can't hold any text, so it is rejected.
+    pub fn new(key: usize) -> Result<Self, CipherError> {
+        Ok(Scytale::from_key(ScytaleLength::new(key)?))
     }
 
-    /// Computes the diameter of the scytale given its length.
-    fn get_diameter(s: &str, length: usize) -> usize {
-        let padded_s = pad(s, length as u32, '.');
+    /// Creates a new scytale with an already validated length.
+    pub fn from_key(key: ScytaleLength) -> Self {
+        Scytale {
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
 
-        padded_s.len() / length
+    /// Sets what happens to the digits: since a scytale only moves characters around, they are
+    /// either stripped, spelled out or transposed along with the letters.
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        self.digits = digits;
+        self
     }
 
-    /// Simulates wrapping the parchment around the scytale.
-    ///
-    /// Mathematically speaking, my implementation and the traditional implementation utilizing
-    /// matrices et alia should be equivalent.
-    fn wrap_around_scytale(text: &str, n: usize) -> String {
-        let mut unwinded = String::new();
-        let l = text.len();
-
-        for i in 0..=l {
-            for j in (i..l).step_by(n) {
-                unwinded.push(text.chars().nth(j).unwrap());
-            }
-        }
-        unwinded.truncate(l);
+    pub fn key(&self) -> &ScytaleLength {
+        &self.key
+    }
 
-        unwinded
+    /// The order in which the characters of a text of len characters are read off the
+    /// parchment: it is written along the scytale, one row of length characters at a time, and
+    /// then unwound, which is the same as reading it column by column.
+    fn unwinding_order(&self, len: usize) -> Vec<usize> {
+        Grid::new(len, self.key.length()).by_route(Route::Columns)
     }
 }
 
 impl Cipher for Scytale {
-    fn encrypt(&self, plaintext: &str) -> String {
-        let clean_plaintext = <Scytale as Cipher>::clean_input(plaintext);
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext).chars().collect::<Vec<char>>();
+        let order = self.unwinding_order(clean_plaintext.len());
 
-        Scytale::wrap_around_scytale(&clean_plaintext, self.key)
+        Ok(transpose(&clean_plaintext, &order))
     }
 
-    fn decrypt(&self, ciphertext: &str) -> String {
-        let clean_ciphertext = <Scytale as Cipher>::clean_input(ciphertext);
-        let diameter = Scytale::get_diameter(&clean_ciphertext, self.key);
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext).chars().collect::<Vec<char>>();
+        let order = self.unwinding_order(clean_ciphertext.len());
 
-        Scytale::wrap_around_scytale(&clean_ciphertext, diameter)
+        Ok(untranspose(&clean_ciphertext, &order))
     }
 }
 
@@ -64,52 +75,90 @@ mod tests {
     use super::*;
 
     #[test]
-    #[should_panic]
     fn test_zero_length() {
         let cipher = Scytale::new(0);
 
-        cipher.encrypt("this should fail");
+        assert!(matches!(cipher, Err(CipherError::InvalidKey(_))));
+    }
+
+    #[test]
+    fn test_huge_length() {
+        // longer than the text, so it goes through unchanged
+        for length in [1_000_000_000, usize::MAX] {
+            let cipher = Scytale::new(length).unwrap();
+
+            assert_eq!("HELLO", cipher.encrypt("hello").unwrap());
+            assert_eq!("HELLO", cipher.decrypt("hello").unwrap());
+        }
     }
 
     #[test]
     fn test_known_pairs() {
         // from https://en.wikipedia.org/wiki/Scytale
-        let cipher = Scytale::new(5);
+        let cipher = Scytale::new(5).unwrap();
         let plaintext = "I am hurt very badly help";
         let ciphertext = String::from("Iryyatbhmvaehedlurlp");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <Scytale as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
 
         // from 'Programming in Cryptol', page 30
-        let cipher = Scytale::new(4); // in the book the diameter is 3
+        let cipher = Scytale::new(4).unwrap(); // in the book the diameter is 3
         let plaintext = "attackatdawn";
         let ciphertext = String::from("ACDTKATAWATN");
 
-        assert_eq!(ciphertext, cipher.encrypt(plaintext));
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
 
         assert_eq!(
-            <Scytale as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
     }
 
     #[test]
-    #[ignore]
+    fn test_digit_policies() {
+        let plaintext = "at 1";
+
+        let cipher = Scytale::new(2).unwrap();
+        assert_eq!("A1T", cipher.encrypt(plaintext).unwrap());
+
+        let cipher = Scytale::new(2).unwrap().with_digits(DigitPolicy::Strip);
+        assert_eq!("AT", cipher.encrypt(plaintext).unwrap());
+
+        let cipher = Scytale::new(2).unwrap().with_digits(DigitPolicy::SpellOut);
+        assert_eq!("ATONE", cipher.clean_input(plaintext));
+    }
+
+    #[test]
+    fn test_incomplete_rows() {
+        // ATTAC
+        // KATDA
+        // WN
+        let cipher = Scytale::new(5).unwrap();
+        let ciphertext = "AKWTANTTADCA";
+
+        assert_eq!(ciphertext, cipher.encrypt("attackatdawn").unwrap());
+        assert_eq!("ATTACKATDAWN", cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
     fn test_correct() {
         let plaintext = String::from("Iamhurtverybadly");
 
-        // FIXME something very subtle is going on here...
-        // FIXME tried copypasting code from another implementation and still didn't work!?
-        for length in 1..=plaintext.len() {
-            let cipher = Scytale::new(length);
+        for length in 1..=plaintext.len() + 1 {
+            let cipher = Scytale::new(length).unwrap();
             assert_eq!(
                 plaintext.to_uppercase(),
-                cipher.decrypt(&cipher.encrypt(&plaintext))
+                cipher
+                    .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                    .unwrap()
             );
         }
     }
@@ -1,58 +1,117 @@
-use crate::Cipher;
-use std::collections::VecDeque;
+use super::utils::{clean, substitute, Ring, DIGITS};
+use crate::keys::ShiftKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
 
 pub struct ShiftCipher {
-    key: i8,
+    key: ShiftKey,
+    alphabet: Alphabet,
+    mode: TextMode,
+    digits: DigitPolicy,
 }
 
 impl ShiftCipher {
     /// Initializes a new shift cipher with a given shift amount.
-    pub fn new(key: i8) -> Self {
-        ShiftCipher { key }
+    pub fn new(key: i8) -> Result<Self, CipherError> {
+        Ok(ShiftCipher::from_key(ShiftKey::new(key)))
     }
 
-    /// Shifts character ch by n in either direction.
+    /// Initializes a new shift cipher with an already validated key.
+    pub fn from_key(key: ShiftKey) -> Self {
+        ShiftCipher::with_alphabet(key, Alphabet::english())
+    }
+
+    /// Initializes a new shift cipher working on the given alphabet.
+    pub fn with_alphabet(key: ShiftKey, alphabet: Alphabet) -> Self {
+        ShiftCipher {
+            key,
+            alphabet,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets how the characters that are not part of the alphabet are treated.
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets what happens to the digits (with `DigitPolicy::Combined` they are appended to the
+    /// alphabet).
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        if digits == DigitPolicy::Combined {
+            self.alphabet = self.alphabet.with_digits();
+        }
+        self.digits = digits;
+        self
+    }
+
+    pub fn key(&self) -> &ShiftKey {
+        &self.key
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Shifts character ch by n in either direction, within the English alphabet.
     ///
     /// Mathematically equivalent to (ch [+-] n) mod 26, with [] having the same meaning
     /// as one would expect when using regexps.
-    pub fn shift_by(n: i8, ch: char) -> char {
+    ///
+    /// Fails if ch is neither an uppercase letter nor a digit.
+    pub fn shift_by(n: i8, ch: char) -> Result<char, CipherError> {
         // don't encrypt digits
         if ch.is_ascii_digit() {
-            return ch;
+            return Ok(ch);
         }
 
-        // VecDeque is faster than Vec at rotations
-        let mut alphabet = ('A'..='Z').collect::<VecDeque<char>>();
-        let abs_shift = (n.abs() % 26) as u32;
-        let idx = ch as u32 - 'A' as u32;
+        Alphabet::english().shift(ch, n as i64)
+    }
 
-        if n < 0 {
-            alphabet.rotate_right(abs_shift as usize);
-        } else {
-            alphabet.rotate_left(abs_shift as usize);
-        }
+    /// The shift amount, reduced to the size of the alphabet.
+    fn reduced_shift(&self, ring: Ring) -> usize {
+        let size = match ring {
+            Ring::Symbols => self.alphabet.len(),
+            Ring::Digits => DIGITS,
+        };
 
-        alphabet[idx as usize]
+        (self.key.shift() as i64).rem_euclid(size as i64) as usize
     }
 }
 
 impl Cipher for ShiftCipher {
-    fn encrypt(&self, plaintext: &str) -> String {
-        let clean_plaintext = <ShiftCipher as Cipher>::clean_input(plaintext);
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
 
-        clean_plaintext
-            .chars()
-            .map(|ch| ShiftCipher::shift_by(self.key, ch))
-            .collect::<String>()
+        substitute(
+            &clean_plaintext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, _, ring| idx + self.reduced_shift(ring),
+        )
     }
 
-    fn decrypt(&self, ciphertext: &str) -> String {
-        let clean_ciphertext = <ShiftCipher as Cipher>::clean_input(ciphertext);
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+        let m = self.alphabet.len();
 
-        clean_ciphertext
-            .chars()
-            .map(|ch| ShiftCipher::shift_by(-self.key, ch))
-            .collect::<String>()
+        substitute(
+            &clean_ciphertext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, _, ring| match ring {
+                Ring::Symbols => idx + m - self.reduced_shift(ring),
+                Ring::Digits => idx + DIGITS - self.reduced_shift(ring),
+            },
+        )
     }
 }
 
@@ -63,60 +122,128 @@ mod tests {
     #[test]
     fn test_shift_by() {
         // basic tests
-        assert_eq!('B', ShiftCipher::shift_by(1, 'A'));
-        assert_eq!('C', ShiftCipher::shift_by(2, 'A'));
-        assert_eq!('D', ShiftCipher::shift_by(3, 'A'));
-        assert_eq!('B', ShiftCipher::shift_by(27, 'A'));
+        assert_eq!('B', ShiftCipher::shift_by(1, 'A').unwrap());
+        assert_eq!('C', ShiftCipher::shift_by(2, 'A').unwrap());
+        assert_eq!('D', ShiftCipher::shift_by(3, 'A').unwrap());
+        assert_eq!('B', ShiftCipher::shift_by(27, 'A').unwrap());
 
         // edge cases
-        assert_eq!('A', ShiftCipher::shift_by(1, 'Z'));
-        assert_eq!('Z', ShiftCipher::shift_by(-1, 'A'));
+        assert_eq!('A', ShiftCipher::shift_by(1, 'Z').unwrap());
+        assert_eq!('Z', ShiftCipher::shift_by(-1, 'A').unwrap());
+
+        // not something we can shift
+        assert!(ShiftCipher::shift_by(1, 'a').is_err());
+        assert!(ShiftCipher::shift_by(1, '!').is_err());
     }
 
     #[test]
     fn test_zero_shift() {
-        let cipher = ShiftCipher::new(0);
+        let cipher = ShiftCipher::new(0).unwrap();
         let plaintext = String::from("Hello");
 
-        assert_eq!(plaintext.to_uppercase(), cipher.encrypt(&plaintext));
+        assert_eq!(
+            plaintext.to_uppercase(),
+            cipher.encrypt(&plaintext).unwrap()
+        );
     }
 
     #[test]
     fn test_numeric_input() {
         for shift in 1..=25 {
-            let cipher = ShiftCipher::new(shift);
+            let cipher = ShiftCipher::new(shift).unwrap();
             let plaintext = "918273456";
 
-            assert_eq!(plaintext.to_string(), cipher.encrypt(plaintext));
+            assert_eq!(plaintext.to_string(), cipher.encrypt(plaintext).unwrap());
         }
     }
 
     #[test]
     fn test_known_pairs() {
         // from https://cryptii.com
-        let cipher = ShiftCipher::new(8);
+        let cipher = ShiftCipher::new(8).unwrap();
         let plaintext = "attackatdawn";
         let ciphertext = String::from("ibbiksibliev");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <ShiftCipher as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
 
-        let cipher = ShiftCipher::new(13);
+        let cipher = ShiftCipher::new(13).unwrap();
         let plaintext = "firstman";
         let ciphertext = String::from("svefgzna");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <ShiftCipher as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
     }
 
+    #[test]
+    fn test_other_alphabets() {
+        let cipher = ShiftCipher::with_alphabet(ShiftKey::new(1), Alphabet::italian());
+        // J is folded onto I, Z wraps around to A
+        assert_eq!("LAVA", cipher.encrypt("Jz uz").unwrap());
+        assert_eq!("IZUZ", cipher.decrypt("LAVA").unwrap());
+
+        let cipher = ShiftCipher::with_alphabet(ShiftKey::new(-3), Alphabet::alphanumeric());
+        assert_eq!("789", cipher.encrypt("abc").unwrap());
+        assert_eq!("ABC", cipher.decrypt("789").unwrap());
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let cipher = ShiftCipher::new(3)
+            .unwrap()
+            .with_mode(TextMode::preserve_format());
+        let plaintext = "Veni, vidi, vici.\nJulius Caesar (47 BC)";
+        let ciphertext = "Yhql, ylgl, ylfl.\nMxolxv Fdhvdu (47 EF)";
+
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!(plaintext, cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let plaintext = "Code 1234";
+
+        let cipher = ShiftCipher::new(3).unwrap().with_digits(DigitPolicy::Strip);
+        assert_eq!("FRGH", cipher.encrypt(plaintext).unwrap());
+
+        let cipher = ShiftCipher::new(3)
+            .unwrap()
+            .with_digits(DigitPolicy::SpellOut);
+        assert_eq!("FRGHRQHWZRWKUHHIRXU", cipher.encrypt(plaintext).unwrap());
+        assert_eq!(
+            "CODEONETWOTHREEFOUR",
+            cipher.decrypt("FRGHRQHWZRWKUHHIRXU").unwrap()
+        );
+
+        let cipher = ShiftCipher::new(13)
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing);
+        assert_eq!("PBQR4567", cipher.encrypt(plaintext).unwrap());
+        assert_eq!("CODE1234", cipher.decrypt("PBQR4567").unwrap());
+
+        let cipher = ShiftCipher::new(3)
+            .unwrap()
+            .with_digits(DigitPolicy::Combined);
+        assert_eq!(cipher.alphabet().len(), 36);
+        assert_eq!("FRGH4567", cipher.encrypt(plaintext).unwrap());
+        assert_eq!("2CD", cipher.encrypt("Z9a").unwrap());
+        assert_eq!("CODE1234", cipher.decrypt("FRGH4567").unwrap());
+    }
+
     #[test]
     #[ignore]
     fn test_correct() {
@@ -124,11 +251,13 @@ mod tests {
 
         // it's actually just enough to check for shifts from 1 to 25...
         for shift in 0..=127 {
-            let cipher = ShiftCipher::new(shift);
+            let cipher = ShiftCipher::new(shift).unwrap();
 
             assert_eq!(
                 plaintext.to_uppercase(),
-                cipher.decrypt(&cipher.encrypt(&plaintext))
+                cipher
+                    .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                    .unwrap()
             );
         }
     }
@@ -0,0 +1,240 @@
+use crate::CipherError;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The four suits, in bridge order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    /// The letter the suit is written with.
+    pub fn letter(&self) -> char {
+        match self {
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+            Suit::Hearts => 'H',
+            Suit::Spades => 'S',
+        }
+    }
+}
+
+/// The rank of a card, from 1 (ace) to 13 (king): it can't be anything else, so every suited
+/// card is a real one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Rank(u8);
+
+impl Rank {
+    /// The rank with the given value, which must be between 1 (ace) and 13 (king).
+    pub fn new(rank: u8) -> Result<Rank, CipherError> {
+        if (1..=13).contains(&rank) {
+            Ok(Rank(rank))
+        } else {
+            Err(CipherError::InvalidKey(format!(
+                "the rank of a card must be between 1 and 13, got {}",
+                rank
+            )))
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// The character the rank is written with.
+    pub fn symbol(&self) -> char {
+        match self.0 {
+            1 => 'A',
+            10 => 'T',
+            11 => 'J',
+            12 => 'Q',
+            13 => 'K',
+            n => char::from(b'0' + n),
+        }
+    }
+}
+
+/// A card of the deck used by Solitaire: the 52 cards of the four suits plus the two jokers.
+///
+/// Cards are written as their rank (A, 2, ..., 9, T or 10, J, Q, K) followed by the suit (C, D, H,
+/// S), so e.g. "AC" is the ace of clubs and "TS" the ten of spades, while the jokers are "JA" and
+/// "JB".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Card {
+    /// A card of the given rank and suit.
+    Suited(Rank, Suit),
+    JokerA,
+    JokerB,
+}
+
+impl Card {
+    /// The card of the given rank (1 for the ace up to 13 for the king) and suit.
+    pub fn new(rank: u8, suit: Suit) -> Result<Card, CipherError> {
+        Ok(Card::Suited(Rank::new(rank)?, suit))
+    }
+
+    /// The card with the given number in bridge order (clubs 1-13, diamonds 14-26, hearts 27-39,
+    /// spades 40-52, then the A joker 53 and the B joker 54).
+    pub fn from_number(n: u32) -> Option<Card> {
+        match n {
+            1..=52 => Some(Card::Suited(
+                Rank(((n - 1) % 13 + 1) as u8),
+                Suit::ALL[((n - 1) / 13) as usize],
+            )),
+            53 => Some(Card::JokerA),
+            54 => Some(Card::JokerB),
+            _ => None,
+        }
+    }
+
+    /// The number of the card in bridge order (see `from_number`).
+    pub fn number(&self) -> u32 {
+        match self {
+            Card::Suited(rank, suit) => *suit as u32 * 13 + rank.value() as u32,
+            Card::JokerA => 53,
+            Card::JokerB => 54,
+        }
+    }
+
+    pub fn is_joker(&self) -> bool {
+        matches!(self, Card::JokerA | Card::JokerB)
+    }
+
+    /// The suit of the card (jokers have none).
+    pub fn suit(&self) -> Option<Suit> {
+        match self {
+            Card::Suited(_, suit) => Some(*suit),
+            _ => None,
+        }
+    }
+
+    /// The rank of the card, from 1 (ace) to 13 (king), if it isn't a joker.
+    pub fn rank(&self) -> Option<u8> {
+        match self {
+            Card::Suited(rank, _) => Some(rank.value()),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = CipherError;
+
+    /// Parses a card in the usual notation (e.g. "QH"), or its number in bridge order.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            CipherError::InvalidKey(format!(
+                "'{}' is not a card, expected e.g. AC, TD, QH, JA or a number between 1 and 54",
+                s
+            ))
+        };
+        let upper = s.trim().to_uppercase();
+
+        if let Ok(n) = upper.parse::<u32>() {
+            return Card::from_number(n).ok_or_else(invalid);
+        }
+
+        match upper.as_str() {
+            "JA" => return Ok(Card::JokerA),
+            "JB" => return Ok(Card::JokerB),
+            _ => (),
+        }
+
+        let mut chars = upper.chars();
+        let suit = match chars.next_back() {
+            Some('C') => Suit::Clubs,
+            Some('D') => Suit::Diamonds,
+            Some('H') => Suit::Hearts,
+            Some('S') => Suit::Spades,
+            _ => return Err(invalid()),
+        };
+        let rank = match chars.as_str() {
+            "A" => 1,
+            "T" | "10" => 10,
+            "J" => 11,
+            "Q" => 12,
+            "K" => 13,
+            r => match r.parse::<u8>() {
+                Ok(n) if (2..=9).contains(&n) => n,
+                _ => return Err(invalid()),
+            },
+        };
+
+        Card::new(rank, suit)
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Card::Suited(rank, suit) => write!(f, "{}{}", rank.symbol(), suit.letter()),
+            Card::JokerA => write!(f, "JA"),
+            Card::JokerB => write!(f, "JB"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers() {
+        for n in 1..=54 {
+            assert_eq!(n, Card::from_number(n).unwrap().number());
+        }
+
+        assert_eq!(
+            Some(Card::new(1, Suit::Clubs).unwrap()),
+            Card::from_number(1)
+        );
+        assert_eq!(
+            Some(Card::new(13, Suit::Diamonds).unwrap()),
+            Card::from_number(26)
+        );
+        assert_eq!(
+            Some(Card::new(1, Suit::Spades).unwrap()),
+            Card::from_number(40)
+        );
+        assert_eq!(None, Card::from_number(0));
+        assert_eq!(None, Card::from_number(55));
+    }
+
+    #[test]
+    fn test_notation() {
+        assert_eq!(Card::new(12, Suit::Hearts).unwrap(), "QH".parse().unwrap());
+        assert_eq!(Card::new(10, Suit::Spades).unwrap(), "10s".parse().unwrap());
+        assert_eq!(Card::new(10, Suit::Spades).unwrap(), "TS".parse().unwrap());
+        assert_eq!(Card::JokerB, "jb".parse().unwrap());
+        assert_eq!(Card::new(1, Suit::Clubs).unwrap(), "1".parse().unwrap());
+
+        assert!("1C".parse::<Card>().is_err());
+        assert!("ZC".parse::<Card>().is_err());
+        assert!("QX".parse::<Card>().is_err());
+        assert!("JC ".parse::<Card>().is_ok());
+        assert!("".parse::<Card>().is_err());
+
+        for n in 1..=54 {
+            let card = Card::from_number(n).unwrap();
+
+            assert_eq!(card, card.to_string().parse().unwrap());
+        }
+        assert_eq!("TD", Card::new(10, Suit::Diamonds).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_ranks() {
+        assert!(Card::new(0, Suit::Clubs).is_err());
+        assert!(Card::new(14, Suit::Hearts).is_err());
+        assert_eq!(Some(13), Card::new(13, Suit::Hearts).unwrap().rank());
+        assert_eq!(39, Card::new(13, Suit::Hearts).unwrap().number());
+        assert_eq!("7S", Card::new(7, Suit::Spades).unwrap().to_string());
+    }
+}
@@ -1,11 +1,28 @@
+use super::card::Card;
+use crate::keys::SolitaireKey;
+use crate::CipherError;
+
 use rand::seq::SliceRandom;
 use rand_pcg::Pcg64;
 use rand_seeder::Seeder;
 
+use std::fmt;
+use std::str::FromStr;
+
+/// The deck of a Solitaire cipher, from the top card to the bottom one.
+///
+/// It can be parsed from and printed as the list of its cards (e.g. "AC 2C ... KS JA JB"), which
+/// comes in handy when keying a physical deck by hand.
+///
+/// A deck keyed by a seed keeps the steps this crate has always used for seeds (swapping the
+/// jokers instead of moving them, and outputting jokers too), so that old ciphertexts can still
+/// be decrypted; every other deck follows Schneier's algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Deck {
     layout: Vec<u32>, // represent the cards by their bridge suits
-                      // A joker = 53
-                      // B joker = 54
+    // A joker = 53
+    // B joker = 54
+    legacy: bool,
 }
 
 impl Deck {
@@ -13,23 +30,96 @@ impl Deck {
     pub fn new() -> Self {
         let init_state = (1..=54).collect::<Vec<u32>>();
 
-        Deck { layout: init_state }
+        Deck {
+            layout: init_state,
+            legacy: false,
+        }
+    }
+
+    /// Initializes a deck with the given cards, from top to bottom (which must be a full deck,
+    /// as checked by `SolitaireKey::deck`).
+    fn from_cards(cards: &[Card]) -> Self {
+        assert_eq!(cards.len(), 54, "A deck must have 54 cards.");
+
+        Deck {
+            layout: cards.iter().map(Card::number).collect(),
+            legacy: false,
+        }
+    }
+
+    /// The cards of the deck, from top to bottom.
+    pub fn cards(&self) -> Vec<Card> {
+        self.layout
+            .iter()
+            .map(|&n| Card::from_number(n).expect("Every card must be between 1 and 54."))
+            .collect()
     }
 
-    /// Generates a single keystream value.
+    /// Initializes a deck keyed as required by the given key.
+    pub fn keyed(key: &SolitaireKey) -> Self {
+        match key {
+            SolitaireKey::Seed(seed) => {
+                let mut deck = Deck::new();
+                deck.key_deck(seed);
+                deck.legacy = true;
+
+                deck
+            }
+            SolitaireKey::Passphrase(passphrase) => {
+                let mut deck = Deck::new();
+                deck.key_with_passphrase(passphrase);
+
+                deck
+            }
+            SolitaireKey::Deck(cards) => Deck::from_cards(cards),
+        }
+    }
+
+    /// Generates a single keystream value (between 1 and 52).
     ///
     /// This function must be repeated for each plaintext/ciphertext character.
     pub fn get_output_card(&mut self) -> u32 {
-        self.swap_A_joker();
-        self.swap_B_joker();
+        loop {
+            self.step();
+
+            // a joker doesn't output anything, so we just go on
+            if let Some(card) = self.output_card() {
+                return card;
+            }
+        }
+    }
+
+    /// Runs the four steps that bring the deck to its next state.
+    pub fn step(&mut self) {
+        if self.legacy {
+            self.swap_A_joker_legacy();
+            self.swap_B_joker_legacy();
+        } else {
+            self.swap_A_joker();
+            self.swap_B_joker();
+        }
         self.triple_cut();
         self.count_cut();
+    }
+
+    /// Looks at the value of the top card and counts down that many cards: the one right below
+    /// is the output card, unless it is a joker.
+    ///
+    /// Seed-keyed decks never skip a step: a B joker on top is the output card itself, and any
+    /// other card counts down by its number (so jokers can come out too).
+    pub fn output_card(&self) -> Option<u32> {
+        if self.legacy {
+            let idx = self.layout[0] as usize;
+
+            return Some(if idx == 54 { 54 } else { self.layout[idx] });
+        }
 
-        let idx = self.layout[0] as usize;
-        if idx == 54 {
-            self.layout[0]
+        let card = self.layout[Deck::value(self.layout[0]) as usize];
+
+        if Deck::is_joker(card) {
+            None
         } else {
-            self.layout[idx]
+            Some(card)
         }
     }
 
@@ -46,49 +136,98 @@ impl Deck {
         }
     }
 
+    /// Keys the deck the way Schneier specifies: for each letter of the passphrase, the deck goes
+    /// through a full step and then gets count cut by the value of the letter (A = 1, B = 2, ...).
+    ///
+    /// Anything that isn't an ASCII letter is ignored.
+    pub fn key_with_passphrase(&mut self, passphrase: &str) {
+        for ch in passphrase.chars().filter(|ch| ch.is_ascii_alphabetic()) {
+            self.step();
+            self.count_cut_by(ch.to_ascii_uppercase() as u32 - 'A' as u32 + 1);
+        }
+    }
+
+    /// The value of a card when counting: its number in bridge order, or 53 for either joker.
+    fn value(card: u32) -> u32 {
+        card.min(53)
+    }
+
+    fn is_joker(card: u32) -> bool {
+        card == 53 || card == 54
+    }
+
+    /// Finds where the given card is in the deck.
+    fn position(&self, card: u32) -> usize {
+        self.layout
+            .iter()
+            .position(|v| *v == card)
+            .expect("Every card must be in the deck.")
+    }
+
+    /// Moves the card at idx down by n places, treating the deck as circular (a card moving past
+    /// the bottom card ends up below the top card, never on top of the deck).
+    fn move_down(&mut self, idx: usize, n: usize) {
+        let card = self.layout.remove(idx);
+        let mut new_idx = idx + n;
+
+        if new_idx > self.layout.len() {
+            new_idx -= self.layout.len();
+        }
+
+        self.layout.insert(new_idx, card);
+    }
+
     /// First step of keystream preparation.
     ///
+    /// We find the A joker (53) and move it one card down.
+    /// If the joker is at the bottom we move it below the top card.
+    #[allow(non_snake_case)]
+    fn swap_A_joker(&mut self) {
+        let idx = self.position(53);
+
+        self.move_down(idx, 1);
+    }
+
+    /// First step of keystream preparation for seed-keyed decks.
+    ///
     /// We find the A joker (53) and swap it with the card beneath it.
     /// If the joker is at the bottom we swap it with the first card in the deck.
     #[allow(non_snake_case)]
-    fn swap_A_joker(&mut self) {
-        for (i, v) in self.layout.iter().enumerate() {
-            if *v == 53 {
-                if i == 53 {
-                    // joker A at bottom
-                    self.layout.swap(i, 0);
-                } else {
-                    // joker A somewhere in the middle
-                    self.layout.swap(i, i + 1);
-                }
+    fn swap_A_joker_legacy(&mut self) {
+        let idx = self.position(53);
 
-                break;
-            }
+        if idx == 53 {
+            self.layout.swap(idx, 0);
+        } else {
+            self.layout.swap(idx, idx + 1);
         }
     }
 
     /// Second step of keystream preparation.
     ///
-    /// We find the B joker (53) and swap it with the card that is two cards beneath it.
+    /// We find the B joker (54) and move it two cards down.
     /// If the joker is the bottom card, move it below the second card of the deck.
     /// If the joker is the second to last card, move it below the top card of the deck.
     #[allow(non_snake_case)]
     fn swap_B_joker(&mut self) {
-        for (i, v) in self.layout.iter().enumerate() {
-            if *v == 54 {
-                if i == 53 {
-                    // joker B at bottom
-                    self.layout.swap(i, 2);
-                } else if i == 52 {
-                    // joker B secont to last
-                    self.layout.swap(i, 1);
-                } else {
-                    // joker B somewhere in the middle
-                    self.layout.swap(i, i + 2);
-                }
+        let idx = self.position(54);
 
-                break;
-            }
+        self.move_down(idx, 2);
+    }
+
+    /// Second step of keystream preparation for seed-keyed decks.
+    ///
+    /// We find the B joker (54) and swap it with the card that is two cards beneath it.
+    /// If the joker is the bottom card, swap it with the third card of the deck.
+    /// If the joker is the second to last card, swap it with the second card of the deck.
+    #[allow(non_snake_case)]
+    fn swap_B_joker_legacy(&mut self) {
+        let idx = self.position(54);
+
+        match idx {
+            53 => self.layout.swap(idx, 2),
+            52 => self.layout.swap(idx, 1),
+            _ => self.layout.swap(idx, idx + 2),
         }
     }
 
@@ -103,19 +242,16 @@ impl Deck {
     ///                                                   B := cards below the second joker, J2
     /// Then, after a triple cut, the deck will appear as such: 'B J1 M J2 A'.
     fn triple_cut(&mut self) {
-        let fj_idx; // fj = first joker
-        let sj_idx; // sj = second joker
-
         let old_layout = &self.layout;
 
-        fj_idx = Deck::find_first_joker_index(old_layout);
+        let fj_idx = Deck::find_first_joker_index(old_layout); // fj = first joker
         let (above_first, rest) = old_layout.split_at(fj_idx);
 
         // XXX ugly but necessary (for now)
         let mut r_v = rest.to_vec();
         let joker = r_v.remove(0);
 
-        sj_idx = Deck::find_first_joker_index(&r_v);
+        let sj_idx = Deck::find_first_joker_index(&r_v); // sj = second joker
         let (mid, below_second) = r_v.split_at(sj_idx + 1);
         // XXX
 
@@ -146,15 +282,18 @@ impl Deck {
     /// bottom card
     /// Then, after a count cut, the layout of the deck will be 'c5, c6, ..., c53, c1, ..., c4, 4'
     fn count_cut(&mut self) {
-        // if the bottom card is the joker there's no need to cut
-        if *self.layout.last().unwrap() == 53 || *self.layout.last().unwrap() == 54 {
-            return;
-        }
+        let bottom_card = *self.layout.last().unwrap();
+
+        self.count_cut_by(Deck::value(bottom_card));
+    }
 
+    /// Performs a count cut by n, i.e. moves the n cards at the top of the deck right above the
+    /// bottom card (if n is 53 there is nothing to do).
+    fn count_cut_by(&mut self, n: u32) {
         let bottom_card = self.layout.pop().unwrap();
 
         let cur_layout = &self.layout;
-        let (above_card, rest) = cur_layout.split_at(bottom_card as usize); // we have to cut AFTER the card
+        let (above_card, rest) = cur_layout.split_at(n as usize); // we have to cut AFTER the card
 
         let mut new_layout: Vec<u32> = Vec::new();
 
@@ -170,7 +309,7 @@ impl Deck {
 
     /// Finds the index corresponding to the first occurence of the joker
     /// given a certain deck, regardless of whether the joker is A or B.
-    fn find_first_joker_index(deck: &Vec<u32>) -> usize {
+    fn find_first_joker_index(deck: &[u32]) -> usize {
         let mut joker_idx: usize = 0;
 
         for (i, v) in deck.iter().enumerate() {
@@ -185,6 +324,33 @@ impl Deck {
     }
 }
 
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
+    }
+}
+
+impl FromStr for Deck {
+    type Err = CipherError;
+
+    /// Parses the cards of a full deck, from top to bottom (see `SolitaireKey::deck`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Deck::keyed(&SolitaireKey::deck(s)?))
+    }
+}
+
+impl fmt::Display for Deck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards = self
+            .cards()
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>();
+
+        write!(f, "{}", cards.join(" "))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,17 +367,13 @@ mod tests {
 
         // a single false means that a value is missing
         // => the deck is not "proper"
-        if s.contains(&false) {
-            false
-        } else {
-            true
-        }
+        !s.contains(&false)
     }
 
     #[test]
     fn test_keying() {
         let mut layouts: Vec<Vec<u32>> = Vec::new();
-        let seeds = vec!["one", "two", "testing"];
+        let seeds = ["one", "two", "testing"];
 
         for s in seeds.iter() {
             let mut deck = Deck::new();
@@ -240,13 +402,15 @@ mod tests {
 
         // joker A at bottom
         deck.swap_A_joker();
-        assert_eq!(deck.layout[0], 53);
-        assert_eq!(deck.layout[53], 1);
+        assert_eq!(deck.layout[1], 53);
+        assert_eq!(deck.layout[0], 1);
+        assert_eq!(deck.layout[2], 2);
+        assert_eq!(deck.layout[53], 54);
 
-        // joker A at top
+        // joker A in the middle
         deck.swap_A_joker();
-        assert_eq!(deck.layout[1], 53);
-        assert_eq!(deck.layout[0], 2);
+        assert_eq!(deck.layout[2], 53);
+        assert_eq!(deck.layout[1], 2);
     }
 
     #[test]
@@ -257,7 +421,8 @@ mod tests {
         // joker B at bottom
         deck.swap_B_joker();
         assert_eq!(deck.layout[2], 54);
-        assert_eq!(deck.layout[53], 3);
+        assert_eq!(deck.layout[3], 3);
+        assert_eq!(deck.layout[53], 53);
 
         // joker B second to last
         deck.layout.swap(2, 52);
@@ -265,12 +430,14 @@ mod tests {
 
         deck.swap_B_joker();
         assert_eq!(deck.layout[1], 54);
-        assert_eq!(deck.layout[52], 2);
+        assert_eq!(deck.layout[2], 2);
+        assert_eq!(deck.layout[53], 53);
 
-        // joker B at top
+        // joker B in the middle: the two cards below it move up, in order
         deck.swap_B_joker();
+        assert_eq!(deck.layout[1], 2);
+        assert_eq!(deck.layout[2], 52);
         assert_eq!(deck.layout[3], 54);
-        assert_eq!(deck.layout[1], 4);
     }
 
     #[test]
@@ -391,4 +558,60 @@ mod tests {
         assert!(is_proper_deck(&deck));
         assert_eq!(deck.layout, old_layout);
     }
+
+    #[test]
+    fn test_unkeyed_keystream() {
+        // from https://www.schneier.com/wp-content/uploads/2015/12/sol-test-1.txt
+        let mut deck = Deck::new();
+        let expected = [4, 49, 10, 24, 8, 51, 44, 6, 4, 33, 20, 39, 19, 34, 42];
+
+        for card in expected {
+            assert_eq!(card, deck.get_output_card());
+            assert!(is_proper_deck(&deck));
+        }
+    }
+
+    #[test]
+    fn test_passphrase_keying() {
+        let mut deck = Deck::new();
+        deck.key_with_passphrase("");
+        assert_eq!(deck.layout, Deck::new().layout);
+
+        let mut deck = Deck::new();
+        deck.key_with_passphrase("f o");
+        assert!(is_proper_deck(&deck));
+
+        let mut same = Deck::new();
+        same.key_with_passphrase("FO");
+        assert_eq!(deck.layout, same.layout);
+    }
+
+    #[test]
+    fn test_keyed_by_layout() {
+        let layout = (1..=54).rev().collect::<Vec<u32>>();
+        let cards = layout
+            .iter()
+            .map(|&n| Card::from_number(n).unwrap())
+            .collect::<Vec<Card>>();
+        let deck = Deck::keyed(&SolitaireKey::Deck(cards.clone()));
+
+        assert_eq!(deck.layout, layout);
+        assert_eq!(deck.cards(), cards);
+    }
+
+    #[test]
+    fn test_notation() {
+        let deck = Deck::new();
+        let notation = deck.to_string();
+
+        assert!(notation.starts_with("AC 2C 3C 4C 5C 6C 7C 8C 9C TC JC QC KC AD"));
+        assert!(notation.ends_with("QS KS JA JB"));
+        assert_eq!(deck, notation.parse().unwrap());
+
+        let mut keyed = Deck::new();
+        keyed.key_with_passphrase("CRYPTONOMICON");
+        assert_eq!(keyed, keyed.to_string().parse().unwrap());
+
+        assert!("AC 2C JA JB".parse::<Deck>().is_err());
+    }
 }
@@ -0,0 +1,124 @@
+use super::card::Card;
+use super::card_deck::Deck;
+use crate::keys::SolitaireKey;
+
+/// The keystream generator of the Solitaire cipher, yielding values between 1 and 26 (the output
+/// card in bridge order, modulo 26, so that the ace of clubs and the ace of diamonds are both 1).
+///
+/// Following Schneier's rule, a step whose output card is a joker yields nothing and the deck
+/// just moves on to the next one (use `step` to look at every single step instead), except for
+/// decks keyed by a seed, which keep their original steps. The stream never ends, and cloning it
+/// gives a snapshot that carries on independently from the same point, e.g.
+///
+/// ```
+/// use classical_cryptography::SolitaireKeystream;
+///
+/// let mut keystream = SolitaireKeystream::new(&"pass:".parse().unwrap());
+/// let first = keystream.by_ref().take(3).collect::<Vec<u8>>();
+/// let snapshot = keystream.clone();
+///
+/// assert_eq!(first, vec![4, 23, 10]);
+/// assert!(keystream.take(5).eq(snapshot.take(5)));
+/// ```
+///
+/// The values can be added to the letters of any text (A = 1, ..., Z = 26) like the shifts of an
+/// additive cipher, which is exactly what `SolitaireCipher` does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolitaireKeystream {
+    deck: Deck,
+}
+
+impl SolitaireKeystream {
+    /// Creates the keystream of a deck keyed with the given key.
+    pub fn new(key: &SolitaireKey) -> Self {
+        SolitaireKeystream::from_deck(Deck::keyed(key))
+    }
+
+    /// Creates the keystream starting from the given deck.
+    pub fn from_deck(deck: Deck) -> Self {
+        SolitaireKeystream { deck }
+    }
+
+    /// The current state of the deck.
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    /// Generates the next output card (never a joker, unless the deck was keyed by a seed), for
+    /// those who need more than the value modulo 26.
+    pub fn next_card(&mut self) -> Card {
+        Card::from_number(self.deck.get_output_card()).expect("The output must be a card.")
+    }
+
+    /// Runs a single step of the generator, returning its output card (None if it is a joker,
+    /// in which case Schneier's rule says to skip it).
+    pub fn step(&mut self) -> Option<Card> {
+        self.deck.step();
+
+        self.deck.output_card().and_then(Card::from_number)
+    }
+}
+
+impl Iterator for SolitaireKeystream {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(((self.next_card().number() - 1) % 26 + 1) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_keystream() {
+        // from https://www.schneier.com/wp-content/uploads/2015/12/sol-test-1.txt
+        let keystream = SolitaireKeystream::new(&"pass:".parse().unwrap());
+        let expected = [4, 23, 10, 24, 8, 25, 18, 6, 4, 7, 20, 13, 19, 8, 16];
+
+        assert_eq!(expected.to_vec(), keystream.take(15).collect::<Vec<u8>>());
+
+        let keystream = SolitaireKeystream::new(&"pass:f".parse().unwrap());
+        let expected = [23, 24, 8, 20, 16, 1, 12, 7, 10, 10, 9, 1, 4, 6, 24];
+
+        assert_eq!(expected.to_vec(), keystream.take(15).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut keystream = SolitaireKeystream::new(&"cards upon cards".parse().unwrap());
+        keystream.by_ref().take(20).for_each(drop);
+
+        let snapshot = keystream.clone();
+        let deck = keystream.deck().clone();
+        let ahead = keystream.by_ref().take(30).collect::<Vec<u8>>();
+
+        assert_eq!(ahead, snapshot.take(30).collect::<Vec<u8>>());
+        assert_eq!(
+            ahead,
+            SolitaireKeystream::from_deck(deck)
+                .take(30)
+                .collect::<Vec<u8>>()
+        );
+        assert!(ahead.iter().all(|value| (1..=26).contains(value)));
+    }
+
+    #[test]
+    fn test_skipping_jokers() {
+        let mut stepping = SolitaireKeystream::new(&"pass:".parse().unwrap());
+        let mut keystream = stepping.clone();
+        let mut jokers = 0;
+
+        for _ in 0..100 {
+            match stepping.step() {
+                Some(card) => assert_eq!(card, keystream.next_card()),
+                None => jokers += 1,
+            }
+        }
+
+        // the jokers show up every now and then, but never in the keystream
+        assert!(jokers > 0);
+        assert_eq!(stepping, keystream);
+    }
+}
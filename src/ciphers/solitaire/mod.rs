@@ -1,4 +1,12 @@
+pub mod card;
+pub mod keystream;
+#[allow(clippy::module_inception)]
 pub mod solitaire;
 
-// accessible only by modules within solitaire
+pub use self::card::{Card, Rank, Suit};
+pub use self::card_deck::Deck;
+pub use self::keystream::SolitaireKeystream;
+pub use self::solitaire::SolitaireCipher;
+
+// accessible only by modules within solitaire (the deck itself is re-exported above)
 mod card_deck;
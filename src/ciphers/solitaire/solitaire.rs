@@ -1,80 +1,258 @@
-use super::card_deck::Deck;
+use super::keystream::SolitaireKeystream;
 // encryption/decryption functions just like Vigenere => repeated shift cipher
-use super::super::shift::ShiftCipher;
-use crate::Cipher;
+use super::super::utils::{clean, substitute, Ring, DIGITS};
+use crate::keys::SolitaireKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
 
 pub struct SolitaireCipher {
-    key: String,
+    key: SolitaireKey,
+    alphabet: Alphabet,
+    mode: TextMode,
+    digits: DigitPolicy,
 }
 
 impl SolitaireCipher {
-    /// Create a new solitarie cipher given the key (the seed needed to randomize the initial deck
-    /// configuration).
-    pub fn new(key: &str) -> Self {
-        SolitaireCipher {
-            key: key.to_string(),
-        }
+    /// Create a new solitarie cipher given the key: the seed needed to randomize the initial deck
+    /// configuration, a passphrase ("pass:...") or an explicit deck order ("deck:...").
+    ///
+    /// Only the latter two are compatible with other Solitaire implementations.
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(SolitaireCipher::from_key(key.parse()?))
     }
-}
 
-impl Cipher for SolitaireCipher {
-    fn encrypt(&self, plaintext: &str) -> String {
-        let clean_plaintext = <SolitaireCipher as Cipher>::clean_input(plaintext);
-        let mut ciphertext: String = String::new();
-        let mut deck = Deck::new();
+    /// Create a new solitaire cipher with an already validated key.
+    pub fn from_key(key: SolitaireKey) -> Self {
+        SolitaireCipher::with_alphabet(key, Alphabet::english())
+    }
 
-        // only key deck once!
-        deck.key_deck(&self.key);
+    /// Create a new solitaire cipher working on the given alphabet (the keystream values are
+    /// reduced modulo its size).
+    pub fn with_alphabet(key: SolitaireKey, alphabet: Alphabet) -> Self {
+        SolitaireCipher {
+            key,
+            alphabet,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        }
+    }
 
-        for ch in clean_plaintext.chars() {
-            let stream_value = deck.get_output_card();
-            let enc_char = ShiftCipher::shift_by(stream_value as i8, ch);
+    /// Sets how the characters that are not part of the alphabet are treated (and whether the
+    /// keystream advances over them).
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
 
-            ciphertext.push(enc_char);
+    /// Sets what happens to the digits (with `DigitPolicy::Combined` they are appended to the
+    /// alphabet, with `DigitPolicy::SeparateRing` the keystream values are reduced modulo 10 for
+    /// them).
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        if digits == DigitPolicy::Combined {
+            self.alphabet = self.alphabet.with_digits();
         }
+        self.digits = digits;
+        self
+    }
 
-        ciphertext
+    pub fn key(&self) -> &SolitaireKey {
+        &self.key
     }
 
-    fn decrypt(&self, ciphertext: &str) -> String {
-        let clean_ciphertext = <SolitaireCipher as Cipher>::clean_input(ciphertext);
-        let mut plaintext: String = String::new();
-        let mut deck = Deck::new();
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
 
+    /// Runs the keystream over the text, with combine mixing the index of each symbol with the
+    /// keystream value for its position, reduced modulo the size of the symbol's ring (which is
+    /// also passed along).
+    fn apply<F>(&self, text: &str, combine: F) -> Result<String, CipherError>
+    where
+        F: Fn(usize, usize, usize) -> usize,
+    {
         // only key deck once!
-        deck.key_deck(&self.key);
+        let mut keystream = SolitaireKeystream::new(&self.key);
+        let mut drawn = 0;
+        let mut card = 0;
 
-        for ch in clean_ciphertext.chars() {
-            let stream_value = deck.get_output_card();
-            // note the minus sign
-            let dec_char = ShiftCipher::shift_by(-(stream_value as i8), ch);
+        substitute(
+            text,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            |idx, pos, ring| {
+                // every position in the text gets its own keystream value
+                while drawn <= pos {
+                    card = keystream.next_card().number() as usize;
+                    drawn += 1;
+                }
 
-            plaintext.push(dec_char);
-        }
+                let size = match ring {
+                    Ring::Symbols => self.alphabet.len(),
+                    Ring::Digits => DIGITS,
+                };
+                combine(idx, card % size, size)
+            },
+        )
+    }
+}
+
+impl Cipher for SolitaireCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
+
+        self.apply(&clean_plaintext, |idx, stream_value, _| idx + stream_value)
+    }
 
-        plaintext
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
+
+        // note the minus sign
+        self.apply(&clean_ciphertext, |idx, stream_value, size| {
+            idx + size - stream_value
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::VigenereCipher;
     use rand::seq::SliceRandom;
 
+    #[test]
+    fn test_known_pairs() {
+        // from https://www.schneier.com/wp-content/uploads/2015/12/sol-test-1.txt
+        let vectors = [
+            ("pass:", "AAAAAAAAAAAAAAA", "EXKYIZSGEHUNTIQ"),
+            ("pass:f", "AAAAAAAAAAAAAAA", "XYIUQBMHKKJBEGY"),
+            ("pass:fo", "AAAAAAAAAAAAAAA", "TUJYMBERLGXNDIW"),
+            ("pass:cryptonomicon", "SOLITAIREX", "KIRAKSFJAN"),
+        ];
+
+        for (key, plaintext, ciphertext) in vectors {
+            let cipher = SolitaireCipher::new(key).unwrap();
+
+            assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+            assert_eq!(plaintext, cipher.decrypt(ciphertext).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_seed_compatibility() {
+        // seeds must keep giving what they always gave, so that old messages can be decrypted
+        let cipher = SolitaireCipher::new("mysecret").unwrap();
+
+        assert_eq!("KGEFKYHUGKMV", cipher.encrypt("attackatdawn").unwrap());
+        assert_eq!("ATTACKATDAWN", cipher.decrypt("KGEFKYHUGKMV").unwrap());
+    }
+
+    #[test]
+    fn test_deck_order() {
+        // the unkeyed deck, written out in full
+        let order = (1..=54)
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>();
+        let cipher = SolitaireCipher::new(&format!("deck:{}", order.join(" "))).unwrap();
+
+        assert_eq!(
+            "EXKYIZSGEHUNTIQ",
+            cipher.encrypt("AAAAAAAAAAAAAAA").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_keystream_as_key() {
+        // the keystream can drive any additive cipher: used as the keyword of a Vigenere cipher
+        // as long as the text, it gives the same ciphertext as Solitaire itself
+        let plaintext = "DONOTUSEPCDOESNOTSAYAFTERBUT";
+        let keyword = SolitaireKeystream::new(&"pass:cryptonomicon".parse().unwrap())
+            .take(plaintext.len())
+            .map(|value| (b'A' + value % 26) as char)
+            .collect::<String>();
+        let vigenere = VigenereCipher::new(&keyword).unwrap();
+
+        let cipher = SolitaireCipher::new("pass:cryptonomicon").unwrap();
+        assert_eq!(
+            cipher.encrypt(plaintext).unwrap(),
+            vigenere.encrypt(plaintext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_other_alphabets() {
+        let plaintext = "Arma virumque cano, Troiae qui primus ab oris";
+        let cipher = SolitaireCipher::with_alphabet("aeneid".parse().unwrap(), Alphabet::latin());
+
+        let ciphertext = cipher.encrypt(plaintext).unwrap();
+        assert!(ciphertext.chars().all(|ch| Alphabet::latin().contains(ch)));
+        assert_eq!(
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let plaintext = "Meet me at 1530";
+
+        for digits in [DigitPolicy::SeparateRing, DigitPolicy::Combined] {
+            let cipher = SolitaireCipher::new("cards upon cards")
+                .unwrap()
+                .with_digits(digits);
+            let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+            assert_eq!("MEETMEAT1530", cipher.decrypt(&ciphertext).unwrap());
+        }
+
+        // the letters don't depend on the way the digits are handled
+        let cipher = SolitaireCipher::new("cards upon cards").unwrap();
+        let passing = cipher.encrypt(plaintext).unwrap();
+        let separate = cipher
+            .with_digits(DigitPolicy::SeparateRing)
+            .encrypt(plaintext)
+            .unwrap();
+        assert_eq!(passing[..8], separate[..8]);
+        assert_eq!("1530", &passing[8..]);
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let plaintext = "Meet me at the usual place, at noon.";
+        let cipher = SolitaireCipher::new("cards upon cards").unwrap();
+        let clean_ciphertext = cipher.encrypt(plaintext).unwrap();
+
+        // skipping the punctuation doesn't change the keystream used for the letters
+        let preserving = SolitaireCipher::new("cards upon cards")
+            .unwrap()
+            .with_mode(TextMode::preserve_format());
+        let ciphertext = preserving.encrypt(plaintext).unwrap();
+
+        assert_eq!(plaintext.len(), ciphertext.len());
+        assert_eq!(clean_ciphertext, cipher.clean_input(&ciphertext));
+        assert_eq!(plaintext, preserving.decrypt(&ciphertext).unwrap());
+    }
+
     #[test]
     #[ignore]
     fn test_correct() {
-        let keys = vec!["lkajhfd", "lslsl s asd", "lk5y&/_7t274otg", "@#@@[^^∏ß"];
+        let keys = ["lkajhfd", "lslsl s asd", "lk5y&/_7t274otg", "@#@@[^^∏ß"];
         let plaintext = String::from("cardgames");
 
         for _ in 0..100 {
             let choice = keys.choose(&mut rand::thread_rng()).unwrap();
 
-            let cipher = SolitaireCipher::new(*choice);
+            let cipher = SolitaireCipher::new(choice).unwrap();
             for _ in 0..10 {
                 assert_eq!(
                     plaintext.to_uppercase(),
-                    cipher.decrypt(&cipher.encrypt(&plaintext))
+                    cipher
+                        .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                        .unwrap()
                 );
             }
         }
@@ -0,0 +1,270 @@
+/// The core shared by the transposition ciphers.
+///
+/// The text is written row by row into a grid with a fixed number of columns (the last row being
+/// possibly incomplete), and the ciphertext is obtained by reading the cells back in a different
+/// order, skipping the empty ones. Decryption simply puts every character back in its cell.
+use super::utils::handle_digits;
+use crate::text::DigitPolicy;
+use crate::{Alphabet, CipherError};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The order in which the cells of the grid are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Route {
+    /// Column by column, top to bottom, left to right.
+    Columns,
+    /// Clockwise, from the top left corner inwards.
+    Spiral,
+    /// Column by column, going down the first one, up the second one and so on.
+    Snake,
+    /// Along the diagonals going up and to the right, starting from the top left corner.
+    Diagonal,
+}
+
+impl FromStr for Route {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "columns" => Ok(Route::Columns),
+            "spiral" => Ok(Route::Spiral),
+            "snake" => Ok(Route::Snake),
+            "diagonal" => Ok(Route::Diagonal),
+            _ => Err(CipherError::InvalidKey(format!(
+                "unknown route '{}', expected one of columns, spiral, snake, diagonal",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Route::Columns => "columns",
+            Route::Spiral => "spiral",
+            Route::Snake => "snake",
+            Route::Diagonal => "diagonal",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A grid holding a text of a given length, written row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Grid {
+    len: usize,
+    columns: usize,
+    rows: usize,
+}
+
+impl Grid {
+    /// Creates the grid for a text of len characters, written in rows of the given number of
+    /// columns (which the keys make sure is greater than 0).
+    ///
+    /// A grid wider than the text is a single row, which reads the same whatever its width: the
+    /// columns past the end of the text are dropped, so that huge keys cost nothing.
+    pub fn new(len: usize, columns: usize) -> Self {
+        assert!(columns > 0, "A grid must have at least one column.");
+        let columns = columns.min(len.max(1));
+
+        Grid {
+            len,
+            columns,
+            rows: len.div_ceil(columns),
+        }
+    }
+
+    /// The position in the text of the character in the given cell (if the cell isn't empty).
+    fn cell(&self, row: usize, column: usize) -> Option<usize> {
+        let idx = row * self.columns + column;
+
+        if column < self.columns && idx < self.len {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+
+    /// The positions of the characters read column by column, taking the columns in the given
+    /// order.
+    pub fn by_columns(&self, order: &[usize]) -> Vec<usize> {
+        order
+            .iter()
+            .flat_map(|&column| (0..self.rows).filter_map(move |row| self.cell(row, column)))
+            .collect()
+    }
+
+    /// The positions of the characters read along the given route.
+    pub fn by_route(&self, route: Route) -> Vec<usize> {
+        let (rows, columns) = (self.rows, self.columns);
+        if rows == 0 {
+            return Vec::new();
+        }
+
+        let cells: Vec<(usize, usize)> = match route {
+            Route::Columns => return self.by_columns(&(0..columns).collect::<Vec<usize>>()),
+            Route::Snake => (0..columns)
+                .flat_map(|column| {
+                    (0..rows).map(move |row| match column % 2 {
+                        0 => (row, column),
+                        _ => (rows - 1 - row, column),
+                    })
+                })
+                .collect(),
+            Route::Diagonal => (0..(rows + columns).saturating_sub(1))
+                .flat_map(|diagonal| {
+                    let first_row = diagonal.saturating_sub(columns - 1);
+                    let last_row = diagonal.min(rows - 1);
+
+                    (first_row..=last_row)
+                        .rev()
+                        .map(move |row| (row, diagonal - row))
+                })
+                .collect(),
+            Route::Spiral => Grid::spiral(rows, columns),
+        };
+
+        cells
+            .into_iter()
+            .filter_map(|(row, column)| self.cell(row, column))
+            .collect()
+    }
+
+    /// All the cells of a rows x columns grid, clockwise from the top left corner inwards.
+    fn spiral(rows: usize, columns: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::with_capacity(rows * columns);
+        let (mut top, mut left) = (0, 0);
+        let (mut bottom, mut right) = (rows, columns);
+
+        while top < bottom && left < right {
+            cells.extend((left..right).map(|column| (top, column)));
+            cells.extend((top + 1..bottom).map(|row| (row, right - 1)));
+            if bottom - top > 1 {
+                cells.extend((left..right - 1).rev().map(|column| (bottom - 1, column)));
+            }
+            if right - left > 1 {
+                cells.extend((top + 1..bottom - 1).rev().map(|row| (row, left)));
+            }
+
+            top += 1;
+            left += 1;
+            bottom -= 1;
+            right -= 1;
+        }
+
+        cells
+    }
+}
+
+/// Reads the characters of the text in the given order (a permutation of their positions).
+pub fn transpose(text: &[char], order: &[usize]) -> String {
+    order.iter().map(|&idx| text[idx]).collect()
+}
+
+/// Puts every character of the text back where transpose took it from.
+pub fn untranspose(text: &[char], order: &[usize]) -> String {
+    let mut original = vec![' '; text.len()];
+
+    for (ch, &idx) in text.iter().zip(order) {
+        original[idx] = *ch;
+    }
+
+    original.into_iter().collect()
+}
+
+/// Cleans up the input of a transposition cipher: only letters and digits are kept (the latter
+/// according to the digit policy), in uppercase.
+pub fn clean(input: &str, digits: DigitPolicy) -> String {
+    handle_digits(input, &Alphabet::english(), digits)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Ranks the symbols of a key alphabetically (ties are broken by position), giving the order in
+/// which the columns are read, e.g. "ZEBRAS" gives [4, 2, 1, 3, 5, 0] (A first, then B, ...).
+pub fn column_order(key: &str) -> Vec<usize> {
+    let symbols = key.chars().collect::<Vec<char>>();
+    let mut order = (0..symbols.len()).collect::<Vec<usize>>();
+
+    order.sort_by_key(|&i| (symbols[i], i));
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str, columns: usize, route: Route) -> String {
+        let chars = text.chars().collect::<Vec<char>>();
+        let order = Grid::new(chars.len(), columns).by_route(route);
+
+        transpose(&chars, &order)
+    }
+
+    #[test]
+    fn test_routes() {
+        // ABC
+        // DEF
+        // GH
+        let text = "ABCDEFGH";
+
+        assert_eq!("ADGBEHCF", read(text, 3, Route::Columns));
+        assert_eq!("ADGHEBCF", read(text, 3, Route::Snake));
+        assert_eq!("ADBGECHF", read(text, 3, Route::Diagonal));
+        assert_eq!("ABCFHGDE", read(text, 3, Route::Spiral));
+    }
+
+    #[test]
+    fn test_spiral() {
+        assert_eq!(
+            vec![(0, 0), (0, 1), (0, 2), (1, 2), (1, 1), (1, 0)],
+            Grid::spiral(2, 3)
+        );
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0)], Grid::spiral(3, 1));
+        assert_eq!(Grid::spiral(5, 4).len(), 20);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG"
+            .chars()
+            .collect::<Vec<char>>();
+        let routes = [Route::Columns, Route::Spiral, Route::Snake, Route::Diagonal];
+
+        for len in 0..=text.len() {
+            for columns in 1..=len + 1 {
+                for route in routes {
+                    let order = Grid::new(len, columns).by_route(route);
+                    let transposed = transpose(&text[..len], &order)
+                        .chars()
+                        .collect::<Vec<char>>();
+
+                    assert_eq!(
+                        text[..len].iter().collect::<String>(),
+                        untranspose(&transposed, &order)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_order() {
+        assert_eq!(vec![4, 2, 1, 3, 5, 0], column_order("ZEBRAS"));
+        assert_eq!(vec![1, 0, 2], column_order("BAB"));
+    }
+
+    #[test]
+    fn test_route_names() {
+        assert_eq!(Route::Spiral, "Spiral".parse::<Route>().unwrap());
+        assert_eq!("snake", Route::Snake.to_string());
+        assert!("zigzag".parse::<Route>().is_err());
+    }
+}
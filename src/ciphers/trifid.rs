@@ -0,0 +1,172 @@
+use super::polybius::{fractionate, keyed_symbols, unfractionate};
+use super::utils::clean;
+use crate::keys::TrifidKey;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
+
+/// The 27 symbols filling the cube: the letters and +.
+pub const CUBE_SYMBOLS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ+";
+
+/// The Trifid cipher, Bifid's three-dimensional sibling: every symbol is told by its layer, row
+/// and column in a 3×3×3 cube, and those are fractionated the same way.
+pub struct TrifidCipher {
+    key: TrifidKey,
+    alphabet: Alphabet,
+    /// The symbols of the cube, layer by layer and row by row.
+    symbols: Vec<char>,
+    digits: DigitPolicy,
+}
+
+impl TrifidCipher {
+    /// Initializes a Trifid cipher given the key (see TrifidKey).
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(TrifidCipher::from_key(key.parse()?))
+    }
+
+    /// Initializes a Trifid cipher with an already validated key.
+    pub fn from_key(key: TrifidKey) -> Self {
+        let alphabet = Alphabet::new(CUBE_SYMBOLS).unwrap();
+
+        TrifidCipher {
+            symbols: keyed_symbols(key.keyword(), &alphabet),
+            alphabet,
+            key,
+            digits: DigitPolicy::default(),
+        }
+    }
+
+    /// Sets what happens to the digits, which have no place in the cube: neither
+    /// `DigitPolicy::Combined` nor `DigitPolicy::SeparateRing` are supported.
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Result<Self, CipherError> {
+        if let DigitPolicy::Combined | DigitPolicy::SeparateRing = digits {
+            return Err(CipherError::InvalidKey(
+                "the Trifid cipher can't encrypt the digits, its cube only holds the letters and +"
+                    .to_string(),
+            ));
+        }
+        self.digits = digits;
+
+        Ok(self)
+    }
+
+    pub fn key(&self) -> &TrifidKey {
+        &self.key
+    }
+
+    /// The symbols of the cube, layer by layer and row by row.
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    /// The layer, row and column of the given symbol.
+    fn coordinates(&self, ch: char) -> Vec<usize> {
+        let cell = self
+            .symbols
+            .iter()
+            .position(|&symbol| symbol == ch)
+            .unwrap();
+
+        vec![cell / 9, cell / 3 % 3, cell % 3]
+    }
+
+    /// Replaces the symbols of the text, a group at a time, with those found at the coordinates
+    /// returned by f (leaving the digits passed through where they are).
+    fn transform(&self, text: &str, f: fn(&[Vec<usize>]) -> Vec<Vec<usize>>) -> String {
+        let mut chars = text.chars().collect::<Vec<char>>();
+        let positions = (0..chars.len())
+            .filter(|pos| self.alphabet.contains(chars[*pos]))
+            .collect::<Vec<usize>>();
+        let period = self.key.period().unwrap_or(positions.len()).max(1);
+
+        for group in positions.chunks(period) {
+            let coordinates = group
+                .iter()
+                .map(|pos| self.coordinates(chars[*pos]))
+                .collect::<Vec<Vec<usize>>>();
+
+            for (pos, cell) in group.iter().zip(f(&coordinates)) {
+                chars[*pos] = self.symbols[cell[0] * 9 + cell[1] * 3 + cell[2]];
+            }
+        }
+
+        chars.into_iter().collect()
+    }
+}
+
+impl Cipher for TrifidCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, TextMode::Clean, self.digits)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(self.transform(&self.clean_input(plaintext), fractionate))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        Ok(self.transform(&self.clean_input(ciphertext), unfractionate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cube() {
+        let cipher = TrifidCipher::new("felix marie delastelle").unwrap();
+
+        assert_eq!(
+            "FELIXMARDSTBCGHJKNOPQUVWYZ+",
+            cipher.symbols().iter().collect::<String>()
+        );
+        assert_eq!(vec![2, 2, 2], cipher.coordinates('+'));
+        assert_eq!(vec![1, 1, 0], cipher.coordinates('C'));
+    }
+
+    #[test]
+    fn test_known_text() {
+        // from https://en.wikipedia.org/wiki/Trifid_cipher
+        let cipher = TrifidCipher::new("felix marie delastelle:5").unwrap();
+        let ciphertext = "FMJFVOISSUFTFPUFEQQC";
+
+        assert_eq!(
+            ciphertext,
+            cipher.encrypt("Aide-toi, le ciel t'aidera").unwrap()
+        );
+        assert_eq!("AIDETOILECIELTAIDERA", cipher.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let cipher = TrifidCipher::new("felix marie delastelle:5").unwrap();
+        assert!(cipher.encrypt("ai7deto").unwrap().starts_with("FM7JFV"));
+
+        for digits in [DigitPolicy::Combined, DigitPolicy::SeparateRing].iter() {
+            assert!(TrifidCipher::new("felix")
+                .unwrap()
+                .with_digits(*digits)
+                .is_err());
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn test_correct() {
+        let keys = ["felix marie delastelle:5", "+zebra", "delastelle:1", "x:7"];
+        let plaintext = "the quick brown fox jumps over the lazy dog + more";
+
+        for key in keys.iter() {
+            let cipher = TrifidCipher::new(key).unwrap();
+
+            for len in 0..plaintext.len() {
+                let plaintext = &plaintext[..len];
+                let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+                assert_eq!(
+                    cipher.clean_input(plaintext),
+                    cipher.decrypt(&ciphertext).unwrap()
+                );
+            }
+        }
+    }
+}
@@ -1,36 +1,280 @@
-/// Pad a string until its length is a multiple of n (the closest multiple
-/// of n with respect to the string's initial length).
+use crate::text::{DigitPolicy, KeyAdvance, TextMode};
+use crate::{Alphabet, CipherError};
+
+/// The size of the ring the digits are encrypted in, when they get one of their own.
+pub const DIGITS: usize = 10;
+
+/// The names of the digits, used when spelling them out.
+const DIGIT_NAMES: [&str; DIGITS] = [
+    "ZERO", "ONE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT", "NINE",
+];
+
+/// The ring a symbol being substituted belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ring {
+    /// The symbols of the alphabet.
+    Symbols,
+    /// The digits, when they are encrypted separately from the alphabet.
+    Digits,
+}
+
+/// Cleans up the input as required by the text mode, after applying the digit policy (stripping
+/// or spelling out the digits, if needed).
+///
+/// When preserving the format, only the characters standing for a symbol of the alphabet are
+/// touched (e.g. by folding 'é' onto 'e'), keeping their case.
+pub fn clean(input: &str, alphabet: &Alphabet, mode: TextMode, digits: DigitPolicy) -> String {
+    let input = handle_digits(input, alphabet, digits);
+
+    match mode {
+        TextMode::Clean => alphabet.clean(&input),
+        TextMode::PreserveFormat(_) => input
+            .chars()
+            .map(|ch| match alphabet.symbol_for(ch) {
+                Some(symbol) => with_case_of(ch, symbol),
+                None => ch,
+            })
+            .collect(),
+    }
+}
+
+/// Strips or spells out the digits that are not symbols of the alphabet, as required by the
+/// digit policy (every other policy leaves them where they are).
+pub fn handle_digits(input: &str, alphabet: &Alphabet, digits: DigitPolicy) -> String {
+    let is_loose_digit = |ch: char| ch.is_ascii_digit() && !alphabet.contains(ch);
+
+    match digits {
+        DigitPolicy::Strip => input.chars().filter(|ch| !is_loose_digit(*ch)).collect(),
+        DigitPolicy::SpellOut => {
+            let mut spelled = String::with_capacity(input.len());
+
+            for ch in input.chars() {
+                match ch.to_digit(10) {
+                    Some(d) if is_loose_digit(ch) => spelled.push_str(DIGIT_NAMES[d as usize]),
+                    _ => spelled.push(ch),
+                }
+            }
+
+            spelled
+        }
+        _ => input.to_string(),
+    }
+}
+
+/// Replaces every symbol of the (clean) text with the symbol found at the index returned by f,
+/// which is given the index of the symbol, its position in the text and the ring it belongs to.
 ///
-/// If n > data.len() then data will be padded until it's of length n.
-pub fn pad(data: &str, n: u32, pad_char: char) -> String {
-    let mut ret = String::from(data);
+/// The digits that are not part of the alphabet make up a ring of their own if the digit policy
+/// says so, otherwise the text mode decides what happens to them and to every other character:
+/// in clean mode digits are left untouched (but still count towards the position) and anything
+/// else is an error, while when preserving the format they are all left untouched and only count
+/// towards the position if the key advances over them. Indexes past the end of the ring wrap
+/// around.
+pub fn substitute<F>(
+    text: &str,
+    alphabet: &Alphabet,
+    mode: TextMode,
+    digits: DigitPolicy,
+    mut f: F,
+) -> Result<String, CipherError>
+where
+    F: FnMut(usize, usize, Ring) -> usize,
+{
+    let mut substituted = String::with_capacity(text.len());
+    let mut pos = 0;
+
+    for ch in text.chars() {
+        let idx = match mode {
+            TextMode::Clean => alphabet.index_of(ch),
+            TextMode::PreserveFormat(_) => alphabet
+                .symbol_for(ch)
+                .and_then(|symbol| alphabet.index_of(symbol)),
+        };
+
+        match (idx, mode) {
+            (Some(idx), _) => {
+                let symbol = alphabet.symbol_at(f(idx, pos, Ring::Symbols));
+                substituted.push(with_case_of(ch, symbol));
+                pos += 1;
+            }
+            (None, _) if digits == DigitPolicy::SeparateRing && ch.is_ascii_digit() => {
+                let idx = ch.to_digit(10).unwrap() as usize;
+                let digit = f(idx, pos, Ring::Digits) % DIGITS;
+                substituted.push(std::char::from_digit(digit as u32, 10).unwrap());
+                pos += 1;
+            }
+            (None, TextMode::Clean) if ch.is_ascii_digit() => {
+                substituted.push(ch);
+                pos += 1;
+            }
+            (None, TextMode::Clean) => return Err(CipherError::InvalidSymbol(ch)),
+            (None, TextMode::PreserveFormat(KeyAdvance::EveryCharacter)) => {
+                substituted.push(ch);
+                pos += 1;
+            }
+            (None, TextMode::PreserveFormat(KeyAdvance::SymbolsOnly)) => substituted.push(ch),
+        }
+    }
 
-    // usually n < data.len() so this while should be pretty fast
-    while ret.len() % (n as usize) != 0 {
-        ret.push(pad_char);
+    Ok(substituted)
+}
+
+/// The inverse of a modulo m, i.e. the number x in 0..m such that ax = 1 (mod m), if there is
+/// one (which happens when a and m are coprime).
+pub fn mod_inverse(a: i64, m: usize) -> Option<usize> {
+    let m = m as i64;
+    // extended Euclid: old_s * a = old_r (mod m) all along
+    let (mut old_r, mut r) = (a.rem_euclid(m), m);
+    let (mut old_s, mut s) = (1i64, 0i64);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
     }
 
-    ret
+    (old_r == 1).then(|| old_s.rem_euclid(m) as usize)
+}
+
+/// Appends the filler to the text until the number of symbols of the alphabet in it is a multiple
+/// of the block size.
+pub fn pad(text: &str, alphabet: &Alphabet, block: usize, filler: char) -> String {
+    let symbols = text.chars().filter(|ch| alphabet.contains(*ch)).count();
+    let missing = (block - symbols % block) % block;
+
+    let mut padded = String::with_capacity(text.len() + missing);
+    padded.push_str(text);
+    padded.extend(std::iter::repeat_n(filler, missing));
+
+    padded
+}
+
+/// Gives symbol the same case as ch.
+fn with_case_of(ch: char, symbol: char) -> char {
+    if ch.is_lowercase() {
+        symbol.to_lowercase().next().unwrap_or(symbol)
+    } else {
+        symbol
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_substitute() {
+        let alphabet = Alphabet::english();
+
+        let mode = TextMode::Clean;
+        let digits = DigitPolicy::PassThrough;
+
+        let out = substitute("AB1Z", &alphabet, mode, digits, |idx, _, _| idx + 1).unwrap();
+        assert_eq!("BC1A", out);
+
+        let out = substitute("AA3A", &alphabet, mode, digits, |idx, pos, _| idx + pos).unwrap();
+        assert_eq!("AB3D", out);
+
+        assert!(substitute("Ab", &alphabet, mode, digits, |idx, _, _| idx).is_err());
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(Some(21), mod_inverse(5, 26));
+        assert_eq!(Some(25), mod_inverse(-1, 26));
+        assert_eq!(Some(1), mod_inverse(27, 26));
+        assert_eq!(Some(3), mod_inverse(7, 10));
+        assert_eq!(None, mod_inverse(13, 26));
+        assert_eq!(None, mod_inverse(0, 26));
+        assert_eq!(None, mod_inverse(6, 36));
+    }
+
     #[test]
     fn test_pad() {
-        let input = String::from("testing");
-        let n = 4;
-        let expected = String::from("testing.");
-        assert_eq!(expected, pad(&input, n, '.'));
-
-        let input = String::from("lololo");
-        let n = 3;
-        assert_eq!(input, pad(&input, n, ')'));
-
-        let input = String::from("te");
-        let n = 12;
-        let expected = String::from("teXXXXXXXXXX");
-        assert_eq!(expected, pad(&input, n, 'X'));
+        let alphabet = Alphabet::english();
+
+        assert_eq!("ABCXX", pad("ABC", &alphabet, 5, 'X'));
+        assert_eq!("AB1CX", pad("AB1C", &alphabet, 2, 'X'));
+        assert_eq!("ABCD", pad("ABCD", &alphabet, 2, 'X'));
+        assert_eq!("", pad("", &alphabet, 3, 'X'));
+    }
+
+    #[test]
+    fn test_substitute_preserving_format() {
+        let alphabet = Alphabet::english();
+
+        let digits = DigitPolicy::PassThrough;
+
+        let mode = TextMode::PreserveFormat(KeyAdvance::SymbolsOnly);
+        let out = substitute("Aa, a!\n", &alphabet, mode, digits, |idx, pos, _| idx + pos).unwrap();
+        assert_eq!("Ab, c!\n", out);
+
+        let mode = TextMode::PreserveFormat(KeyAdvance::EveryCharacter);
+        let out = substitute("Aa, a!\n", &alphabet, mode, digits, |idx, pos, _| idx + pos).unwrap();
+        assert_eq!("Ab, e!\n", out);
+    }
+
+    #[test]
+    fn test_substitute_digits() {
+        let alphabet = Alphabet::english();
+        let digits = DigitPolicy::SeparateRing;
+        let shift = |idx, _, ring| match ring {
+            Ring::Symbols => idx + 13,
+            Ring::Digits => idx + 3,
+        };
+
+        let out = substitute("AB19", &alphabet, TextMode::Clean, digits, shift).unwrap();
+        assert_eq!("NO42", out);
+
+        let mode = TextMode::preserve_format();
+        let out = substitute("Ab 19!", &alphabet, mode, digits, shift).unwrap();
+        assert_eq!("No 42!", out);
+    }
+
+    #[test]
+    fn test_clean_preserving_format() {
+        let italian = Alphabet::italian();
+
+        assert_eq!(
+            "Perche no?",
+            clean(
+                "Perché no?",
+                &italian,
+                TextMode::preserve_format(),
+                DigitPolicy::PassThrough
+            )
+        );
+        assert_eq!(
+            "PERCHENO",
+            clean(
+                "Perché no?",
+                &italian,
+                TextMode::Clean,
+                DigitPolicy::PassThrough
+            )
+        );
+    }
+
+    #[test]
+    fn test_handle_digits() {
+        let english = Alphabet::english();
+        let input = "Room 101";
+
+        assert_eq!(
+            input,
+            handle_digits(input, &english, DigitPolicy::PassThrough)
+        );
+        assert_eq!("Room ", handle_digits(input, &english, DigitPolicy::Strip));
+        assert_eq!(
+            "Room ONEZEROONE",
+            handle_digits(input, &english, DigitPolicy::SpellOut)
+        );
+
+        // digits belonging to the alphabet are symbols like any other
+        let alphanumeric = Alphabet::alphanumeric();
+        assert_eq!(
+            input,
+            handle_digits(input, &alphanumeric, DigitPolicy::Strip)
+        );
     }
 }
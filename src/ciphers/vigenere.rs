@@ -1,102 +1,247 @@
 // Vigenere is just a repeated shift cipher
-use super::shift::ShiftCipher;
-use crate::Cipher;
+use super::polyalphabetic::{Keystream, Tableau};
+use super::utils::clean;
+use crate::keys::VigenereKeyword;
+use crate::text::{DigitPolicy, TextMode};
+use crate::{Alphabet, Cipher, CipherError};
 
 pub struct VigenereCipher {
-    key: String,
+    key: VigenereKeyword,
+    alphabet: Alphabet,
+    keystream: Keystream,
+    mode: TextMode,
+    digits: DigitPolicy,
 }
 
 impl VigenereCipher {
     /// Initializes a new Vigenere cipher given the key.
-    pub fn new(key: &str) -> Self {
+    ///
+    /// The key must be a non-empty word made up only of letters.
+    pub fn new(key: &str) -> Result<Self, CipherError> {
+        Ok(VigenereCipher::from_key(key.parse()?))
+    }
+
+    /// Initializes a new Vigenere cipher with an already validated key.
+    pub fn from_key(key: VigenereKeyword) -> Self {
+        let alphabet = Alphabet::english();
+        let keystream = Keystream::repeating(&key, &alphabet);
+
         VigenereCipher {
-            key: key.to_string(),
+            key,
+            alphabet,
+            keystream,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
         }
     }
-}
 
-impl Cipher for VigenereCipher {
-    fn encrypt(&self, plaintext: &str) -> String {
-        let clean_plaintext = <VigenereCipher as Cipher>::clean_input(plaintext);
+    /// Initializes a new Vigenere cipher working on the given alphabet, to which the symbols of the
+    /// key must belong (after folding).
+    pub fn with_alphabet(key: VigenereKeyword, alphabet: Alphabet) -> Result<Self, CipherError> {
+        let key = VigenereKeyword::parse_for(key.letters(), &alphabet)?;
+        let keystream = Keystream::repeating(&key, &alphabet);
 
-        let upper_key = self.key.to_uppercase();
-        let mut ciphertext: Vec<char> = Vec::new();
+        Ok(VigenereCipher {
+            key,
+            alphabet,
+            keystream,
+            mode: TextMode::default(),
+            digits: DigitPolicy::default(),
+        })
+    }
 
-        for (idx, ch) in clean_plaintext.chars().enumerate() {
-            let ch_k = upper_key.as_bytes()[(idx % self.key.len())];
-            let shift = (ch_k as u32 - 'A' as u32) as i8;
+    /// Sets how the characters that are not part of the alphabet are treated (and whether the key
+    /// advances over them).
+    pub fn with_mode(mut self, mode: TextMode) -> Self {
+        self.mode = mode;
+        self
+    }
 
-            ciphertext.push(ShiftCipher::shift_by(shift, ch));
+    /// Sets what happens to the digits (with `DigitPolicy::Combined` they are appended to the
+    /// alphabet, with `DigitPolicy::SeparateRing` each letter of the key shifts them by its index
+    /// modulo 10).
+    pub fn with_digits(mut self, digits: DigitPolicy) -> Self {
+        // the digits go after the existing symbols, so the key stream doesn't change
+        if digits == DigitPolicy::Combined {
+            self.alphabet = self.alphabet.with_digits();
         }
+        self.digits = digits;
+        self
+    }
 
-        ciphertext.iter().collect::<String>()
+    pub fn key(&self) -> &VigenereKeyword {
+        &self.key
     }
 
-    fn decrypt(&self, ciphertext: &str) -> String {
-        let clean_ciphertext = <VigenereCipher as Cipher>::clean_input(ciphertext);
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+}
+
+impl Cipher for VigenereCipher {
+    fn clean_input(&self, input: &str) -> String {
+        clean(input, &self.alphabet, self.mode, self.digits)
+    }
 
-        let upper_key = self.key.to_uppercase();
-        let mut plaintext: Vec<char> = Vec::new();
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let clean_plaintext = self.clean_input(plaintext);
 
-        for (idx, ch) in clean_ciphertext.chars().enumerate() {
-            let ch_k = upper_key.as_bytes()[(idx % self.key.len())];
-            let shift = (ch_k as u32 - 'A' as u32) as i8;
+        self.keystream.run(
+            &clean_plaintext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            Tableau::Vigenere,
+            false,
+        )
+    }
 
-            plaintext.push(ShiftCipher::shift_by(-shift, ch));
-        }
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let clean_ciphertext = self.clean_input(ciphertext);
 
-        plaintext.iter().collect::<String>()
+        self.keystream.run(
+            &clean_ciphertext,
+            &self.alphabet,
+            self.mode,
+            self.digits,
+            Tableau::Vigenere,
+            true,
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::text::KeyAdvance;
     use rand::seq::SliceRandom;
 
     #[test]
-    #[should_panic]
     fn test_empty_key() {
         let cipher = VigenereCipher::new("");
 
-        cipher.encrypt("Hello");
+        assert!(matches!(cipher, Err(CipherError::InvalidKey(_))));
+    }
+
+    #[test]
+    fn test_non_alphabetic_key() {
+        let cipher = VigenereCipher::new("lem0n");
+
+        assert!(matches!(cipher, Err(CipherError::InvalidKey(_))));
     }
 
     #[test]
     fn test_key_longer_than_pt() {
-        let cipher = VigenereCipher::new("testinglongkey");
+        let cipher = VigenereCipher::new("testinglongkey").unwrap();
         let ciphertext = String::from("llgkbgkih");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt("shorttext"));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt("shorttext").unwrap()
+        );
     }
 
     #[test]
     fn test_known_pairs() {
         // from https://en.wikipedia.org/wiki/Vigenere_cipher
-        let cipher = VigenereCipher::new("lemon");
+        let cipher = VigenereCipher::new("lemon").unwrap();
         let plaintext = "attackatdawn";
         let ciphertext = String::from("lxfopvefrnhr");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <VigenereCipher as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
 
         // from https://cryptii.com/
-        let cipher = VigenereCipher::new("cryptii");
+        let cipher = VigenereCipher::new("cryptii").unwrap();
         let plaintext = "firstman";
         let ciphertext = String::from("hzphmuip");
 
-        assert_eq!(ciphertext.to_uppercase(), cipher.encrypt(plaintext));
+        assert_eq!(
+            ciphertext.to_uppercase(),
+            cipher.encrypt(plaintext).unwrap()
+        );
 
         assert_eq!(
-            <VigenereCipher as Cipher>::clean_input(plaintext),
-            cipher.decrypt(&ciphertext)
+            cipher.clean_input(plaintext),
+            cipher.decrypt(&ciphertext).unwrap()
         );
     }
 
+    #[test]
+    fn test_other_alphabets() {
+        // the umlaut is folded in the key as well as in the plaintext
+        let key = VigenereKeyword::parse_for("Schlüssel", &Alphabet::german()).unwrap();
+        let cipher = VigenereCipher::with_alphabet(key, Alphabet::german()).unwrap();
+        let ciphertext = cipher.encrypt("Grüße").unwrap();
+
+        assert_eq!("GRUESSE", cipher.decrypt(&ciphertext).unwrap());
+        assert_eq!(
+            VigenereCipher::new("schluessel")
+                .unwrap()
+                .encrypt("gruesse")
+                .unwrap(),
+            ciphertext
+        );
+
+        let cipher =
+            VigenereCipher::with_alphabet("b".parse().unwrap(), Alphabet::alphanumeric()).unwrap();
+        assert_eq!("0A", cipher.encrypt("z9").unwrap());
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let cipher = VigenereCipher::new("lemon").unwrap();
+        let plaintext = "Attack at dawn!";
+
+        // the key only advances over letters...
+        let preserving = cipher.with_mode(TextMode::preserve_format());
+        let ciphertext = "Lxfopv ef rnhr!";
+        assert_eq!(ciphertext, preserving.encrypt(plaintext).unwrap());
+        assert_eq!(plaintext, preserving.decrypt(ciphertext).unwrap());
+
+        // ...unless told otherwise
+        let preserving = VigenereCipher::new("lemon")
+            .unwrap()
+            .with_mode(TextMode::PreserveFormat(KeyAdvance::EveryCharacter));
+        let ciphertext = "Lxfopv mh oeib!";
+        assert_eq!(ciphertext, preserving.encrypt(plaintext).unwrap());
+        assert_eq!(plaintext, preserving.decrypt(ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_digit_policies() {
+        let plaintext = "Agent 007";
+
+        let cipher = VigenereCipher::new("lemon")
+            .unwrap()
+            .with_digits(DigitPolicy::SeparateRing);
+        // L, E, M shift the digits by 11, 4 and 12, i.e. by 1, 4 and 2
+        let ciphertext = "LKQBG149";
+        assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+        assert_eq!("AGENT007", cipher.decrypt(ciphertext).unwrap());
+
+        let cipher = VigenereCipher::new("lemon")
+            .unwrap()
+            .with_digits(DigitPolicy::Combined);
+        let ciphertext = cipher.encrypt(plaintext).unwrap();
+        // N + O wraps around onto the digits now
+        assert_eq!("LKQ16", &ciphertext[..5]);
+        assert_ne!("007", &ciphertext[5..]);
+        assert_eq!("AGENT007", cipher.decrypt(&ciphertext).unwrap());
+
+        let cipher = VigenereCipher::new("lemon")
+            .unwrap()
+            .with_digits(DigitPolicy::Strip);
+        assert_eq!("LKQBG", cipher.encrypt(plaintext).unwrap());
+    }
+
     #[test]
     #[ignore]
     fn test_correct() {
@@ -111,10 +256,12 @@ mod tests {
         for _ in 0..1000 {
             let choice = keys.choose(&mut rand::thread_rng()).unwrap();
 
-            let cipher = VigenereCipher::new(*choice);
+            let cipher = VigenereCipher::new(choice).unwrap();
             assert_eq!(
                 plaintext.to_uppercase(),
-                cipher.decrypt(&cipher.encrypt(&plaintext))
+                cipher
+                    .decrypt(&cipher.encrypt(&plaintext).unwrap())
+                    .unwrap()
             );
         }
     }
@@ -0,0 +1,58 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// All the things that can go wrong when building or using a cipher.
+#[derive(Debug)]
+pub enum CipherError {
+    /// The key is malformed or can't be used with the chosen cipher.
+    InvalidKey(String),
+    /// No cipher goes by the given name.
+    UnknownCipher(String),
+    /// A cipher with the same name (or alias) has already been registered.
+    DuplicateCipher(String),
+    /// The input contains a symbol the cipher doesn't know how to handle.
+    InvalidSymbol(char),
+    /// The length of the input doesn't fit the cipher (e.g. a block cipher given an incomplete
+    /// block to decrypt).
+    InvalidLength(String),
+    /// A language model couldn't be parsed.
+    InvalidModel(String),
+    /// Reading the input data failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::InvalidKey(reason) => write!(f, "invalid key: {}", reason),
+            CipherError::UnknownCipher(name) => write!(
+                f,
+                "unknown cipher '{}': it has not yet been implemented or it doesn't exist",
+                name
+            ),
+            CipherError::DuplicateCipher(name) => {
+                write!(f, "a cipher called '{}' has already been registered", name)
+            }
+            CipherError::InvalidSymbol(ch) => write!(f, "invalid input symbol {:?}", ch),
+            CipherError::InvalidLength(reason) => write!(f, "invalid input length: {}", reason),
+            CipherError::InvalidModel(reason) => write!(f, "invalid language model: {}", reason),
+            CipherError::Io(err) => write!(f, "error reading input: {}", err),
+        }
+    }
+}
+
+impl Error for CipherError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CipherError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CipherError {
+    fn from(err: io::Error) -> Self {
+        CipherError::Io(err)
+    }
+}
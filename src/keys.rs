@@ -0,0 +1,1601 @@
+/// This module holds the keys used by the ciphers.
+///
+/// Every key can be parsed from a string (validating it in the process) and printed back, so that
+/// keys can be checked before touching any data and stored wherever one sees fit.
+use crate::ciphers::polybius::{keyed_symbols, SquareLetters};
+use crate::ciphers::solitaire::Card;
+use crate::ciphers::transposition::{column_order, Route};
+use crate::{Alphabet, CipherError};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// The key of a shift cipher: the amount by which to shift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShiftKey(i8);
+
+impl ShiftKey {
+    pub fn new(shift: i8) -> Self {
+        ShiftKey(shift)
+    }
+
+    pub fn shift(&self) -> i8 {
+        self.0
+    }
+}
+
+impl FromStr for ShiftKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+
+        if trimmed.parse::<i64>().is_ok() {
+            trimmed.parse::<i8>().map(ShiftKey).map_err(|_| {
+                CipherError::InvalidKey(format!(
+                    "the shift amount must be between {} and {}, got {}",
+                    i8::MIN,
+                    i8::MAX,
+                    trimmed
+                ))
+            })
+        } else {
+            Err(CipherError::InvalidKey(format!(
+                "the key used in a shift cipher must be an integer (the amount by which to shift), got '{}'",
+                s
+            )))
+        }
+    }
+}
+
+impl fmt::Display for ShiftKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The key of an affine cipher: the multiplier a and the shift b of E(x) = ax + b.
+///
+/// Whether a can be used depends on the alphabet (it must be coprime with its size), so that is
+/// checked by the cipher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AffineKey {
+    a: i64,
+    b: i64,
+}
+
+impl AffineKey {
+    pub fn new(a: i64, b: i64) -> Self {
+        AffineKey { a, b }
+    }
+
+    /// The key of the Atbash cipher (the alphabet reversed), whatever the size of the alphabet:
+    /// E(x) = -x - 1.
+    pub fn atbash() -> Self {
+        AffineKey::new(-1, -1)
+    }
+
+    /// The key of a multiplicative cipher: E(x) = ax.
+    pub fn multiplicative(a: i64) -> Self {
+        AffineKey::new(a, 0)
+    }
+
+    pub fn a(&self) -> i64 {
+        self.a
+    }
+
+    pub fn b(&self) -> i64 {
+        self.b
+    }
+}
+
+impl FromStr for AffineKey {
+    type Err = CipherError;
+
+    /// Parses the two numbers, separated by a comma (e.g. "5,8").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            CipherError::InvalidKey(format!(
+                "the key of an affine cipher must be two integers separated by a comma (a,b), got '{}'",
+                s
+            ))
+        };
+
+        let (a, b) = s.split_once(',').ok_or_else(invalid)?;
+        let a = a.trim().parse::<i64>().map_err(|_| invalid())?;
+        let b = b.trim().parse::<i64>().map_err(|_| invalid())?;
+
+        Ok(AffineKey::new(a, b))
+    }
+}
+
+impl fmt::Display for AffineKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.a, self.b)
+    }
+}
+
+/// The key of a monoalphabetic cipher: a permutation of the alphabet (the English one, unless
+/// stated otherwise).
+///
+/// The i-th symbol of the permutation is the one the i-th symbol of the alphabet gets mapped to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubstitutionAlphabet(String);
+
+impl SubstitutionAlphabet {
+    /// Parses a permutation of the given alphabet.
+    pub fn parse_for(s: &str, alphabet: &Alphabet) -> Result<Self, CipherError> {
+        let upper = s.trim().to_uppercase();
+        let mut seen: HashSet<char> = HashSet::new();
+
+        for (idx, ch) in upper.chars().enumerate() {
+            if !alphabet.contains(ch) {
+                return Err(CipherError::InvalidKey(format!(
+                    "the substitution alphabet may only contain symbols of the alphabet {}, found {:?} at position {}",
+                    alphabet,
+                    ch,
+                    idx + 1
+                )));
+            }
+            if !seen.insert(ch) {
+                return Err(CipherError::InvalidKey(format!(
+                    "the symbol {} appears more than once in the substitution alphabet",
+                    ch
+                )));
+            }
+        }
+
+        if seen.len() != alphabet.len() {
+            let missing = alphabet
+                .symbols()
+                .iter()
+                .filter(|ch| !seen.contains(ch))
+                .collect::<String>();
+
+            return Err(CipherError::InvalidKey(format!(
+                "the substitution alphabet must be a permutation of the alphabet {}, missing: {}",
+                alphabet, missing
+            )));
+        }
+
+        Ok(SubstitutionAlphabet(upper))
+    }
+
+    /// The symbols of the permutation, in uppercase.
+    pub fn letters(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for SubstitutionAlphabet {
+    type Err = CipherError;
+
+    /// Parses a permutation of the English alphabet.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SubstitutionAlphabet::parse_for(s, &Alphabet::english())
+    }
+}
+
+impl fmt::Display for SubstitutionAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How the rest of the alphabet follows the keyword in a keyword-mixed alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum KeywordOrder {
+    /// The keyword, then the other symbols from the start of the alphabet.
+    #[default]
+    Plain,
+    /// The keyword, then the other symbols starting from the one after its last symbol (and
+    /// wrapping around).
+    FromLastLetter,
+    /// The plain keyword-mixed alphabet written in rows as wide as the keyword, read back column
+    /// by column in the alphabetical order of the symbols heading them.
+    Columnar,
+}
+
+impl FromStr for KeywordOrder {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "plain" => Ok(KeywordOrder::Plain),
+            "last" => Ok(KeywordOrder::FromLastLetter),
+            "columnar" => Ok(KeywordOrder::Columnar),
+            _ => Err(CipherError::InvalidKey(format!(
+                "unknown keyword order '{}', expected one of plain, last or columnar",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for KeywordOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KeywordOrder::Plain => "plain",
+            KeywordOrder::FromLastLetter => "last",
+            KeywordOrder::Columnar => "columnar",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// The key of a monoalphabetic cipher given as a keyword, from which the substitution alphabet is
+/// derived: the symbols of the keyword without repetitions, followed by the rest of the alphabet
+/// in the given order.
+///
+/// Written as the keyword optionally followed by a colon and "last" or "columnar" (e.g.
+/// "kangaroo:last").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeywordAlphabet {
+    keyword: String,
+    order: KeywordOrder,
+}
+
+impl KeywordAlphabet {
+    /// Fails if the keyword isn't made up of letters only.
+    pub fn new(keyword: &str, order: KeywordOrder) -> Result<Self, CipherError> {
+        Ok(KeywordAlphabet {
+            keyword: square_keyword(
+                keyword,
+                "keyword alphabet",
+                |ch| ch.is_alphabetic(),
+                "letters",
+            )?,
+            order,
+        })
+    }
+
+    /// The keyword, in uppercase and without spaces.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn order(&self) -> KeywordOrder {
+        self.order
+    }
+
+    /// The symbols of the given alphabet in their keyword-mixed order (the keyword is folded onto
+    /// the alphabet, skipping whatever isn't part of it).
+    pub fn symbols(&self, alphabet: &Alphabet) -> Vec<char> {
+        let folded = alphabet.fold(&self.keyword);
+        let mixed = keyed_symbols(&self.keyword, alphabet);
+        // how many distinct symbols of the keyword the mixed alphabet starts with
+        let head = mixed.iter().take_while(|ch| folded.contains(**ch)).count();
+
+        match self.order {
+            KeywordOrder::Plain => mixed,
+            KeywordOrder::FromLastLetter => {
+                let start = match head {
+                    0 => 0,
+                    _ => alphabet.index_of(mixed[head - 1]).unwrap() + 1,
+                };
+                let rest = (0..alphabet.len())
+                    .map(|i| alphabet.symbol_at(start + i))
+                    .filter(|ch| !mixed[..head].contains(ch));
+
+                mixed[..head].iter().copied().chain(rest).collect()
+            }
+            KeywordOrder::Columnar => {
+                let width = head.max(1);
+                let order = column_order(&mixed[..width].iter().collect::<String>());
+
+                order
+                    .into_iter()
+                    .flat_map(|col| mixed.iter().skip(col).step_by(width).copied())
+                    .collect()
+            }
+        }
+    }
+
+    /// The substitution alphabet derived from the keyword, for the given alphabet.
+    pub fn substitution(&self, alphabet: &Alphabet) -> SubstitutionAlphabet {
+        SubstitutionAlphabet(self.symbols(alphabet).into_iter().collect())
+    }
+}
+
+impl FromStr for KeywordAlphabet {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((keyword, order)) => KeywordAlphabet::new(keyword, order.parse()?),
+            None => KeywordAlphabet::new(s, KeywordOrder::default()),
+        }
+    }
+}
+
+impl fmt::Display for KeywordAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        if self.order != KeywordOrder::default() {
+            write!(f, ":{}", self.order)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The key of a Vigenere cipher: a non-empty word made up only of symbols of the alphabet (the
+/// English one, unless stated otherwise).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VigenereKeyword(String);
+
+impl VigenereKeyword {
+    /// Parses a keyword made up of symbols of the given alphabet (after folding).
+    pub fn parse_for(s: &str, alphabet: &Alphabet) -> Result<Self, CipherError> {
+        VigenereKeyword::parse_named(s, alphabet, "Vigenere")
+    }
+
+    /// Same as `parse_for`, for the keyword of another cipher of the Vigenère family (which the
+    /// errors are about).
+    pub fn parse_named(s: &str, alphabet: &Alphabet, cipher: &str) -> Result<Self, CipherError> {
+        let folded = alphabet.fold(s.trim());
+
+        if folded.is_empty() {
+            return Err(CipherError::InvalidKey(format!(
+                "the keyword of a {} cipher can't be empty",
+                cipher
+            )));
+        }
+        if let Some((idx, ch)) = folded
+            .chars()
+            .enumerate()
+            .find(|(_, ch)| !alphabet.contains(*ch))
+        {
+            return Err(CipherError::InvalidKey(format!(
+                "the keyword of a {} cipher may only contain symbols of the alphabet {}, found {:?} at position {}",
+                cipher,
+                alphabet,
+                ch,
+                idx + 1
+            )));
+        }
+
+        Ok(VigenereKeyword(folded))
+    }
+
+    /// The symbols of the keyword, in uppercase.
+    pub fn letters(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for VigenereKeyword {
+    type Err = CipherError;
+
+    /// Parses a keyword made up only of letters of the English alphabet.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        VigenereKeyword::parse_for(s, &Alphabet::english())
+    }
+}
+
+impl fmt::Display for VigenereKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The key of a Gronsfeld cipher: a non-empty string of digits, each the amount by which to
+/// shift the symbol it falls on (e.g. "31415").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GronsfeldKey(Vec<usize>);
+
+impl GronsfeldKey {
+    /// The shifts, one per digit.
+    pub fn shifts(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl FromStr for GronsfeldKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let shifts = s
+            .trim()
+            .chars()
+            .map(|ch| ch.to_digit(10).map(|d| d as usize))
+            .collect::<Option<Vec<usize>>>()
+            .filter(|shifts| !shifts.is_empty())
+            .ok_or_else(|| {
+                CipherError::InvalidKey(format!(
+                    "the key of a Gronsfeld cipher must be a non-empty string of digits, got '{}'",
+                    s
+                ))
+            })?;
+
+        Ok(GronsfeldKey(shifts))
+    }
+}
+
+impl fmt::Display for GronsfeldKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for shift in self.0.iter() {
+            write!(f, "{}", shift)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The key of a Quagmire cipher: the keyword of the plaintext alphabet (or of both alphabets), the
+/// keyword of the ciphertext alphabet (Quagmire IV only) and the indicator, whose letters pick the
+/// alphabet each symbol of the text is encrypted with.
+///
+/// Written as the keywords followed by the indicator, separated by commas (e.g.
+/// "senory,perctivl,extra").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuagmireKey {
+    keyword: String,
+    cipher_keyword: Option<String>,
+    indicator: String,
+}
+
+impl QuagmireKey {
+    /// Fails if any of the keywords isn't made up of letters only.
+    pub fn new(
+        keyword: &str,
+        cipher_keyword: Option<&str>,
+        indicator: &str,
+    ) -> Result<Self, CipherError> {
+        let is_letter = |ch: &char| ch.is_alphabetic();
+
+        Ok(QuagmireKey {
+            keyword: square_keyword(keyword, "Quagmire alphabet", is_letter, "letters")?,
+            cipher_keyword: cipher_keyword
+                .map(|keyword| square_keyword(keyword, "Quagmire alphabet", is_letter, "letters"))
+                .transpose()?,
+            indicator: square_keyword(indicator, "Quagmire indicator", is_letter, "letters")?,
+        })
+    }
+
+    /// The keyword of the plaintext alphabet (and of the ciphertext one, unless there's a keyword
+    /// of its own), in uppercase and without spaces.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// The keyword of the ciphertext alphabet, if it has one of its own.
+    pub fn cipher_keyword(&self) -> Option<&str> {
+        self.cipher_keyword.as_deref()
+    }
+
+    /// The indicator, in uppercase and without spaces.
+    pub fn indicator(&self) -> &str {
+        &self.indicator
+    }
+}
+
+impl FromStr for QuagmireKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(',').collect::<Vec<&str>>()[..] {
+            [keyword, indicator] => QuagmireKey::new(keyword, None, indicator),
+            [keyword, cipher_keyword, indicator] => {
+                QuagmireKey::new(keyword, Some(cipher_keyword), indicator)
+            }
+            _ => Err(CipherError::InvalidKey(format!(
+                "the key of a Quagmire cipher is made up of one or two keywords followed by the indicator, separated by commas, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for QuagmireKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},", self.keyword)?;
+        if let Some(cipher_keyword) = &self.cipher_keyword {
+            write!(f, "{},", cipher_keyword)?;
+        }
+
+        write!(f, "{}", self.indicator)
+    }
+}
+
+/// The key of a Playfair cipher: the keyword the square starts with, which letter makes room for
+/// the others (I and J merged, unless stated otherwise) and the filler letter (X, unless stated
+/// otherwise).
+///
+/// Written as the keyword optionally followed by "ij" or "noq" and by the filler, separated by
+/// colons (e.g. "playfair example:noq:z").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayfairKey {
+    keyword: String,
+    letters: SquareLetters,
+    filler: char,
+}
+
+impl PlayfairKey {
+    /// Fails if the keyword isn't made up of letters only, or if the filler is not part of the
+    /// square.
+    pub fn new(keyword: &str, letters: SquareLetters, filler: char) -> Result<Self, CipherError> {
+        let keyword = square_keyword(
+            keyword,
+            "Playfair cipher",
+            char::is_ascii_alphabetic,
+            "letters",
+        )?;
+
+        let filler = filler.to_ascii_uppercase();
+        if !letters.alphabet().contains(filler) {
+            return Err(CipherError::InvalidKey(format!(
+                "the filler of a Playfair cipher must be one of the letters of the square, got {:?}",
+                filler
+            )));
+        }
+
+        Ok(PlayfairKey {
+            keyword,
+            letters,
+            filler,
+        })
+    }
+
+    /// The keyword, in uppercase and without spaces.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn letters(&self) -> SquareLetters {
+        self.letters
+    }
+
+    pub fn filler(&self) -> char {
+        self.filler
+    }
+}
+
+impl FromStr for PlayfairKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let keyword = parts.next().unwrap_or_default();
+        let mut letters = SquareLetters::default();
+        let mut filler = 'X';
+
+        for part in parts {
+            let mut chars = part.trim().chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => filler = ch,
+                _ => letters = part.parse()?,
+            }
+        }
+
+        PlayfairKey::new(keyword, letters, filler)
+    }
+}
+
+impl fmt::Display for PlayfairKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        if self.letters != SquareLetters::default() {
+            write!(f, ":{}", self.letters)?;
+        }
+        if self.filler != 'X' {
+            write!(f, ":{}", self.filler)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The key of a Hill cipher: an n×n matrix (n being at least 2), row by row.
+///
+/// Written either as n² integers separated by commas (e.g. "7,8,11,11") or as a keyword of n²
+/// letters, each standing for its index in the alphabet (e.g. "hill"). Whether the matrix can be
+/// inverted depends on the size of the alphabet, so that is checked by the cipher.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HillKey {
+    size: usize,
+    matrix: Vec<i64>,
+}
+
+impl HillKey {
+    /// Fails if the number of entries isn't the square of an integer greater than 1.
+    pub fn new(matrix: Vec<i64>) -> Result<Self, CipherError> {
+        let size = (2..)
+            .find(|n| n * n >= matrix.len())
+            .filter(|n| n * n == matrix.len())
+            .ok_or_else(|| {
+                CipherError::InvalidKey(format!(
+                    "the key of a Hill cipher must be a square matrix of at least 2×2 entries, got {} entries",
+                    matrix.len()
+                ))
+            })?;
+
+        Ok(HillKey { size, matrix })
+    }
+
+    /// Parses either a list of integers or a keyword made up of symbols of the given alphabet
+    /// (after folding).
+    pub fn parse_for(s: &str, alphabet: &Alphabet) -> Result<Self, CipherError> {
+        let trimmed = s.trim();
+        let is_number = |part: &str| part.trim().parse::<i64>().is_ok();
+
+        if trimmed.contains(',') || is_number(trimmed) {
+            let matrix = trimmed
+                .split(',')
+                .map(|part| {
+                    part.trim().parse::<i64>().map_err(|_| {
+                        CipherError::InvalidKey(format!(
+                            "the entries of the matrix of a Hill cipher must be integers, got '{}'",
+                            part
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<i64>, CipherError>>()?;
+
+            return HillKey::new(matrix);
+        }
+
+        let folded = alphabet.fold(trimmed);
+        let matrix = folded
+            .chars()
+            .map(|ch| match alphabet.index_of(ch) {
+                Some(idx) => Ok(idx as i64),
+                None => Err(CipherError::InvalidKey(format!(
+                    "the keyword of a Hill cipher may only contain symbols of the alphabet {}, found {:?}",
+                    alphabet, ch
+                ))),
+            })
+            .collect::<Result<Vec<i64>, CipherError>>()?;
+
+        HillKey::new(matrix)
+    }
+
+    /// The number of rows (and columns) of the matrix, i.e. the size of the blocks.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The entries of the matrix, row by row.
+    pub fn matrix(&self) -> &[i64] {
+        &self.matrix
+    }
+}
+
+impl FromStr for HillKey {
+    type Err = CipherError;
+
+    /// Parses either a list of integers or a keyword made up only of letters of the English
+    /// alphabet.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HillKey::parse_for(s, &Alphabet::english())
+    }
+}
+
+impl fmt::Display for HillKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = self
+            .matrix
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<String>>();
+
+        write!(f, "{}", entries.join(","))
+    }
+}
+
+/// The key of a Bifid cipher: the keyword of its 5×5 square, which letter makes room for the
+/// others (I and J merged, unless stated otherwise) and the period, i.e. the length of the groups
+/// of letters the text is fractionated in (the whole text, unless stated otherwise).
+///
+/// Written as the keyword optionally followed by "ij" or "noq" and by the period, separated by
+/// colons (e.g. "bgwkzqpndsioaxefclumthyvr:5").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BifidKey {
+    keyword: String,
+    letters: SquareLetters,
+    period: Option<usize>,
+}
+
+impl BifidKey {
+    /// Fails if the keyword isn't made up of letters only, or if the period is 0.
+    pub fn new(
+        keyword: &str,
+        letters: SquareLetters,
+        period: Option<usize>,
+    ) -> Result<Self, CipherError> {
+        Ok(BifidKey {
+            keyword: square_keyword(
+                keyword,
+                "Bifid cipher",
+                char::is_ascii_alphabetic,
+                "letters",
+            )?,
+            letters,
+            period: check_period(period, "Bifid cipher")?,
+        })
+    }
+
+    /// The keyword, in uppercase and without spaces.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn letters(&self) -> SquareLetters {
+        self.letters
+    }
+
+    pub fn period(&self) -> Option<usize> {
+        self.period
+    }
+}
+
+impl FromStr for BifidKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let keyword = parts.next().unwrap_or_default();
+        let mut letters = SquareLetters::default();
+        let mut period = None;
+
+        for part in parts {
+            match part.trim().parse::<usize>() {
+                Ok(n) => period = Some(n),
+                Err(_) => letters = part.parse()?,
+            }
+        }
+
+        BifidKey::new(keyword, letters, period)
+    }
+}
+
+impl fmt::Display for BifidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        if self.letters != SquareLetters::default() {
+            write!(f, ":{}", self.letters)?;
+        }
+        if let Some(period) = self.period {
+            write!(f, ":{}", period)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The key of a Trifid cipher: the keyword of its 3×3×3 cube (made up of the 26 letters and +)
+/// and the period, i.e. the length of the groups of symbols the text is fractionated in (the
+/// whole text, unless stated otherwise).
+///
+/// Written as the keyword optionally followed by a colon and the period (e.g. "felix marie
+/// delastelle:5").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrifidKey {
+    keyword: String,
+    period: Option<usize>,
+}
+
+impl TrifidKey {
+    /// Fails if the keyword isn't made up of letters (and +) only, or if the period is 0.
+    pub fn new(keyword: &str, period: Option<usize>) -> Result<Self, CipherError> {
+        let is_symbol = |ch: &char| ch.is_ascii_alphabetic() || *ch == '+';
+
+        Ok(TrifidKey {
+            keyword: square_keyword(keyword, "Trifid cipher", is_symbol, "letters and +")?,
+            period: check_period(period, "Trifid cipher")?,
+        })
+    }
+
+    /// The keyword, in uppercase and without spaces.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn period(&self) -> Option<usize> {
+        self.period
+    }
+}
+
+impl FromStr for TrifidKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once(':') {
+            Some((keyword, period)) => {
+                let period = period.trim().parse::<usize>().map_err(|_| {
+                    CipherError::InvalidKey(format!(
+                        "the period of a Trifid cipher must be a uint, got '{}'",
+                        period
+                    ))
+                })?;
+
+                TrifidKey::new(keyword, Some(period))
+            }
+            None => TrifidKey::new(s, None),
+        }
+    }
+}
+
+impl fmt::Display for TrifidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        if let Some(period) = self.period {
+            write!(f, ":{}", period)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The key of an ADFGX or ADFGVX cipher: the keyword of its square and the key of the columnar
+/// transposition that follows, written separated by a comma (e.g. "ph0qg64mea1yl2nofdxkr3cvs5zw7bj9uti8,privacy").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdfgvxKey {
+    square: String,
+    transposition: ColumnarKey,
+}
+
+impl AdfgvxKey {
+    /// Fails if the keyword of the square isn't made up of letters and digits only.
+    pub fn new(square: &str, transposition: ColumnarKey) -> Result<Self, CipherError> {
+        Ok(AdfgvxKey {
+            square: square_keyword(
+                square,
+                "ADFGVX square",
+                char::is_ascii_alphanumeric,
+                "letters and digits",
+            )?,
+            transposition,
+        })
+    }
+
+    /// The keyword of the square, in uppercase and without spaces.
+    pub fn square(&self) -> &str {
+        &self.square
+    }
+
+    pub fn transposition(&self) -> &ColumnarKey {
+        &self.transposition
+    }
+}
+
+impl FromStr for AdfgvxKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(',') {
+            Some((square, transposition)) => AdfgvxKey::new(square, transposition.parse()?),
+            None => Err(CipherError::InvalidKey(format!(
+                "the key of an ADFGVX cipher is made up of the keyword of the square and the one of the transposition, separated by a comma, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for AdfgvxKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.square, self.transposition)
+    }
+}
+
+/// Uppercases the keyword of a square (or cube), dropping the whitespace, and checks that it's
+/// not empty and only contains the allowed symbols.
+fn square_keyword(
+    keyword: &str,
+    cipher: &str,
+    allowed: fn(&char) -> bool,
+    symbols: &str,
+) -> Result<String, CipherError> {
+    let keyword = keyword
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+
+    if keyword.is_empty() {
+        return Err(CipherError::InvalidKey(format!(
+            "the keyword of a {} can't be empty",
+            cipher
+        )));
+    }
+    if let Some(ch) = keyword.chars().find(|ch| !allowed(ch)) {
+        return Err(CipherError::InvalidKey(format!(
+            "the keyword of a {} may only contain {}, found {:?}",
+            cipher, symbols, ch
+        )));
+    }
+
+    Ok(keyword)
+}
+
+/// Checks that the period of a fractionating cipher, if any, is greater than 0.
+fn check_period(period: Option<usize>, cipher: &str) -> Result<Option<usize>, CipherError> {
+    if period == Some(0) {
+        return Err(CipherError::InvalidKey(format!(
+            "the period of a {} must be greater than 0",
+            cipher
+        )));
+    }
+
+    Ok(period)
+}
+
+/// The key of a scytale: its length (which must be greater than 0).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScytaleLength(usize);
+
+impl ScytaleLength {
+    /// Fails if the length is 0, since such a scytale can't hold any text.
+    pub fn new(length: usize) -> Result<Self, CipherError> {
+        if length == 0 {
+            return Err(CipherError::InvalidKey(
+                "the length of a scytale must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(ScytaleLength(length))
+    }
+
+    pub fn length(&self) -> usize {
+        self.0
+    }
+}
+
+impl FromStr for ScytaleLength {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let length = s.trim().parse::<usize>().map_err(|_| {
+            CipherError::InvalidKey(format!(
+                "the key used for a scytale cipher is the length of the scytale itself, so must be a uint, got '{}'",
+                s
+            ))
+        })?;
+
+        ScytaleLength::new(length)
+    }
+}
+
+impl fmt::Display for ScytaleLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The key of a Solitaire cipher, i.e. the way the deck is arranged before generating the
+/// keystream.
+///
+/// Written as a plain (non-empty) string, the key seeds a PRNG used to shuffle the deck. The
+/// "pass:" prefix selects Schneier's passphrase keying instead, and the "deck:" prefix an explicit
+/// deck order (the 54 cards, from top to bottom, either as "AC 2C ... KS JA JB" or as numbers in
+/// bridge order with the jokers being 53 and 54). A seed that happens to start with one of the prefixes can be written as
+/// "seed:<seed>".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SolitaireKey {
+    /// Shuffles the deck with a PRNG seeded by the string (not compatible with other Solitaire
+    /// implementations).
+    Seed(String),
+    /// Keys the deck as described by Schneier, using the letters of the passphrase (an empty
+    /// passphrase leaves the deck unkeyed).
+    Passphrase(String),
+    /// Uses the given order of the deck as it is.
+    Deck(Vec<Card>),
+}
+
+impl SolitaireKey {
+    /// Parses a passphrase: only letters are used, in uppercase, while whitespace is ignored.
+    pub fn passphrase(s: &str) -> Result<Self, CipherError> {
+        if let Some(ch) = s
+            .chars()
+            .find(|ch| !ch.is_ascii_alphabetic() && !ch.is_whitespace())
+        {
+            return Err(CipherError::InvalidKey(format!(
+                "the passphrase of a Solitaire cipher may only contain letters, found {:?}",
+                ch
+            )));
+        }
+
+        Ok(SolitaireKey::Passphrase(
+            s.chars()
+                .filter(|ch| ch.is_ascii_alphabetic())
+                .collect::<String>()
+                .to_uppercase(),
+        ))
+    }
+
+    /// Parses a deck order: the 54 cards from top to bottom, separated by whitespace or commas
+    /// (each written either as e.g. "QH" or as its number in bridge order).
+    pub fn deck(s: &str) -> Result<Self, CipherError> {
+        let cards = s
+            .split(|ch: char| ch.is_whitespace() || ch == ',')
+            .filter(|card| !card.is_empty())
+            .map(|card| card.parse::<Card>())
+            .collect::<Result<Vec<Card>, CipherError>>()?;
+
+        SolitaireKey::check_deck(&cards)?;
+
+        Ok(SolitaireKey::Deck(cards))
+    }
+
+    /// Checks that the cards make up a full deck, each card appearing exactly once.
+    fn check_deck(cards: &[Card]) -> Result<(), CipherError> {
+        let mut seen: HashSet<Card> = HashSet::new();
+
+        for card in cards {
+            if !seen.insert(*card) {
+                return Err(CipherError::InvalidKey(format!(
+                    "the card {} appears more than once in the deck",
+                    card
+                )));
+            }
+        }
+        if cards.len() != 54 {
+            return Err(CipherError::InvalidKey(format!(
+                "a Solitaire deck must have 54 cards, got {}",
+                cards.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SolitaireKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(passphrase) = s.strip_prefix("pass:") {
+            return SolitaireKey::passphrase(passphrase);
+        }
+        if let Some(deck) = s.strip_prefix("deck:") {
+            return SolitaireKey::deck(deck);
+        }
+
+        let seed = s.strip_prefix("seed:").unwrap_or(s);
+        if seed.is_empty() {
+            return Err(CipherError::InvalidKey(
+                "the key of a Solitaire cipher can't be empty".to_string(),
+            ));
+        }
+
+        Ok(SolitaireKey::Seed(seed.to_string()))
+    }
+}
+
+impl fmt::Display for SolitaireKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolitaireKey::Seed(seed) => {
+                let prefixes = ["pass:", "deck:", "seed:"];
+
+                if prefixes.iter().any(|prefix| seed.starts_with(prefix)) {
+                    write!(f, "seed:{}", seed)
+                } else {
+                    write!(f, "{}", seed)
+                }
+            }
+            SolitaireKey::Passphrase(passphrase) => write!(f, "pass:{}", passphrase),
+            SolitaireKey::Deck(cards) => {
+                let cards = cards
+                    .iter()
+                    .map(|card| card.to_string())
+                    .collect::<Vec<String>>();
+
+                write!(f, "deck:{}", cards.join(" "))
+            }
+        }
+    }
+}
+
+/// The key of a columnar transposition: a word whose letters, ranked alphabetically, give the
+/// order in which the columns are read (its length being the number of columns).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnarKey(String);
+
+impl ColumnarKey {
+    /// The symbols of the key, in uppercase.
+    pub fn letters(&self) -> &str {
+        &self.0
+    }
+
+    /// The number of columns of the grid.
+    pub fn columns(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The order in which the columns are read (ties between repeated letters are broken left to
+    /// right).
+    pub fn order(&self) -> Vec<usize> {
+        column_order(&self.0)
+    }
+}
+
+impl FromStr for ColumnarKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+
+        if trimmed.is_empty() {
+            return Err(CipherError::InvalidKey(
+                "the key of a columnar transposition can't be empty".to_string(),
+            ));
+        }
+        if let Some(ch) = trimmed.chars().find(|ch| !ch.is_ascii_alphanumeric()) {
+            return Err(CipherError::InvalidKey(format!(
+                "the key of a columnar transposition may only contain letters and digits, found {:?}",
+                ch
+            )));
+        }
+
+        Ok(ColumnarKey(trimmed.to_uppercase()))
+    }
+}
+
+impl fmt::Display for ColumnarKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The key of a double transposition: two columnar keys, written separated by a comma (e.g.
+/// "zebras,stripe").
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DoubleColumnarKey {
+    first: ColumnarKey,
+    second: ColumnarKey,
+}
+
+impl DoubleColumnarKey {
+    pub fn new(first: ColumnarKey, second: ColumnarKey) -> Self {
+        DoubleColumnarKey { first, second }
+    }
+
+    /// The key of the first transposition.
+    pub fn first(&self) -> &ColumnarKey {
+        &self.first
+    }
+
+    /// The key of the second transposition.
+    pub fn second(&self) -> &ColumnarKey {
+        &self.second
+    }
+}
+
+impl FromStr for DoubleColumnarKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(',') {
+            Some((first, second)) => Ok(DoubleColumnarKey::new(first.parse()?, second.parse()?)),
+            None => Err(CipherError::InvalidKey(format!(
+                "the key of a double transposition is made up of two words separated by a comma, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for DoubleColumnarKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.first, self.second)
+    }
+}
+
+/// The key of a route cipher: the number of columns of the grid and the route along which it is
+/// read, written as e.g. "6:spiral".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RouteKey {
+    columns: usize,
+    route: Route,
+}
+
+impl RouteKey {
+    /// Fails if there are no columns.
+    pub fn new(columns: usize, route: Route) -> Result<Self, CipherError> {
+        if columns == 0 {
+            return Err(CipherError::InvalidKey(
+                "the grid of a route cipher must have at least one column".to_string(),
+            ));
+        }
+
+        Ok(RouteKey { columns, route })
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn route(&self) -> Route {
+        self.route
+    }
+}
+
+impl FromStr for RouteKey {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (columns, route) = s.split_once(':').ok_or_else(|| {
+            CipherError::InvalidKey(format!(
+                "the key of a route cipher is made up of the number of columns and the route (e.g. 6:spiral), got '{}'",
+                s
+            ))
+        })?;
+        let columns = columns.trim().parse::<usize>().map_err(|_| {
+            CipherError::InvalidKey(format!(
+                "the number of columns of a route cipher must be a uint, got '{}'",
+                columns
+            ))
+        })?;
+
+        RouteKey::new(columns, route.parse()?)
+    }
+}
+
+impl fmt::Display for RouteKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.columns, self.route)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_invalid<T: FromStr<Err = CipherError>>(s: &str) {
+        assert!(matches!(s.parse::<T>(), Err(CipherError::InvalidKey(_))));
+    }
+
+    fn assert_round_trip<T>(s: &str)
+    where
+        T: FromStr<Err = CipherError> + fmt::Display + PartialEq + fmt::Debug,
+    {
+        let key = s.parse::<T>().unwrap();
+
+        assert_eq!(key, key.to_string().parse::<T>().unwrap());
+    }
+
+    #[test]
+    fn test_shift_key() {
+        assert_eq!("-3".parse::<ShiftKey>().unwrap().shift(), -3);
+        assert_eq!(" 25 ".parse::<ShiftKey>().unwrap().shift(), 25);
+
+        assert_invalid::<ShiftKey>("three");
+        assert_invalid::<ShiftKey>("");
+        assert_invalid::<ShiftKey>("300");
+
+        assert_round_trip::<ShiftKey>("-128");
+    }
+
+    #[test]
+    fn test_affine_key() {
+        let key = " 5, 8".parse::<AffineKey>().unwrap();
+        assert_eq!((5, 8), (key.a(), key.b()));
+        assert_eq!(AffineKey::new(-1, -1), AffineKey::atbash());
+        assert_eq!(AffineKey::new(3, 0), AffineKey::multiplicative(3));
+
+        assert_invalid::<AffineKey>("5");
+        assert_invalid::<AffineKey>("5,");
+        assert_invalid::<AffineKey>("a,b");
+        assert_invalid::<AffineKey>("5,8,1");
+
+        assert_round_trip::<AffineKey>("-1,-1");
+    }
+
+    #[test]
+    fn test_substitution_alphabet() {
+        let key = "zyxwvutsrqponmlkjihgfedcba"
+            .parse::<SubstitutionAlphabet>()
+            .unwrap();
+        assert_eq!(key.letters(), "ZYXWVUTSRQPONMLKJIHGFEDCBA");
+
+        assert_invalid::<SubstitutionAlphabet>("");
+        // duplicate 'e', missing 'f'
+        assert_invalid::<SubstitutionAlphabet>("klmnopqrstuvwxyzabcdeeghij");
+        // a digit appended to a correct one
+        assert_invalid::<SubstitutionAlphabet>("klmnopqrstuvwxyzabcdefghij1");
+        // too short
+        assert_invalid::<SubstitutionAlphabet>("abcdefghijklmnopqrstuvwxy");
+
+        assert_round_trip::<SubstitutionAlphabet>("myxnvestrqpowzlkjihgfudabc");
+
+        let italian = Alphabet::italian();
+        assert!(SubstitutionAlphabet::parse_for("zvutsrqponmlihgfedcba", &italian).is_ok());
+        assert!(SubstitutionAlphabet::parse_for("zyxwvutsrqponmlkjihgfedcba", &italian).is_err());
+    }
+
+    #[test]
+    fn test_gronsfeld_key() {
+        assert_eq!(
+            " 31415 ".parse::<GronsfeldKey>().unwrap().shifts(),
+            &[3, 1, 4, 1, 5]
+        );
+
+        assert_invalid::<GronsfeldKey>("");
+        assert_invalid::<GronsfeldKey>("3.1415");
+        assert_invalid::<GronsfeldKey>("pi");
+
+        assert_round_trip::<GronsfeldKey>("0123");
+    }
+
+    #[test]
+    fn test_keyword_alphabet() {
+        let english = Alphabet::english();
+        let symbols = |key: &str| {
+            key.parse::<KeywordAlphabet>()
+                .unwrap()
+                .symbols(&english)
+                .into_iter()
+                .collect::<String>()
+        };
+
+        assert_eq!("KANGROBCDEFHIJLMPQSTUVWXYZ", symbols("kangaroo"));
+        assert_eq!("KANGROPQSTUVWXYZBCDEFHIJLM", symbols("kangaroo:last"));
+        // KANGRO / BCDEFH / IJLMPQ / STUVWX / YZ, read from the A column to the R one
+        assert_eq!("ACJTZGEMVKBISYNDLUOHQXRFPW", symbols("kangaroo:columnar"));
+
+        let key = "Schlüssel".parse::<KeywordAlphabet>().unwrap();
+        assert_eq!(
+            "SCHLUEABDFGIJKMNOPQRTVWXY",
+            key.symbols(&Alphabet::german())[..25]
+                .iter()
+                .collect::<String>()
+        );
+
+        assert_invalid::<KeywordAlphabet>("");
+        assert_invalid::<KeywordAlphabet>("kangaroo:backwards");
+        assert_invalid::<KeywordAlphabet>("kang4roo");
+        assert_round_trip::<KeywordAlphabet>("kangaroo:last");
+        assert_round_trip::<KeywordAlphabet>("kangaroo");
+    }
+
+    #[test]
+    fn test_quagmire_key() {
+        let key = "senory, perctivl, extra".parse::<QuagmireKey>().unwrap();
+        assert_eq!(key.keyword(), "SENORY");
+        assert_eq!(key.cipher_keyword(), Some("PERCTIVL"));
+        assert_eq!(key.indicator(), "EXTRA");
+        assert_eq!(
+            "autom,highway"
+                .parse::<QuagmireKey>()
+                .unwrap()
+                .cipher_keyword(),
+            None
+        );
+
+        assert_invalid::<QuagmireKey>("automobile");
+        assert_invalid::<QuagmireKey>("a,b,c,d");
+        assert_invalid::<QuagmireKey>("automobile,");
+        assert_invalid::<QuagmireKey>("automobile,h1ghway");
+        assert_round_trip::<QuagmireKey>("senory,perctivl,extra");
+        assert_round_trip::<QuagmireKey>("automobile,highway");
+    }
+
+    #[test]
+    fn test_vigenere_keyword() {
+        assert_eq!(
+            "lemon".parse::<VigenereKeyword>().unwrap().letters(),
+            "LEMON"
+        );
+
+        assert_invalid::<VigenereKeyword>("");
+        assert_invalid::<VigenereKeyword>("lem0n");
+        assert_invalid::<VigenereKeyword>("two words");
+
+        assert_round_trip::<VigenereKeyword>("Jackpot");
+
+        let german = Alphabet::german();
+        assert_eq!(
+            VigenereKeyword::parse_for("Müller", &german)
+                .unwrap()
+                .letters(),
+            "MUELLER"
+        );
+        assert!(VigenereKeyword::parse_for("ab12", &Alphabet::alphanumeric()).is_ok());
+    }
+
+    #[test]
+    fn test_playfair_key() {
+        let key = "playfair example".parse::<PlayfairKey>().unwrap();
+        assert_eq!(key.keyword(), "PLAYFAIREXAMPLE");
+        assert_eq!(key.letters(), SquareLetters::MergeIJ);
+        assert_eq!(key.filler(), 'X');
+
+        let key = "jumbo:noq:z".parse::<PlayfairKey>().unwrap();
+        assert_eq!(key.letters(), SquareLetters::OmitQ);
+        assert_eq!(key.filler(), 'Z');
+
+        assert_invalid::<PlayfairKey>("");
+        assert_invalid::<PlayfairKey>("m0narchy");
+        assert_invalid::<PlayfairKey>("monarchy:ijq");
+        // the filler must have a cell of its own
+        assert_invalid::<PlayfairKey>("monarchy:j");
+        assert_invalid::<PlayfairKey>("monarchy:noq:q");
+        assert_invalid::<PlayfairKey>("monarchy:1");
+
+        assert_round_trip::<PlayfairKey>("monarchy");
+        assert_round_trip::<PlayfairKey>("jumbo:z:noq");
+    }
+
+    #[test]
+    fn test_hill_key() {
+        let key = "7, 8, 11, 11".parse::<HillKey>().unwrap();
+        assert_eq!(key.size(), 2);
+        assert_eq!(key.matrix(), &[7, 8, 11, 11]);
+        assert_eq!(key, "hill".parse::<HillKey>().unwrap());
+        assert_eq!("GYBNQKURP".parse::<HillKey>().unwrap().size(), 3);
+
+        assert_invalid::<HillKey>("");
+        assert_invalid::<HillKey>("7");
+        assert_invalid::<HillKey>("1,2,3");
+        assert_invalid::<HillKey>("1,2,three,4");
+        assert_invalid::<HillKey>("hills");
+        assert_invalid::<HillKey>("h1ll");
+
+        assert_round_trip::<HillKey>("-1,0,0,-1");
+
+        // keywords are read according to the alphabet
+        let latin = Alphabet::latin();
+        assert_eq!(
+            HillKey::parse_for("vale", &latin).unwrap().matrix(),
+            &[19, 0, 10, 4]
+        );
+    }
+
+    #[test]
+    fn test_fractionating_keys() {
+        let key = "bgwkz qpnds:noq:5".parse::<BifidKey>().unwrap();
+        assert_eq!(key.keyword(), "BGWKZQPNDS");
+        assert_eq!(key.letters(), SquareLetters::OmitQ);
+        assert_eq!(key.period(), Some(5));
+        assert_eq!("bgwkz".parse::<BifidKey>().unwrap().period(), None);
+
+        assert_invalid::<BifidKey>("");
+        assert_invalid::<BifidKey>("bgwkz:0");
+        assert_invalid::<BifidKey>("bgwkz:five");
+        assert_invalid::<BifidKey>("bgw1z");
+        assert_round_trip::<BifidKey>("bgwkz:noq:5");
+
+        let key = "felix marie delastelle:5".parse::<TrifidKey>().unwrap();
+        assert_eq!(key.keyword(), "FELIXMARIEDELASTELLE");
+        assert_eq!(key.period(), Some(5));
+        assert_eq!("abc+".parse::<TrifidKey>().unwrap().period(), None);
+
+        assert_invalid::<TrifidKey>("felix:0");
+        assert_invalid::<TrifidKey>("felix:");
+        assert_invalid::<TrifidKey>("fel1x");
+        assert_round_trip::<TrifidKey>("felix+:7");
+
+        let key = "na1c3h, privacy".parse::<AdfgvxKey>().unwrap();
+        assert_eq!(key.square(), "NA1C3H");
+        assert_eq!(key.transposition().letters(), "PRIVACY");
+
+        assert_invalid::<AdfgvxKey>("na1c3h");
+        assert_invalid::<AdfgvxKey>(",privacy");
+        assert_invalid::<AdfgvxKey>("na1c3h,");
+        assert_invalid::<AdfgvxKey>("na-c3h,privacy");
+        assert_round_trip::<AdfgvxKey>("na1c3h,privacy");
+    }
+
+    #[test]
+    fn test_scytale_length() {
+        assert_eq!("5".parse::<ScytaleLength>().unwrap().length(), 5);
+
+        assert_invalid::<ScytaleLength>("0");
+        assert_invalid::<ScytaleLength>("-4");
+        assert_invalid::<ScytaleLength>("four");
+
+        assert_round_trip::<ScytaleLength>("12");
+    }
+
+    #[test]
+    fn test_solitaire_key() {
+        assert_eq!(
+            "cards upon cards".parse::<SolitaireKey>().unwrap(),
+            SolitaireKey::Seed("cards upon cards".to_string())
+        );
+
+        assert_invalid::<SolitaireKey>("");
+        assert_invalid::<SolitaireKey>("seed:");
+
+        assert_round_trip::<SolitaireKey>("@#@@[^^∏ß");
+        assert_round_trip::<SolitaireKey>("seed:pass:word");
+    }
+
+    #[test]
+    fn test_solitaire_passphrase() {
+        assert_eq!(
+            "pass:crypto nomicon".parse::<SolitaireKey>().unwrap(),
+            SolitaireKey::Passphrase("CRYPTONOMICON".to_string())
+        );
+        assert_eq!(
+            "pass:".parse::<SolitaireKey>().unwrap(),
+            SolitaireKey::Passphrase(String::new())
+        );
+
+        assert_invalid::<SolitaireKey>("pass:r2d2");
+
+        assert_round_trip::<SolitaireKey>("pass:foo");
+    }
+
+    #[test]
+    fn test_solitaire_deck() {
+        let order = (1..=54)
+            .rev()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>();
+        let key = format!("deck:{}", order.join(","))
+            .parse::<SolitaireKey>()
+            .unwrap();
+        let cards = (1..=54)
+            .rev()
+            .map(|n| Card::from_number(n).unwrap())
+            .collect::<Vec<Card>>();
+        assert_eq!(key, SolitaireKey::Deck(cards.clone()));
+
+        // the same deck in card notation
+        let notation = cards
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            key,
+            format!("deck:{}", notation.join(" "))
+                .parse::<SolitaireKey>()
+                .unwrap()
+        );
+        assert!(key.to_string().starts_with("deck:JB JA KS QS"));
+
+        // missing a card
+        assert_invalid::<SolitaireKey>(&format!("deck:{}", order[1..].join(" ")));
+        // a card twice
+        assert_invalid::<SolitaireKey>(&format!("deck:1 {}", order[1..].join(" ")));
+        assert_invalid::<SolitaireKey>(&format!("deck:55 {}", order[1..].join(" ")));
+        assert_invalid::<SolitaireKey>("deck:one two");
+        assert_invalid::<SolitaireKey>(&format!("deck:AC {}", order[1..].join(" ")));
+
+        assert_round_trip::<SolitaireKey>(&format!("deck:{}", notation.join(" ")));
+    }
+
+    #[test]
+    fn test_columnar_key() {
+        let key = "zebras".parse::<ColumnarKey>().unwrap();
+        assert_eq!(key.columns(), 6);
+        assert_eq!(key.order(), vec![4, 2, 1, 3, 5, 0]);
+
+        assert_invalid::<ColumnarKey>("");
+        assert_invalid::<ColumnarKey>("two words");
+
+        assert_round_trip::<ColumnarKey>("Stripe");
+    }
+
+    #[test]
+    fn test_double_columnar_key() {
+        let key = "zebras, stripe".parse::<DoubleColumnarKey>().unwrap();
+        assert_eq!(key.first().letters(), "ZEBRAS");
+        assert_eq!(key.second().letters(), "STRIPE");
+
+        assert_invalid::<DoubleColumnarKey>("zebras");
+        assert_invalid::<DoubleColumnarKey>("zebras,");
+
+        assert_round_trip::<DoubleColumnarKey>("zebras,stripe");
+    }
+
+    #[test]
+    fn test_route_key() {
+        let key = "6:Spiral".parse::<RouteKey>().unwrap();
+        assert_eq!(key.columns(), 6);
+        assert_eq!(key.route(), Route::Spiral);
+
+        assert_invalid::<RouteKey>("6");
+        assert_invalid::<RouteKey>("0:snake");
+        assert_invalid::<RouteKey>("six:snake");
+        assert_invalid::<RouteKey>("6:zigzag");
+
+        assert_round_trip::<RouteKey>("4:diagonal");
+    }
+}
@@ -1,18 +1,32 @@
-mod ciphers;
+mod alphabet;
+pub mod analysis;
+pub mod ciphers;
+mod error;
+pub mod keys;
+pub mod registry;
+pub mod text;
 
-use ciphers::monoalphabetic::MonoalphaCipher;
-use ciphers::scytale::Scytale;
-use ciphers::shift::ShiftCipher;
-use ciphers::solitaire::solitaire::SolitaireCipher;
-use ciphers::vigenere::VigenereCipher;
+pub use alphabet::Alphabet;
+pub use ciphers::{
+    AdfgvxCipher, AffineCipher, BifidCipher, ColumnarCipher, DoubleColumnarCipher, HillCipher,
+    MonoalphaCipher, PlayfairCipher, PolyalphabeticCipher, QuagmireCipher, RouteCipher, Scytale,
+    ShiftCipher, SolitaireCipher, SolitaireKeystream, TrifidCipher, VigenereCipher,
+};
+pub use error::CipherError;
+pub use registry::Settings;
+pub use text::{DigitPolicy, KeyAdvance, LetterCase, OutputFormat, TextMode};
 
 use std::fs::read_to_string;
 
-pub(crate) trait Cipher {
+/// The interface shared by all the ciphers.
+///
+/// The trait is object safe, so different ciphers can be used interchangeably through a
+/// `Box<dyn Cipher>`, and can be implemented outside of this crate to plug in new ciphers.
+pub trait Cipher {
     /// Cleans up the input by removing all characters that are not alphanumeric.
     ///
     /// Returns an uppercase String.
-    fn clean_input(input: &str) -> String {
+    fn clean_input(&self, input: &str) -> String {
         let cleaned = input
             .chars()
             .filter(|c| c.is_ascii_alphanumeric())
@@ -24,107 +38,60 @@ pub(crate) trait Cipher {
             .to_uppercase()
     }
 
-    fn encrypt(&self, plaintext: &str) -> String;
-    fn decrypt(&self, ciphertext: &str) -> String;
+    /// Encrypts the plaintext, cleaning it up first.
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError>;
+    /// Decrypts the ciphertext, cleaning it up first.
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError>;
 }
 
 /// Gets data based on the CLI args provided (if a file has been specified that one is used
 /// otherwise use stdin).
-pub fn get_data(file: Option<&str>, data: Option<&str>) -> String {
-    if file.is_some() {
-        println!("{:?}", file);
-        read_to_string(file.unwrap()).expect("Error reading from file.")
-    } else {
-        data.unwrap().to_string()
+pub fn get_data(file: Option<&str>, data: Option<&str>) -> Result<String, CipherError> {
+    match file {
+        Some(path) => Ok(read_to_string(path)?),
+        None => Ok(data.unwrap_or_default().to_string()),
     }
 }
 
 /// Encrypts the given data provided given a choice of cipher.
-pub fn encrypt_data(cipher: &str, data: &str, key: &str) -> String {
-    match cipher {
-        "shift" => {
-            let shift_amount = key.parse::<i8>();
-            if shift_amount.is_err() {
-                panic!("The key used in a shift cipher must be an integer (the amount by which to shift).");
-            }
-
-            let sc = ShiftCipher::new(shift_amount.unwrap());
-
-            sc.encrypt(data)
-        }
-        "monoalphabetic" => {
-            let mc = MonoalphaCipher::new(key);
-
-            mc.encrypt(data)
-        }
-        "vigenere" => {
-            let vc = VigenereCipher::new(key);
-
-            vc.encrypt(data)
-        }
-        "scytale" => {
-            let length = key.parse::<usize>();
-            if length.is_err() {
-                panic!("The key used for a scytale cipher is the length of the scytale itself, so must be a uint.");
-            }
-
-            let scytale = Scytale::new(length.unwrap());
-
-            scytale.encrypt(data)
-        }
-        "solitaire" => {
-            let solitaire_c = SolitaireCipher::new(key);
-
-            solitaire_c.encrypt(data)
-        }
-        _ => {
-            panic!("This cipher has not yet been implemented or it doesn't exist.");
-        }
-    }
+///
+/// The cipher is looked up by name (or alias) among the ones available in the registry.
+pub fn encrypt_data(cipher: &str, data: &str, key: &str) -> Result<String, CipherError> {
+    encrypt_data_with(cipher, data, key, &Settings::default())
 }
 
 /// Decrypts the given data give a choice of cipher.
-pub fn decrypt_data(cipher: &str, data: &str, key: &str) -> String {
-    match cipher {
-        "shift" => {
-            let shift_amount = key.parse::<i8>();
-            if shift_amount.is_err() {
-                panic!("The key used in a shift cipher must be an integer (the amount by which to shift).");
-            }
-
-            let sc = ShiftCipher::new(shift_amount.unwrap());
-
-            sc.decrypt(data)
-        }
-        "monoalphabetic" => {
-            let mc = MonoalphaCipher::new(key);
-
-            mc.decrypt(data)
-        }
-        "vigenere" => {
-            let vc = VigenereCipher::new(key);
+///
+/// The cipher is looked up by name (or alias) among the ones available in the registry.
+pub fn decrypt_data(cipher: &str, data: &str, key: &str) -> Result<String, CipherError> {
+    decrypt_data_with(cipher, data, key, &Settings::default())
+}
 
-            vc.decrypt(data)
-        }
-        "scytale" => {
-            let length = key.parse::<usize>();
-            if length.is_err() {
-                panic!("The key used for a scytale cipher is the length of the scytale itself, so must be a uint.");
-            }
+/// Same as `encrypt_data`, but with the cipher set up according to the given settings (and the
+/// ciphertext laid out according to them).
+pub fn encrypt_data_with(
+    cipher: &str,
+    data: &str,
+    key: &str,
+    settings: &Settings,
+) -> Result<String, CipherError> {
+    let encrypted = registry::build_cipher_with(cipher, key, settings)?.encrypt(data)?;
 
-            let scytale = Scytale::new(length.unwrap());
+    Ok(settings.format.apply(&encrypted))
+}
 
-            scytale.decrypt(data)
-        }
-        "solitaire" => {
-            let solitaire_c = SolitaireCipher::new(key);
+/// Same as `decrypt_data`, but with the cipher set up according to the given settings.
+///
+/// The grouping of the ciphertext (if any) is ignored.
+pub fn decrypt_data_with(
+    cipher: &str,
+    data: &str,
+    key: &str,
+    settings: &Settings,
+) -> Result<String, CipherError> {
+    let data = settings.format.strip(data);
 
-            solitaire_c.decrypt(data)
-        }
-        _ => {
-            panic!("This cipher has not yet been implemented or it doesn't exist.");
-        }
-    }
+    registry::build_cipher_with(cipher, key, settings)?.decrypt(&data)
 }
 
 #[cfg(test)]
@@ -136,36 +103,192 @@ mod tests {
         struct T {}
 
         impl Cipher for T {
-            fn encrypt(&self, _plaintext: &str) -> String {
-                String::new()
+            fn encrypt(&self, _plaintext: &str) -> Result<String, CipherError> {
+                Ok(String::new())
             }
-            fn decrypt(&self, _ciphertext: &str) -> String {
-                String::new()
+            fn decrypt(&self, _ciphertext: &str) -> Result<String, CipherError> {
+                Ok(String::new())
             }
         }
 
         let input = String::from("awesome_testing_functionality with spaces");
         let expected = String::from("AWESOMETESTINGFUNCTIONALITYWITHSPACES");
-        assert_eq!(expected, T::clean_input(&input));
+        assert_eq!(expected, T {}.clean_input(&input));
 
         let input = String::from("NoW@wITHéé˛Ånumb3rz00712");
         let expected = String::from("NOWWITHNUMB3RZ00712");
-        assert_eq!(expected, T::clean_input(&input));
+        assert_eq!(expected, T {}.clean_input(&input));
+
+        assert_eq!("".to_string(), T {}.clean_input(""));
+    }
 
-        assert_eq!("".to_string(), T::clean_input(""));
+    #[test]
+    fn test_trait_objects() {
+        let ciphers: Vec<Box<dyn Cipher>> = vec![
+            Box::new(ShiftCipher::new(3).unwrap()),
+            Box::new(MonoalphaCipher::new("zyxwvutsrqponmlkjihgfedcba").unwrap()),
+            Box::new(VigenereCipher::new("lemon").unwrap()),
+            Box::new(Scytale::new(4).unwrap()),
+            Box::new(SolitaireCipher::new("cards").unwrap()),
+        ];
+        let plaintext = "attack at dawn";
+
+        for cipher in ciphers.iter() {
+            let ciphertext = cipher.encrypt(plaintext).unwrap();
+
+            assert_eq!(
+                cipher.clean_input(plaintext),
+                cipher.decrypt(&ciphertext).unwrap()
+            );
+        }
     }
 
     #[test]
-    #[should_panic]
     fn test_nonexisting_file() {
-        get_data(Some("nonexisting_file.txt"), Some("completely valid data"));
+        let data = get_data(Some("nonexisting_file.txt"), Some("completely valid data"));
+
+        assert!(matches!(data, Err(CipherError::Io(_))));
     }
 
     #[test]
     fn test_valid_data() {
         let inp = String::from("this is valid data");
-        let outp = get_data(None, Some(&inp));
+        let outp = get_data(None, Some(&inp)).unwrap();
 
         assert_eq!(outp, inp);
     }
+
+    #[test]
+    fn test_unknown_cipher() {
+        let res = encrypt_data("enigma", "attackatdawn", "whatever");
+        assert!(matches!(res, Err(CipherError::UnknownCipher(_))));
+
+        let res = decrypt_data("enigma", "attackatdawn", "whatever");
+        assert!(matches!(res, Err(CipherError::UnknownCipher(_))));
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = Settings {
+            alphabet: Alphabet::italian(),
+            mode: TextMode::preserve_format(),
+            ..Settings::default()
+        };
+        let plaintext = "Nel mezzo del cammin di nostra vita";
+
+        let ciphertext = encrypt_data_with("vigenere", plaintext, "dante", &settings).unwrap();
+        assert_eq!(
+            plaintext,
+            decrypt_data_with("vigenere", &ciphertext, "dante", &settings).unwrap()
+        );
+
+        // 'w' isn't part of the Italian alphabet
+        assert!(encrypt_data_with(
+            "monoalphabetic",
+            plaintext,
+            "zyxwvutsrqponmlkjihgfedcba",
+            &settings
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_digit_settings() {
+        let plaintext = "Agent 007";
+
+        for cipher in ["shift", "monoalphabetic", "vigenere", "solitaire"] {
+            let key = if cipher == "shift" {
+                "7"
+            } else {
+                "qhjwotyrxbkmpiazevnulsgdcf"
+            };
+            let settings = Settings {
+                digits: DigitPolicy::SeparateRing,
+                ..Settings::default()
+            };
+
+            let ciphertext = encrypt_data_with(cipher, plaintext, key, &settings).unwrap();
+            assert!(!ciphertext.ends_with("007"));
+            assert_eq!(
+                "AGENT007",
+                decrypt_data_with(cipher, &ciphertext, key, &settings).unwrap()
+            );
+        }
+
+        // with a combined alphabet, a substitution key must cover the digits too
+        let settings = Settings {
+            digits: DigitPolicy::Combined,
+            ..Settings::default()
+        };
+        assert!(encrypt_data_with(
+            "substitution",
+            plaintext,
+            "qhjwotyrxbkmpiazevnulsgdcf",
+            &settings
+        )
+        .is_err());
+        assert!(encrypt_data_with(
+            "substitution",
+            plaintext,
+            "qhjwotyrxbkmpiazevnulsgdcf0123456789",
+            &settings
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_output_format() {
+        let settings = Settings {
+            format: OutputFormat::grouped(5).with_pad('X').with_lines(2),
+            ..Settings::default()
+        };
+        let plaintext = "Meet me by the old oak tree at noon";
+        let unformatted = encrypt_data("vigenere", plaintext, "lemon").unwrap();
+
+        let ciphertext = encrypt_data_with("vigenere", plaintext, "lemon", &settings).unwrap();
+        let lines = ciphertext.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            format!("{} {}", &unformatted[..5], &unformatted[5..10])
+        );
+        assert_eq!(
+            lines[2],
+            format!("{} {}XXX", &unformatted[20..25], &unformatted[25..])
+        );
+
+        // the grouping is ignored, the nulls are not
+        assert_eq!(
+            "MEETMEBYTHEOLDOAKTREEATNOON",
+            &decrypt_data_with("vigenere", &ciphertext, "lemon", &settings).unwrap()[..27]
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        assert_eq!(
+            encrypt_data("shift", "attackatdawn", "8").unwrap(),
+            encrypt_data("caesar", "attackatdawn", "8").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(matches!(
+            encrypt_data("shift", "attackatdawn", "three"),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            decrypt_data("scytale", "attackatdawn", "-4"),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            encrypt_data("vigenere", "attackatdawn", ""),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            encrypt_data("monoalphabetic", "attackatdawn", "abc"),
+            Err(CipherError::InvalidKey(_))
+        ));
+    }
 }
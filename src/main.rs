@@ -1,26 +1,440 @@
 /// This crate implements a bunch of classical ciphers.
 mod opts;
 
-use classical_cryptography::{decrypt_data, encrypt_data, get_data};
+use clap::ArgMatches;
+use classical_cryptography::{
+    analysis::{
+        chi_squared,
+        crack::{
+            crack_columnar, crack_scytale, crack_shift, crack_vigenere, transposition, vigenere,
+            Candidate, SubstitutionSolver,
+        },
+        english, identify, LanguageModel, NgramCounts, Statistics,
+    },
+    ciphers::solitaire::Deck,
+    decrypt_data_with, encrypt_data_with, get_data,
+    keys::SolitaireKey,
+    registry, Alphabet, CipherError, DigitPolicy, KeyAdvance, LetterCase, OutputFormat, Settings,
+    TextMode,
+};
 use opts::get_opts;
 
+use std::fmt::Display;
+use std::process;
+
 fn main() {
     let opts = get_opts();
 
+    if let Err(err) = run(&opts) {
+        eprintln!("Error: {}", err);
+        process::exit(exit_code(&err));
+    }
+}
+
+fn run(opts: &ArgMatches) -> Result<(), CipherError> {
+    if opts.subcommand_matches("list-ciphers").is_some() {
+        list_ciphers();
+
+        return Ok(());
+    }
+    if let Some(analyze_opts) = opts.subcommand_matches("analyze") {
+        return analyze(analyze_opts);
+    }
+    if let Some(identify_opts) = opts.subcommand_matches("identify") {
+        return print_identification(identify_opts);
+    }
+    if let Some(crack_opts) = opts.subcommand_matches("crack") {
+        return crack(crack_opts);
+    }
+    if let Some(train_opts) = opts.subcommand_matches("train-model") {
+        return train_model(train_opts);
+    }
+
     let cipher = opts.value_of("cipher").unwrap();
     let key = opts.value_of("key").unwrap();
+    if opts.is_present("dump-deck") {
+        return print_deck(cipher, key, opts.value_of("dump-deck"));
+    }
+    let settings = get_settings(opts)?;
+    // a bad key is better found out before waiting for the input
+    registry::check_key(cipher, key, &settings)?;
 
-    let data = get_data(opts.value_of("file"), opts.value_of("data"));
+    let data = get_data(opts.value_of("file"), opts.value_of("data"))?;
 
     // no fancy stuff output-wise to allow people to easily edit the output when
     // redirecting to other files/using pipes
     if opts.is_present("encrypt") {
-        let encrypted = encrypt_data(cipher, &data, key);
+        let encrypted = encrypt_data_with(cipher, &data, key, &settings)?;
         println!("Encrypted data:\n\n{}\n", encrypted);
     } else if opts.is_present("decrypt") {
-        let decrypted = decrypt_data(cipher, &data, key);
+        let decrypted = decrypt_data_with(cipher, &data, key, &settings)?;
         println!("Decrypted data:\n\n{}\n", decrypted);
     } else {
         panic!("You have to specify whether you want to decrypt or encrypt!");
     }
+
+    Ok(())
+}
+
+/// Builds the settings of the cipher (alphabet, text mode, digit policy, output format) out of
+/// the CLI opts.
+fn get_settings(opts: &ArgMatches) -> Result<Settings, CipherError> {
+    let alphabet = match opts.value_of("alphabet") {
+        Some(alphabet) => alphabet.parse::<Alphabet>()?,
+        None => Alphabet::default(),
+    };
+
+    let mode = if !opts.is_present("preserve-format") {
+        TextMode::Clean
+    } else if opts.is_present("advance-key-on-skipped") {
+        TextMode::PreserveFormat(KeyAdvance::EveryCharacter)
+    } else {
+        TextMode::PreserveFormat(KeyAdvance::SymbolsOnly)
+    };
+
+    let digits = match opts.value_of("digits") {
+        Some("strip") => DigitPolicy::Strip,
+        Some("spell-out") => DigitPolicy::SpellOut,
+        Some("separate-ring") => DigitPolicy::SeparateRing,
+        Some("combined") => DigitPolicy::Combined,
+        _ => DigitPolicy::PassThrough,
+    };
+
+    // the values have already been validated by clap
+    let number = |name| opts.value_of(name).map_or(0, |v| v.parse().unwrap());
+    let format = OutputFormat {
+        group_size: number("group"),
+        groups_per_line: number("line-groups"),
+        pad: opts.value_of("pad").and_then(|v| v.chars().next()),
+        case: match opts.value_of("case") {
+            Some("upper") => LetterCase::Upper,
+            Some("lower") => LetterCase::Lower,
+            _ => LetterCase::AsIs,
+        },
+    };
+
+    Ok(Settings {
+        alphabet,
+        mode,
+        digits,
+        format,
+    })
+}
+
+/// Prints a table with the available ciphers.
+fn list_ciphers() {
+    for info in registry::ciphers() {
+        let aliases = if info.aliases.is_empty() {
+            String::from("-")
+        } else {
+            info.aliases.join(", ")
+        };
+
+        println!(
+            "{:<16} aliases: {:<16} key: {}",
+            info.name, aliases, info.key_description
+        );
+    }
+}
+
+/// Prints a histogram of the n-gram frequencies of the text, next to the most common English
+/// n-grams (when counting English letters), followed by the chi-squared statistic.
+fn analyze(opts: &ArgMatches) -> Result<(), CipherError> {
+    const BAR_WIDTH: f64 = 40.0;
+
+    let data = get_data(opts.value_of("file"), opts.value_of("data"))?;
+    let alphabet = match opts.value_of("alphabet") {
+        Some(alphabet) => alphabet.parse::<Alphabet>()?,
+        None => Alphabet::default(),
+    };
+    // the values have already been validated by clap
+    let n = opts.value_of("ngram").map_or(1, |v| v.parse().unwrap());
+    let rows = opts.value_of("top").map_or(26, |v| v.parse().unwrap());
+
+    let counts = NgramCounts::count(&data, n, &alphabet)?;
+    let reference = english::ngrams(n).filter(|_| alphabet == Alphabet::english());
+    let name = match n {
+        1 => "Letter",
+        2 => "Bigram",
+        _ => "Trigram",
+    };
+
+    println!("{} frequencies ({} in total):\n", name, counts.total());
+
+    let ranked = counts.ranked();
+    let expected = reference.map(|r| r.ranked()).unwrap_or_default();
+    let highest = ranked
+        .first()
+        .map_or(0.0, |(ngram, _)| counts.frequency(ngram));
+
+    for row in 0..rows.min(ranked.len().max(expected.len())) {
+        let observed = match ranked.get(row) {
+            Some((ngram, count)) => {
+                let frequency = counts.frequency(ngram);
+                let bar = "#".repeat((frequency / highest * BAR_WIDTH).round() as usize);
+
+                format!(
+                    "{:<3} {:>6} {:>6.2}%  {:<width$}",
+                    ngram,
+                    count,
+                    frequency * 100.0,
+                    bar,
+                    width = BAR_WIDTH as usize
+                )
+            }
+            None => " ".repeat(BAR_WIDTH as usize + 20),
+        };
+
+        match expected.get(row) {
+            Some((ngram, frequency)) => {
+                println!("{}  {:<3} {:>6.2}%", observed, ngram, frequency * 100.0)
+            }
+            None => println!("{}", observed.trim_end()),
+        }
+    }
+
+    if let Some(reference) = reference {
+        println!(
+            "\nChi-squared against English: {:.2}",
+            chi_squared(&counts, reference)?
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Prints the statistics of a ciphertext and the ciphers they point to.
+fn print_identification(opts: &ArgMatches) -> Result<(), CipherError> {
+    let ciphertext = get_data(opts.value_of("file"), opts.value_of("data"))?;
+    let statistics = Statistics::compute(&ciphertext);
+    let guesses = identify(&statistics)?;
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    println!("Statistics:\n");
+    println!(
+        "Letters:                   {} ({} different, even: {})",
+        statistics.letters,
+        statistics.distinct_letters,
+        yes_no(statistics.even_length)
+    );
+    println!(
+        "Digits:                    {}",
+        yes_no(statistics.has_digits)
+    );
+    println!(
+        "Index of coincidence:      {:.4} (English: {:.4}, random: {:.4})",
+        statistics.index_of_coincidence,
+        english::INDEX_OF_COINCIDENCE,
+        english::RANDOM_INDEX_OF_COINCIDENCE
+    );
+    match statistics.periodic_ic {
+        Some((period, ic)) => println!("Best periodic IC:          {:.4} (period {})", ic, period),
+        None => println!("Best periodic IC:          -"),
+    }
+    println!(
+        "Chi-squared per letter:    {:.2} (shifted by {}: {:.2}, sorted: {:.2})",
+        statistics.chi_squared,
+        statistics.shifted_chi_squared.0,
+        statistics.shifted_chi_squared.1,
+        statistics.sorted_chi_squared
+    );
+
+    println!("\nLikely ciphers:\n");
+    for guess in guesses {
+        println!(
+            "{:>6.2}%  {:<15} ({})",
+            guess.likelihood * 100.0,
+            guess.family.to_string(),
+            guess.family.ciphers().join(", ")
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Runs the solver of the chosen cipher on the ciphertext.
+fn crack(opts: &ArgMatches) -> Result<(), CipherError> {
+    match opts.subcommand() {
+        ("shift", Some(opts)) => {
+            let ciphertext = get_data(opts.value_of("file"), opts.value_of("data"))?;
+
+            print_candidates(opts, &crack_shift(&ciphertext)?);
+        }
+        ("vigenere", Some(opts)) => {
+            let ciphertext = get_data(opts.value_of("file"), opts.value_of("data"))?;
+            // already validated by clap
+            let period = opts.value_of("period").map(|v| v.parse().unwrap());
+
+            if period.is_none() {
+                print_periods(&ciphertext);
+            }
+            print_candidates(opts, &crack_vigenere(&ciphertext, period)?);
+        }
+        ("substitution", Some(opts)) => {
+            let ciphertext = get_data(opts.value_of("file"), opts.value_of("data"))?;
+
+            // the values have already been validated by clap
+            let mut solver = SubstitutionSolver::new();
+            if let Some(restarts) = opts.value_of("restarts") {
+                solver = solver.with_restarts(restarts.parse().unwrap());
+            }
+            if let Some(seed) = opts.value_of("seed") {
+                solver = solver.with_seed(seed.parse().unwrap());
+            }
+            for crib in opts.values_of("crib").into_iter().flatten() {
+                let (plain, cipher) = crib.split_once('=').unwrap();
+
+                solver = solver.with_crib(
+                    plain.chars().next().unwrap(),
+                    cipher.chars().next().unwrap(),
+                )?;
+            }
+
+            print_candidates(opts, &[solver.solve(&ciphertext)?]);
+        }
+        ("scytale", Some(opts)) => {
+            let ciphertext = get_data(opts.value_of("file"), opts.value_of("data"))?;
+
+            print_candidates(opts, &crack_scytale(&ciphertext)?);
+        }
+        ("columnar", Some(opts)) => {
+            let ciphertext = get_data(opts.value_of("file"), opts.value_of("data"))?;
+            // already validated by clap
+            let max_columns = opts
+                .value_of("max-columns")
+                .map_or(transposition::MAX_COLUMNS, |v| v.parse().unwrap());
+
+            print_candidates(opts, &crack_columnar(&ciphertext, max_columns)?);
+        }
+        _ => unreachable!("clap requires one of the solvers"),
+    }
+
+    Ok(())
+}
+
+/// Prints what the different estimates of the period of a Vigenere cipher say.
+fn print_periods(ciphertext: &str) {
+    let list = |periods: Vec<String>| match periods.is_empty() {
+        true => String::from("-"),
+        false => periods.join(", "),
+    };
+
+    let kasiski = vigenere::kasiski(ciphertext, vigenere::MAX_PERIOD)
+        .into_iter()
+        .take(5)
+        .map(|(period, votes)| format!("{} ({} votes)", period, votes))
+        .collect();
+    let coincidence = vigenere::estimate_periods(ciphertext, vigenere::MAX_PERIOD)
+        .into_iter()
+        .take(5)
+        .map(|(period, ic)| format!("{} ({:.4})", period, ic))
+        .collect();
+    let friedman =
+        vigenere::friedman(ciphertext).map_or(String::from("-"), |f| format!("{:.1}", f));
+
+    println!("Likely periods:\n");
+    println!("Kasiski examination:     {}", list(kasiski));
+    println!("Index of coincidence:    {}", list(coincidence));
+    println!("Friedman test:           {}\n", friedman);
+}
+
+/// Prints a table with the best candidates found by a solver, followed by the whole plaintext
+/// of the best one.
+fn print_candidates<K: Display>(opts: &ArgMatches, candidates: &[Candidate<K>]) {
+    const PREVIEW: usize = 50;
+
+    // the value has already been validated by clap
+    let rows = opts.value_of("top").map_or(5, |v| v.parse().unwrap());
+
+    println!("Best candidates:\n");
+    for candidate in candidates.iter().take(rows) {
+        let line = candidate.plaintext.lines().next().unwrap_or_default();
+        let preview = match line.char_indices().nth(PREVIEW) {
+            Some((end, _)) => format!("{}...", &line[..end]),
+            None => line.to_string(),
+        };
+
+        println!(
+            "{:<16} {:>12.2}   {}",
+            candidate.key.to_string(),
+            candidate.score,
+            preview
+        );
+    }
+
+    if let Some(best) = candidates.first() {
+        println!("\nBest guess (key {}):\n\n{}\n", best.key, best.plaintext);
+    }
+}
+
+/// Prints the Solitaire deck as it is after keying and, if asked to, after generating a number
+/// of keystream values, so that a deck keyed by hand can be checked card by card.
+fn print_deck(cipher: &str, key: &str, steps: Option<&str>) -> Result<(), CipherError> {
+    if registry::lookup(cipher)?.name != "solitaire" {
+        clap::Error::with_description(
+            &format!(
+                "only the solitaire cipher has a deck to dump, not {}",
+                cipher
+            ),
+            clap::ErrorKind::ArgumentConflict,
+        )
+        .exit();
+    }
+
+    let key = key.parse::<SolitaireKey>()?;
+    let mut deck = Deck::keyed(&key);
+
+    println!("Deck after keying:\n\n{}\n", deck);
+
+    if let Some(steps) = steps {
+        // already validated by clap
+        let steps = steps.parse::<usize>().unwrap();
+        for _ in 0..steps {
+            deck.get_output_card();
+        }
+
+        println!("Deck after {} keystream values:\n\n{}\n", steps, deck);
+    }
+
+    Ok(())
+}
+
+/// Trains a language model on the given files, then saves or prints it.
+fn train_model(opts: &ArgMatches) -> Result<(), CipherError> {
+    let alphabet = match opts.value_of("alphabet") {
+        Some(alphabet) => alphabet.parse::<Alphabet>()?,
+        None => Alphabet::default(),
+    };
+    // already validated by clap
+    let min_count = opts.value_of("min-count").map_or(1, |v| v.parse().unwrap());
+
+    let mut builder =
+        LanguageModel::builder(opts.value_of("name").unwrap(), alphabet)?.with_min_count(min_count);
+    for file in opts.values_of("file").unwrap() {
+        builder = builder.with_file(file)?;
+    }
+    let model = builder.build();
+
+    match opts.value_of("output") {
+        Some(output) => model.save(output)?,
+        None => print!("{}", model),
+    }
+
+    Ok(())
+}
+
+/// Maps each kind of error to its own exit code, so that scripts can tell them apart (1 is
+/// already taken by clap for usage errors).
+fn exit_code(err: &CipherError) -> i32 {
+    match err {
+        CipherError::InvalidKey(_) => 2,
+        CipherError::UnknownCipher(_) => 3,
+        CipherError::InvalidSymbol(_) => 4,
+        CipherError::Io(_) => 5,
+        CipherError::DuplicateCipher(_) => 6,
+        CipherError::InvalidModel(_) => 7,
+        CipherError::InvalidLength(_) => 8,
+    }
 }
@@ -1,17 +1,228 @@
 /// This module is meant to store anything relating to the managment of CLI opts (/args)
 extern crate clap;
-use clap::{App, Arg, ArgMatches};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use classical_cryptography::registry;
 
 pub fn get_opts() -> ArgMatches<'static> {
+    let ciphers = registry::ciphers();
+
+    let names = ciphers.iter().map(|info| info.name).collect::<Vec<&str>>();
+    let cipher_help = format!(
+        "Available options are: {} (use the list-ciphers command to see their aliases).",
+        names.join(", ")
+    );
+
+    let key_descriptions = ciphers
+        .iter()
+        .map(|info| format!("    {}: {}", info.name, info.key_description))
+        .collect::<Vec<String>>();
+    let key_help = format!(
+        "Specifies the key used to decrypt/encrypt. What a key looks like depends on the cipher:\n{}",
+        key_descriptions.join("\n")
+    );
+
     App::new("-- Classical Crypto Toolkit --")
         .about("A collection of classical ciphers.")
         .help_message("Prints help information (--help for more details).")
         .version_message("Prints version information.")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        // the data is free text, which mustn't be mistaken for a (mistyped) subcommand
+        .setting(AppSettings::ArgsNegateSubcommands)
+        .setting(AppSettings::DisableHelpSubcommand)
+        .subcommand(
+            SubCommand::with_name("list-ciphers")
+                .about("Lists the available ciphers, along with their aliases and keys."),
+        )
+        .subcommand(
+            SubCommand::with_name("analyze")
+                .about("Prints the frequencies of the letters (or of longer n-grams) of a text, compared with English.")
+                .arg(
+                    Arg::with_name("data")
+                        .help("Specifies the text to analyze (in case no file has been specified with -f).")
+                        .takes_value(true)
+                        .required_unless("file")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Specifies a file to read the text from.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("ngram")
+                        .short("n")
+                        .long("ngram")
+                        .value_name("N")
+                        .help("Counts the n-grams of the given length (1 by default).")
+                        .possible_values(&["1", "2", "3"])
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("alphabet")
+                        .short("a")
+                        .long("alphabet")
+                        .value_name("ALPHABET")
+                        .help("Specifies the symbols to count (english by default, the only one compared with a reference).")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .value_name("ROWS")
+                        .help("Only prints the ROWS most common n-grams (26 by default).")
+                        .takes_value(true)
+                        .validator(is_number),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("identify")
+                .about("Guesses which cipher produced a ciphertext, from its statistics.")
+                .arg(
+                    Arg::with_name("data")
+                        .help("Specifies the ciphertext (in case no file has been specified with -f).")
+                        .takes_value(true)
+                        .required_unless("file")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Specifies a file to read the ciphertext from.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("crack")
+                .about("Recovers the key (and the plaintext) of a ciphertext, knowing only the cipher.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    crack_subcommand("shift")
+                        .about("Breaks a shift cipher by trying all the shifts."),
+                )
+                .subcommand(
+                    crack_subcommand("vigenere")
+                        .about("Breaks a Vigenere cipher, estimating the period first.")
+                        .arg(
+                            Arg::with_name("period")
+                                .long("period")
+                                .value_name("LENGTH")
+                                .help("Uses the given period (the length of the keyword) instead of estimating it.")
+                                .takes_value(true)
+                                .validator(|v| match v.parse::<usize>() {
+                                    Ok(n) if n > 0 => Ok(()),
+                                    _ => Err(format!("expected a number greater than 0, got '{}'", v)),
+                                }),
+                        ),
+                )
+                .subcommand(
+                    crack_subcommand("substitution")
+                        .about("Breaks a monoalphabetic substitution by hill climbing on quadgram statistics.")
+                        .arg(
+                            Arg::with_name("crib")
+                                .long("crib")
+                                .value_name("PLAIN=CIPHER")
+                                .help("Locks a plaintext letter to the ciphertext letter it is known to become (e.g. E=X), can be repeated.")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .validator(|v| match v.split_once('=') {
+                                    Some((plain, cipher)) if plain.chars().count() == 1 && cipher.chars().count() == 1 => Ok(()),
+                                    _ => Err(format!("expected a pair of letters like E=X, got '{}'", v)),
+                                }),
+                        )
+                        .arg(
+                            Arg::with_name("restarts")
+                                .long("restarts")
+                                .value_name("N")
+                                .help("Climbs N times, from different keys (20 by default).")
+                                .takes_value(true)
+                                .validator(is_number),
+                        )
+                        .arg(
+                            Arg::with_name("seed")
+                                .long("seed")
+                                .value_name("SEED")
+                                .help("Seeds the random keys the climbs start from (0 by default).")
+                                .takes_value(true)
+                                .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| format!("expected a number, got '{}'", v))),
+                        ),
+                )
+                .subcommand(
+                    crack_subcommand("scytale")
+                        .about("Breaks a scytale by trying all the lengths."),
+                )
+                .subcommand(
+                    crack_subcommand("columnar")
+                        .about("Breaks a columnar transposition, trying all the orders of the columns of narrow grids and anagramming the wider ones.")
+                        .arg(
+                            Arg::with_name("max-columns")
+                                .long("max-columns")
+                                .value_name("N")
+                                .help("Tries grids up to N columns wide (12 by default).")
+                                .takes_value(true)
+                                .validator(|v| match v.parse::<usize>() {
+                                    Ok(n) if n > 1 => Ok(()),
+                                    _ => Err(format!("expected a number greater than 1, got '{}'", v)),
+                                }),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("train-model")
+                .about("Trains a language model (the log-probabilities of the n-grams of a language) on some text files.")
+                .arg(
+                    Arg::with_name("name")
+                        .help("Specifies the name of the model (usually the language).")
+                        .required(true)
+                        .takes_value(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Specifies a file to train the model on, can be repeated.")
+                        .required(true)
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("alphabet")
+                        .short("a")
+                        .long("alphabet")
+                        .value_name("ALPHABET")
+                        .help("Specifies the alphabet the language is written in (english by default).")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("min-count")
+                        .long("min-count")
+                        .value_name("N")
+                        .help("Leaves out the n-grams appearing less than N times, to make the model smaller (1 by default).")
+                        .takes_value(true)
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Saves the model to FILE instead of printing it.")
+                        .takes_value(true),
+                ),
+        )
         .arg(
             Arg::with_name("cipher")
                 .help("Specifies the cipher to use (use --help to see a complete list).")
-                // TODO put all these options in a config file
-                .long_help("Available options are: shift, monoalphabetic, scytale, vigenere and solitaire.")
+                .long_help(&cipher_help)
                 .required(true)
                 .takes_value(true)
                 .index(1),
@@ -19,6 +230,7 @@ pub fn get_opts() -> ArgMatches<'static> {
         .arg(
             Arg::with_name("key")
                 .help("Specifies the key used to decrypt/encrypt.")
+                .long_help(&key_help)
                 .required(true)
                 .takes_value(true)
                 .index(2),
@@ -26,7 +238,7 @@ pub fn get_opts() -> ArgMatches<'static> {
         .arg(Arg::with_name("data")
                 .help("Specifies the data to read from stdin (in case no file has been specified with -f).")
                 .takes_value(true)
-                .required_unless("file")
+                .required_unless_one(&["file", "dump-deck"])
                 .index(3)
             )
         .arg(
@@ -36,7 +248,7 @@ pub fn get_opts() -> ArgMatches<'static> {
                 .help(
                     "Instructs the binary to do encryption. If unspecified, the -d option must be.",
                 )
-                .required_unless("decrypt"),
+                .required_unless_one(&["decrypt", "dump-deck"]),
         )
         .arg(
             Arg::with_name("decrypt")
@@ -45,7 +257,7 @@ pub fn get_opts() -> ArgMatches<'static> {
                 .help(
                     "Instructs the binary to do decryption. If unspecified, the -e option must be.",
                 )
-                .required_unless("encrypt"),
+                .required_unless_one(&["encrypt", "dump-deck"]),
         )
         .arg(
             Arg::with_name("file")
@@ -54,7 +266,136 @@ pub fn get_opts() -> ArgMatches<'static> {
                 .value_name("FILE")
                 .help("Specifies a file to read from. If not specified, stdin is used.")
                 .takes_value(true)
-                .required_unless("data"),
+                .required_unless_one(&["data", "dump-deck"]),
+        )
+        .arg(
+            Arg::with_name("dump-deck")
+                .long("dump-deck")
+                .value_name("N")
+                .help("Prints the deck of the solitaire cipher after keying (and after N keystream values) instead of encrypting/decrypting.")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .validator(is_number)
+                .conflicts_with_all(&["encrypt", "decrypt", "data", "file"]),
+        )
+        .arg(
+            Arg::with_name("alphabet")
+                .short("a")
+                .long("alphabet")
+                .value_name("ALPHABET")
+                .help("Specifies the alphabet the cipher works on (english by default).")
+                .long_help(
+                    "Specifies the alphabet the cipher works on. Either one of the predefined ones \
+                    (english, italian, german, french, latin, alphanumeric) or the list of symbols making up \
+                    a custom one. Defaults to english.",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("preserve-format")
+                .short("p")
+                .long("preserve-format")
+                .help("Only transforms the symbols of the alphabet, keeping spaces, punctuation and case in place."),
+        )
+        .arg(
+            Arg::with_name("advance-key-on-skipped")
+                .long("advance-key-on-skipped")
+                .help("Advances the key of polyalphabetic ciphers over the characters left untouched as well.")
+                .requires("preserve-format"),
+        )
+        .arg(
+            Arg::with_name("digits")
+                .long("digits")
+                .value_name("POLICY")
+                .help("Specifies what happens to the digits (pass-through by default).")
+                .long_help(
+                    "Specifies what happens to the digits that are not part of the alphabet: \
+                    pass-through leaves them untouched, strip removes them, spell-out turns them \
+                    into words (ONE, TWO, ...), separate-ring encrypts them among themselves and \
+                    combined appends them to the alphabet. Defaults to pass-through.",
+                )
+                .possible_values(&[
+                    "pass-through",
+                    "strip",
+                    "spell-out",
+                    "separate-ring",
+                    "combined",
+                ])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("group")
+                .short("g")
+                .long("group")
+                .value_name("SIZE")
+                .help("Splits the ciphertext in groups of SIZE characters (e.g. 5).")
+                .takes_value(true)
+                .validator(is_number),
+        )
+        .arg(
+            Arg::with_name("line-groups")
+                .long("line-groups")
+                .value_name("GROUPS")
+                .help("Puts GROUPS groups on each line of the ciphertext.")
+                .takes_value(true)
+                .validator(is_number)
+                .requires("group"),
+        )
+        .arg(
+            Arg::with_name("pad")
+                .long("pad")
+                .value_name("CHAR")
+                .help("Completes the last group of the ciphertext with nulls (e.g. X).")
+                .takes_value(true)
+                .validator(|v| match v.chars().count() {
+                    1 => Ok(()),
+                    _ => Err(String::from("the null must be a single character")),
+                })
+                .requires("group"),
+        )
+        .arg(
+            Arg::with_name("case")
+                .long("case")
+                .value_name("CASE")
+                .help("Prints the ciphertext in upper or lower case.")
+                .possible_values(&["upper", "lower"])
+                .takes_value(true),
         )
         .get_matches()
 }
+
+/// The subcommand breaking the given cipher, with the arguments shared by all the solvers.
+fn crack_subcommand(name: &'static str) -> App<'static, 'static> {
+    SubCommand::with_name(name)
+        .arg(
+            Arg::with_name("data")
+                .help("Specifies the ciphertext (in case no file has been specified with -f).")
+                .takes_value(true)
+                .required_unless("file")
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("file")
+                .short("f")
+                .long("file")
+                .value_name("FILE")
+                .help("Specifies a file to read the ciphertext from.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("top")
+                .long("top")
+                .value_name("ROWS")
+                .help("Prints the ROWS best candidates (5 by default).")
+                .takes_value(true)
+                .validator(is_number),
+        )
+}
+
+/// Checks that a value is a (non-negative) number.
+fn is_number(v: String) -> Result<(), String> {
+    v.parse::<usize>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a number, got '{}'", v))
+}
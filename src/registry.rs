@@ -0,0 +1,905 @@
+/// This module keeps track of all the available ciphers, so that everything that has to list or
+/// look up ciphers by name (the library entry points, the CLI, ...) is derived from one table.
+use crate::ciphers::polyalphabetic::{Keystream, Tableau};
+use crate::ciphers::quagmire::Quagmire;
+use crate::keys::{
+    AdfgvxKey, AffineKey, BifidKey, ColumnarKey, DoubleColumnarKey, GronsfeldKey, HillKey,
+    KeywordAlphabet, PlayfairKey, RouteKey, ScytaleLength, ShiftKey, SolitaireKey,
+    SubstitutionAlphabet, TrifidKey, VigenereKeyword,
+};
+use crate::text::{DigitPolicy, OutputFormat, TextMode};
+use crate::{
+    AdfgvxCipher, AffineCipher, BifidCipher, ColumnarCipher, DoubleColumnarCipher, HillCipher,
+    MonoalphaCipher, PlayfairCipher, PolyalphabeticCipher, QuagmireCipher, RouteCipher, Scytale,
+    ShiftCipher, SolitaireCipher, TrifidCipher, VigenereCipher,
+};
+use crate::{Alphabet, Cipher, CipherError};
+
+use std::fs::read_to_string;
+use std::sync::{OnceLock, RwLock};
+
+/// Checks whether a key is valid for a given cipher, without building the cipher itself.
+pub type KeyParser = fn(&str, &Settings) -> Result<(), CipherError>;
+
+/// Builds a cipher given its key.
+pub type CipherFactory = fn(&str, &Settings) -> Result<Box<dyn Cipher>, CipherError>;
+
+/// The settings a cipher is built with, on top of its key.
+///
+/// Ciphers are free to ignore the settings that make no sense for them (e.g. a transposition
+/// cipher doesn't care about the alphabet).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    /// The alphabet the cipher works on.
+    pub alphabet: Alphabet,
+    /// How the characters that are not part of the alphabet are treated.
+    pub mode: TextMode,
+    /// What happens to the digits.
+    pub digits: DigitPolicy,
+    /// How the ciphertext is laid out (applied by `encrypt_data_with` and undone by
+    /// `decrypt_data_with`, the ciphers themselves don't care).
+    pub format: OutputFormat,
+}
+
+impl Settings {
+    /// The alphabet the cipher ends up working on, which includes the digits if they are to be
+    /// encrypted along with the symbols.
+    pub fn effective_alphabet(&self) -> Alphabet {
+        match self.digits {
+            DigitPolicy::Combined => self.alphabet.clone().with_digits(),
+            _ => self.alphabet.clone(),
+        }
+    }
+}
+
+/// Everything there is to know about a cipher in order to use it by name.
+#[derive(Clone, Copy)]
+pub struct CipherInfo {
+    /// The name the cipher is known by.
+    pub name: &'static str,
+    /// Other names that can be used in place of the main one.
+    pub aliases: &'static [&'static str],
+    /// A short, human readable description of what the key looks like.
+    pub key_description: &'static str,
+    /// Validates the key before any data is touched.
+    pub parse_key: KeyParser,
+    /// Builds the cipher out of a (valid) key.
+    pub factory: CipherFactory,
+}
+
+impl CipherInfo {
+    /// Checks whether the cipher goes by the given name (either the main one or an alias).
+    ///
+    /// The comparison is case insensitive.
+    pub fn is_called(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        std::iter::once(&self.name)
+            .chain(self.aliases.iter())
+            .any(|alias| alias.to_lowercase() == name)
+    }
+}
+
+/// A table of ciphers, which can be looked up by name.
+pub struct Registry {
+    entries: Vec<CipherInfo>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Registry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Creates a registry holding all the ciphers implemented by this crate.
+    pub fn with_builtins() -> Self {
+        let mut registry = Registry::new();
+
+        for info in builtins() {
+            registry
+                .register(info)
+                .expect("The built-in ciphers must have unique names.");
+        }
+
+        registry
+    }
+
+    /// Adds a cipher to the registry.
+    ///
+    /// Fails if the cipher's name or any of its aliases is already taken.
+    pub fn register(&mut self, info: CipherInfo) -> Result<(), CipherError> {
+        let names = std::iter::once(&info.name).chain(info.aliases.iter());
+
+        for name in names {
+            if self.entries.iter().any(|entry| entry.is_called(name)) {
+                return Err(CipherError::DuplicateCipher(name.to_string()));
+            }
+        }
+        self.entries.push(info);
+
+        Ok(())
+    }
+
+    /// Looks up a cipher by name (or alias).
+    pub fn get(&self, name: &str) -> Result<&CipherInfo, CipherError> {
+        self.entries
+            .iter()
+            .find(|entry| entry.is_called(name))
+            .ok_or_else(|| CipherError::UnknownCipher(name.to_string()))
+    }
+
+    /// Checks the key (and the settings) of the cipher going by the given name with its parse_key,
+    /// without building the cipher: this is meant to be done before reading any input.
+    pub fn check_key(&self, name: &str, key: &str, settings: &Settings) -> Result<(), CipherError> {
+        let info = self.get(name)?;
+
+        (info.parse_key)(key, settings)
+    }
+
+    /// Builds the cipher going by the given name, using the given key.
+    pub fn build(&self, name: &str, key: &str) -> Result<Box<dyn Cipher>, CipherError> {
+        self.build_with(name, key, &Settings::default())
+    }
+
+    /// Builds the cipher going by the given name, using the given key and settings (the factory
+    /// validates the key itself, `check_key` is only there to fail early).
+    pub fn build_with(
+        &self,
+        name: &str,
+        key: &str,
+        settings: &Settings,
+    ) -> Result<Box<dyn Cipher>, CipherError> {
+        let info = self.get(name)?;
+
+        (info.factory)(key, settings)
+    }
+
+    /// Iterates over the registered ciphers, in the order they were registered in.
+    pub fn iter(&self) -> impl Iterator<Item = &CipherInfo> {
+        self.entries.iter()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+/// The registry used by the library entry points, holding the built-in ciphers plus any cipher
+/// registered at runtime.
+fn global() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| RwLock::new(Registry::with_builtins()))
+}
+
+/// Registers a new cipher, making it available by name to the whole crate (e.g. to
+/// `encrypt_data` and `decrypt_data`).
+pub fn register(info: CipherInfo) -> Result<(), CipherError> {
+    global()
+        .write()
+        .expect("The cipher registry has been poisoned.")
+        .register(info)
+}
+
+/// Lists all the available ciphers.
+pub fn ciphers() -> Vec<CipherInfo> {
+    global()
+        .read()
+        .expect("The cipher registry has been poisoned.")
+        .iter()
+        .copied()
+        .collect()
+}
+
+/// Looks up an available cipher by name (or alias).
+pub fn lookup(name: &str) -> Result<CipherInfo, CipherError> {
+    global()
+        .read()
+        .expect("The cipher registry has been poisoned.")
+        .get(name)
+        .copied()
+}
+
+/// Checks the key (and the settings) of an available cipher, without building it.
+pub fn check_key(name: &str, key: &str, settings: &Settings) -> Result<(), CipherError> {
+    global()
+        .read()
+        .expect("The cipher registry has been poisoned.")
+        .check_key(name, key, settings)
+}
+
+/// Builds an available cipher given its name (or alias) and key.
+pub fn build_cipher(name: &str, key: &str) -> Result<Box<dyn Cipher>, CipherError> {
+    build_cipher_with(name, key, &Settings::default())
+}
+
+/// Builds an available cipher given its name (or alias), key and settings.
+pub fn build_cipher_with(
+    name: &str,
+    key: &str,
+    settings: &Settings,
+) -> Result<Box<dyn Cipher>, CipherError> {
+    global()
+        .read()
+        .expect("The cipher registry has been poisoned.")
+        .build_with(name, key, settings)
+}
+
+/// The ciphers implemented by this crate.
+fn builtins() -> Vec<CipherInfo> {
+    vec![
+        CipherInfo {
+            name: "shift",
+            aliases: &["caesar"],
+            key_description: "an integer, the amount by which to shift",
+            parse_key: |key, _| key.parse::<ShiftKey>().map(|_| ()),
+            factory: |key, settings| {
+                let cipher = ShiftCipher::with_alphabet(key.parse()?, settings.alphabet.clone());
+
+                Ok(Box::new(
+                    cipher.with_mode(settings.mode).with_digits(settings.digits),
+                ))
+            },
+        },
+        CipherInfo {
+            name: "monoalphabetic",
+            aliases: &["substitution"],
+            key_description: "a permutation of the alphabet",
+            parse_key: |key, settings| {
+                SubstitutionAlphabet::parse_for(key, &settings.effective_alphabet()).map(|_| ())
+            },
+            factory: |key, settings| {
+                let alphabet = settings.effective_alphabet();
+                let key = SubstitutionAlphabet::parse_for(key, &alphabet)?;
+                let cipher = MonoalphaCipher::with_alphabet(key, alphabet)?;
+
+                Ok(Box::new(
+                    cipher
+                        .with_mode(settings.mode)
+                        .with_digits(settings.digits)?,
+                ))
+            },
+        },
+        CipherInfo {
+            name: "keyword-substitution",
+            aliases: &["keyword"],
+            key_description: "a keyword, followed by the rest of the alphabet (optionally :last to start after its last letter, or :columnar to read it by columns)",
+            parse_key: |key, _| key.parse::<KeywordAlphabet>().map(|_| ()),
+            factory: |key, settings| {
+                let alphabet = settings.effective_alphabet();
+                let key = key.parse::<KeywordAlphabet>()?.substitution(&alphabet);
+                let cipher = MonoalphaCipher::with_alphabet(key, alphabet)?;
+
+                Ok(Box::new(
+                    cipher
+                        .with_mode(settings.mode)
+                        .with_digits(settings.digits)?,
+                ))
+            },
+        },
+        CipherInfo {
+            name: "affine",
+            aliases: &[],
+            key_description: "two integers a,b (E(x) = ax + b), a coprime with the size of the alphabet",
+            parse_key: |key, settings| affine(key.parse()?, settings).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(affine(key.parse()?, settings)?)),
+        },
+        CipherInfo {
+            name: "multiplicative",
+            aliases: &[],
+            key_description: "an integer coprime with the size of the alphabet, the multiplier",
+            parse_key: |key, settings| multiplicative_key(key).and_then(|key| affine(key, settings)).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(affine(multiplicative_key(key)?, settings)?)),
+        },
+        CipherInfo {
+            name: "atbash",
+            aliases: &[],
+            key_description: "no key needed (whatever is given is ignored)",
+            parse_key: |_, settings| affine(AffineKey::atbash(), settings).map(|_| ()),
+            factory: |_, settings| Ok(Box::new(affine(AffineKey::atbash(), settings)?)),
+        },
+        CipherInfo {
+            name: "vigenere",
+            aliases: &["vigenère"],
+            key_description: "a word made up only of symbols of the alphabet",
+            parse_key: |key, settings| {
+                VigenereKeyword::parse_for(key, &settings.effective_alphabet()).map(|_| ())
+            },
+            factory: |key, settings| {
+                let alphabet = settings.effective_alphabet();
+                let key = VigenereKeyword::parse_for(key, &alphabet)?;
+                let cipher = VigenereCipher::with_alphabet(key, alphabet)?;
+
+                Ok(Box::new(
+                    cipher.with_mode(settings.mode).with_digits(settings.digits),
+                ))
+            },
+        },
+        CipherInfo {
+            name: "beaufort",
+            aliases: &[],
+            key_description: "a word made up only of symbols of the alphabet",
+            parse_key: |key, settings| polyalphabetic("Beaufort", Tableau::Beaufort, keyword, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("Beaufort", Tableau::Beaufort, keyword, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "variant-beaufort",
+            aliases: &[],
+            key_description: "a word made up only of symbols of the alphabet",
+            parse_key: |key, settings| polyalphabetic("variant Beaufort", Tableau::VariantBeaufort, keyword, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("variant Beaufort", Tableau::VariantBeaufort, keyword, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "gronsfeld",
+            aliases: &[],
+            key_description: "a string of digits, the amounts by which to shift",
+            parse_key: |key, settings| polyalphabetic("Gronsfeld", Tableau::Vigenere, gronsfeld, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("Gronsfeld", Tableau::Vigenere, gronsfeld, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "porta",
+            aliases: &["della-porta"],
+            key_description: "a word made up only of symbols of the alphabet (which must have an even number of them)",
+            parse_key: |key, settings| polyalphabetic("Porta", Tableau::Porta, keyword, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("Porta", Tableau::Porta, keyword, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "autokey",
+            aliases: &["plaintext-autokey"],
+            key_description: "a word made up only of symbols of the alphabet, followed by the plaintext",
+            parse_key: |key, settings| polyalphabetic("plaintext autokey", Tableau::Vigenere, plaintext_autokey, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("plaintext autokey", Tableau::Vigenere, plaintext_autokey, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "ciphertext-autokey",
+            aliases: &[],
+            key_description: "a word made up only of symbols of the alphabet, followed by the ciphertext",
+            parse_key: |key, settings| polyalphabetic("ciphertext autokey", Tableau::Vigenere, ciphertext_autokey, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("ciphertext autokey", Tableau::Vigenere, ciphertext_autokey, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "running-key",
+            aliases: &[],
+            key_description: "the path of a text file, at least as long as the message, whose symbols make up the key",
+            parse_key: |key, settings| polyalphabetic("running key", Tableau::Vigenere, running, key, settings).map(|_| ()),
+            factory: |key, settings| {
+                Ok(Box::new(polyalphabetic("running key", Tableau::Vigenere, running, key, settings)?))
+            },
+        },
+        CipherInfo {
+            name: "quagmire1",
+            aliases: &["quagmire-i"],
+            key_description: "the keyword of the plaintext alphabet and the indicator, separated by a comma",
+            parse_key: |key, settings| quagmire(Quagmire::I, key, settings).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(quagmire(Quagmire::I, key, settings)?)),
+        },
+        CipherInfo {
+            name: "quagmire2",
+            aliases: &["quagmire-ii"],
+            key_description: "the keyword of the ciphertext alphabet and the indicator, separated by a comma",
+            parse_key: |key, settings| quagmire(Quagmire::II, key, settings).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(quagmire(Quagmire::II, key, settings)?)),
+        },
+        CipherInfo {
+            name: "quagmire3",
+            aliases: &["quagmire-iii"],
+            key_description: "the keyword of both alphabets and the indicator, separated by a comma",
+            parse_key: |key, settings| quagmire(Quagmire::III, key, settings).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(quagmire(Quagmire::III, key, settings)?)),
+        },
+        CipherInfo {
+            name: "quagmire4",
+            aliases: &["quagmire-iv"],
+            key_description: "the keyword of the plaintext alphabet and the ciphertext one, then the indicator, separated by commas",
+            parse_key: |key, settings| quagmire(Quagmire::IV, key, settings).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(quagmire(Quagmire::IV, key, settings)?)),
+        },
+        CipherInfo {
+            name: "hill",
+            aliases: &[],
+            key_description: "an n×n matrix invertible modulo the size of the alphabet, as n² integers separated by commas or a keyword of n² letters",
+            parse_key: |key, settings| hill(key, settings).map(|_| ()),
+            factory: |key, settings| Ok(Box::new(hill(key, settings)?)),
+        },
+        CipherInfo {
+            name: "playfair",
+            aliases: &[],
+            key_description: "a keyword, optionally followed by :noq (leaving out Q instead of merging I and J) and :<filler letter>",
+            parse_key: |key, settings| {
+                square_settings("Playfair", settings)?;
+
+                key.parse::<PlayfairKey>().map(|_| ())
+            },
+            factory: |key, settings| {
+                square_settings("Playfair", settings)?;
+                let cipher = PlayfairCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)?))
+            },
+        },
+        CipherInfo {
+            name: "bifid",
+            aliases: &[],
+            key_description: "a keyword, optionally followed by :noq (leaving out Q instead of merging I and J) and :<period>",
+            parse_key: |key, settings| {
+                square_settings("Bifid", settings)?;
+
+                key.parse::<BifidKey>().map(|_| ())
+            },
+            factory: |key, settings| {
+                square_settings("Bifid", settings)?;
+                let cipher = BifidCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)?))
+            },
+        },
+        CipherInfo {
+            name: "trifid",
+            aliases: &[],
+            key_description: "a keyword of letters and +, optionally followed by :<period>",
+            parse_key: |key, settings| {
+                square_settings("Trifid", settings)?;
+
+                key.parse::<TrifidKey>().map(|_| ())
+            },
+            factory: |key, settings| {
+                square_settings("Trifid", settings)?;
+                let cipher = TrifidCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)?))
+            },
+        },
+        CipherInfo {
+            name: "adfgx",
+            aliases: &[],
+            key_description: "the keyword of the 5×5 square and the one of the columnar transposition, separated by a comma",
+            parse_key: |key, settings| {
+                square_settings("ADFGX", settings)?;
+
+                key.parse::<AdfgvxKey>().map(|_| ())
+            },
+            factory: |key, settings| {
+                square_settings("ADFGX", settings)?;
+                let cipher = AdfgvxCipher::adfgx_from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)?))
+            },
+        },
+        CipherInfo {
+            name: "adfgvx",
+            aliases: &[],
+            key_description: "the keyword of the 6×6 square (letters and digits) and the one of the columnar transposition, separated by a comma",
+            parse_key: |key, settings| {
+                square_settings("ADFGVX", settings)?;
+
+                key.parse::<AdfgvxKey>().map(|_| ())
+            },
+            factory: |key, settings| {
+                square_settings("ADFGVX", settings)?;
+                let cipher = AdfgvxCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)?))
+            },
+        },
+        CipherInfo {
+            name: "scytale",
+            aliases: &["skytale"],
+            key_description: "a positive integer, the length of the scytale",
+            parse_key: |key, _| key.parse::<ScytaleLength>().map(|_| ()),
+            factory: |key, settings| {
+                let cipher = Scytale::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)))
+            },
+        },
+        CipherInfo {
+            name: "columnar",
+            aliases: &["columnar-transposition"],
+            key_description: "a word, whose letters give the order in which the columns are read",
+            parse_key: |key, _| key.parse::<ColumnarKey>().map(|_| ()),
+            factory: |key, settings| {
+                let cipher = ColumnarCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)))
+            },
+        },
+        CipherInfo {
+            name: "double-columnar",
+            aliases: &["double-transposition"],
+            key_description: "two words separated by a comma, one for each transposition",
+            parse_key: |key, _| key.parse::<DoubleColumnarKey>().map(|_| ()),
+            factory: |key, settings| {
+                let cipher = DoubleColumnarCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)))
+            },
+        },
+        CipherInfo {
+            name: "route",
+            aliases: &[],
+            key_description: "the number of columns and a route (columns, spiral, snake, diagonal), e.g. 6:spiral",
+            parse_key: |key, _| key.parse::<RouteKey>().map(|_| ()),
+            factory: |key, settings| {
+                let cipher = RouteCipher::from_key(key.parse()?);
+
+                Ok(Box::new(cipher.with_digits(settings.digits)))
+            },
+        },
+        CipherInfo {
+            name: "solitaire",
+            aliases: &["pontifex"],
+            key_description: "any string used to seed the shuffle, pass:<passphrase> or deck:<54 cards>",
+            parse_key: |key, _| key.parse::<SolitaireKey>().map(|_| ()),
+            factory: |key, settings| {
+                let cipher =
+                    SolitaireCipher::with_alphabet(key.parse()?, settings.alphabet.clone());
+
+                Ok(Box::new(
+                    cipher.with_mode(settings.mode).with_digits(settings.digits),
+                ))
+            },
+        },
+    ]
+}
+
+/// Builds an affine cipher (or one of its special cases) with the given settings, which is where
+/// the multiplier gets checked against the size of the alphabet.
+fn affine(key: AffineKey, settings: &Settings) -> Result<AffineCipher, CipherError> {
+    AffineCipher::with_alphabet(key, settings.alphabet.clone())?
+        .with_mode(settings.mode)
+        .with_digits(settings.digits)
+}
+
+/// Rejects the settings a cipher working on a square of English letters can't honour: another
+/// alphabet, or keeping the format of the text (its output never lines up with its input).
+fn square_settings(cipher: &str, settings: &Settings) -> Result<(), CipherError> {
+    if settings.alphabet != Alphabet::english() {
+        return Err(CipherError::InvalidKey(format!(
+            "the {} cipher only works on the English alphabet",
+            cipher
+        )));
+    }
+    if settings.mode != TextMode::Clean {
+        return Err(CipherError::InvalidKey(format!(
+            "the {} cipher can't preserve the format of the text",
+            cipher
+        )));
+    }
+
+    Ok(())
+}
+
+/// Builds a Hill cipher with the given settings, which is where the matrix gets checked against
+/// the size of the alphabet.
+fn hill(key: &str, settings: &Settings) -> Result<HillCipher, CipherError> {
+    let key = HillKey::parse_for(key, &settings.alphabet)?;
+
+    HillCipher::with_alphabet(key, settings.alphabet.clone())?.with_digits(settings.digits)
+}
+
+/// Builds a cipher of the Vigenère family with the given settings, the key stream being built by
+/// the given function for the alphabet the cipher ends up working on (and named after the cipher
+/// in its errors).
+fn polyalphabetic(
+    cipher: &str,
+    tableau: Tableau,
+    keystream: fn(&str, &Alphabet, &str) -> Result<Keystream, CipherError>,
+    key: &str,
+    settings: &Settings,
+) -> Result<PolyalphabeticCipher, CipherError> {
+    let alphabet = settings.effective_alphabet();
+    let keystream = keystream(key, &alphabet, cipher)?;
+
+    PolyalphabeticCipher::with_alphabet(tableau, keystream, alphabet)?
+        .with_mode(settings.mode)
+        .with_digits(settings.digits)
+}
+
+fn keyword(key: &str, alphabet: &Alphabet, cipher: &str) -> Result<Keystream, CipherError> {
+    Ok(Keystream::repeating(
+        &VigenereKeyword::parse_named(key, alphabet, cipher)?,
+        alphabet,
+    ))
+}
+
+fn gronsfeld(key: &str, _: &Alphabet, _: &str) -> Result<Keystream, CipherError> {
+    Ok(Keystream::gronsfeld(&key.parse::<GronsfeldKey>()?))
+}
+
+fn plaintext_autokey(
+    key: &str,
+    alphabet: &Alphabet,
+    cipher: &str,
+) -> Result<Keystream, CipherError> {
+    Ok(Keystream::plaintext_autokey(
+        &VigenereKeyword::parse_named(key, alphabet, cipher)?,
+        alphabet,
+    ))
+}
+
+fn ciphertext_autokey(
+    key: &str,
+    alphabet: &Alphabet,
+    cipher: &str,
+) -> Result<Keystream, CipherError> {
+    Ok(Keystream::ciphertext_autokey(
+        &VigenereKeyword::parse_named(key, alphabet, cipher)?,
+        alphabet,
+    ))
+}
+
+/// Reads the text of a running key from the file at the given path.
+fn running(path: &str, alphabet: &Alphabet, _: &str) -> Result<Keystream, CipherError> {
+    Keystream::running(&read_to_string(path.trim())?, alphabet)
+}
+
+/// Builds a Quagmire cipher with the given settings, which is where the indicator gets checked
+/// against the alphabet.
+fn quagmire(
+    variant: Quagmire,
+    key: &str,
+    settings: &Settings,
+) -> Result<QuagmireCipher, CipherError> {
+    QuagmireCipher::with_alphabet(variant, key.parse()?, settings.alphabet.clone())?
+        .with_mode(settings.mode)
+        .with_digits(settings.digits)
+}
+
+/// Parses the key of a multiplicative cipher, a single integer.
+fn multiplicative_key(key: &str) -> Result<AffineKey, CipherError> {
+    key.trim()
+        .parse::<i64>()
+        .map(AffineKey::multiplicative)
+        .map_err(|_| {
+            CipherError::InvalidKey(format!(
+                "the key of a multiplicative cipher must be an integer (the multiplier), got '{}'",
+                key
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rot13;
+
+    impl Cipher for Rot13 {
+        fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+            ShiftCipher::new(13)?.encrypt(plaintext)
+        }
+
+        fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+            ShiftCipher::new(13)?.decrypt(ciphertext)
+        }
+    }
+
+    fn rot13_info(name: &'static str) -> CipherInfo {
+        CipherInfo {
+            name,
+            aliases: &[],
+            key_description: "no key needed",
+            parse_key: |_, _| Ok(()),
+            factory: |_, _| Ok(Box::new(Rot13)),
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let registry = Registry::with_builtins();
+
+        assert_eq!(registry.get("shift").unwrap().name, "shift");
+        assert_eq!(registry.get("Caesar").unwrap().name, "shift");
+        assert_eq!(registry.get("SUBSTITUTION").unwrap().name, "monoalphabetic");
+        assert!(matches!(
+            registry.get("enigma"),
+            Err(CipherError::UnknownCipher(_))
+        ));
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = Registry::new();
+        assert_eq!(registry.iter().count(), 0);
+
+        registry.register(rot13_info("rot13")).unwrap();
+
+        let cipher = registry.build("rot13", "").unwrap();
+        assert_eq!("NGGNPX", cipher.encrypt("attack").unwrap());
+
+        assert!(matches!(
+            registry.register(rot13_info("ROT13")),
+            Err(CipherError::DuplicateCipher(_))
+        ));
+        assert_eq!(registry.iter().count(), 1);
+
+        // names are told apart regardless of the case they were registered in
+        registry.register(rot13_info("MyRot13")).unwrap();
+        assert_eq!(registry.get("MyRot13").unwrap().name, "MyRot13");
+        assert_eq!(registry.get("myrot13").unwrap().name, "MyRot13");
+        assert!(matches!(
+            registry.register(rot13_info("myROT13")),
+            Err(CipherError::DuplicateCipher(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_key() {
+        let mut registry = Registry::with_builtins();
+        registry
+            .register(CipherInfo {
+                parse_key: |key, _| match key {
+                    "" => Ok(()),
+                    _ => Err(CipherError::InvalidKey("ROT13 takes no key".to_string())),
+                },
+                ..rot13_info("rot13")
+            })
+            .unwrap();
+        let settings = Settings::default();
+
+        // the parser registered with the cipher is the one that runs
+        assert!(registry.check_key("rot13", "", &settings).is_ok());
+        assert!(matches!(
+            registry.check_key("ROT13", "3", &settings),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            registry.check_key("rot26", "", &settings),
+            Err(CipherError::UnknownCipher(_))
+        ));
+
+        assert!(registry.check_key("shift", "3", &settings).is_ok());
+        assert!(registry.check_key("shift", "three", &settings).is_err());
+        let italian = Settings {
+            alphabet: Alphabet::italian(),
+            ..Settings::default()
+        };
+        assert!(registry
+            .check_key("playfair", "monarchy", &italian)
+            .is_err());
+    }
+
+    #[test]
+    fn test_register_alias_clash() {
+        let mut registry = Registry::with_builtins();
+        let builtins_count = registry.iter().count();
+
+        // clashes with the alias of the shift cipher
+        assert!(registry.register(rot13_info("caesar")).is_err());
+        assert_eq!(registry.iter().count(), builtins_count);
+    }
+
+    #[test]
+    fn test_build_validates_key() {
+        let registry = Registry::with_builtins();
+
+        assert!(matches!(
+            registry.build("shift", "three"),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            registry.build("scytale", "0"),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(registry.build("scytale", "4").is_ok());
+
+        // the multiplier depends on the alphabet
+        assert!(matches!(
+            registry.build("affine", "13,2"),
+            Err(CipherError::InvalidKey(_))
+        ));
+        assert!(registry.build("affine", "3,2").is_ok());
+        let italian = Settings {
+            alphabet: Alphabet::italian(),
+            ..Settings::default()
+        };
+        assert!(registry.build_with("affine", "3,2", &italian).is_err());
+        assert!(registry.build("multiplicative", "3,2").is_err());
+        assert!(registry.build("atbash", "").is_ok());
+
+        assert!(registry.build("playfair", "monarchy:noq:z").is_ok());
+        assert!(registry.build("playfair", "monarchy:j").is_err());
+        assert!(registry
+            .build_with("playfair", "monarchy", &italian)
+            .is_err());
+        let preserve_format = Settings {
+            mode: TextMode::preserve_format(),
+            ..Settings::default()
+        };
+        assert!(registry
+            .build_with("playfair", "monarchy", &preserve_format)
+            .is_err());
+
+        assert!(registry.build("hill", "hill").is_ok());
+        assert!(registry.build("hill", "0,1,2,3").is_err());
+
+        assert!(registry.build("bifid", "delastelle:5").is_ok());
+        assert!(registry.build("bifid", "delastelle:0").is_err());
+        assert!(registry.build("trifid", "delastelle+:5").is_ok());
+        assert!(registry.build("adfgx", "btalp,cargo").is_ok());
+        assert!(registry.build("adfgvx", "na1c3h").is_err());
+        let squares = [
+            ("bifid", "delastelle"),
+            ("trifid", "delastelle+"),
+            ("adfgx", "btalp,cargo"),
+            ("adfgvx", "na1c3h,privacy"),
+        ];
+        for (name, key) in squares {
+            assert!(registry.build(name, key).is_ok());
+            assert!(registry.build_with(name, key, &italian).is_err());
+            assert!(registry.build_with(name, key, &preserve_format).is_err());
+        }
+
+        assert!(registry.build("beaufort", "fortification").is_ok());
+        assert!(registry.build("gronsfeld", "31415").is_ok());
+        assert!(registry.build("gronsfeld", "lemon").is_err());
+        assert!(registry.build("della-porta", "lemon").is_ok());
+        assert!(registry.build_with("porta", "lemon", &italian).is_err());
+        assert!(registry.build("plaintext-autokey", "queenly").is_ok());
+        for (name, cipher) in [
+            ("beaufort", "Beaufort"),
+            ("variant-beaufort", "variant Beaufort"),
+            ("porta", "Porta"),
+            ("autokey", "plaintext autokey"),
+            ("ciphertext-autokey", "ciphertext autokey"),
+        ] {
+            match registry.build(name, "") {
+                Err(CipherError::InvalidKey(reason)) => {
+                    assert!(
+                        reason.contains(&format!("a {} cipher", cipher)),
+                        "{}",
+                        reason
+                    )
+                }
+                _ => panic!("an empty keyword must be rejected by {}", name),
+            }
+        }
+        assert!(matches!(
+            registry.build("running-key", "no/such/file.txt"),
+            Err(CipherError::Io(_))
+        ));
+
+        assert!(registry.build("keyword", "kangaroo:last").is_ok());
+        assert!(registry
+            .build("keyword-substitution", "kangaroo:up")
+            .is_err());
+        assert!(registry.build("quagmire3", "automobile,highway").is_ok());
+        assert!(registry
+            .build("quagmire-iv", "senory,perctivl,extra")
+            .is_ok());
+        assert!(registry
+            .build("quagmire1", "senory,perctivl,extra")
+            .is_err());
+    }
+
+    #[test]
+    fn test_global_registration() {
+        register(rot13_info("global-rot13")).unwrap();
+
+        assert!(ciphers().iter().any(|info| info.name == "global-rot13"));
+        assert_eq!(lookup("global-rot13").unwrap().name, "global-rot13");
+        assert_eq!(
+            "NGGNPX",
+            build_cipher("global-rot13", "")
+                .unwrap()
+                .encrypt("attack")
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! This module holds the options controlling how the ciphers treat the text they work on.
+
+/// How the characters that are not symbols of the alphabet are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextMode {
+    /// Everything but the symbols of the alphabet (and the digits) is stripped, and the output is
+    /// uppercase. Digits are left untouched, but they still use up a keystream value.
+    #[default]
+    Clean,
+    /// Only the symbols of the alphabet are transformed, while everything else (spaces,
+    /// punctuation, newlines, ...) is left in place and the case of each letter is kept.
+    PreserveFormat(KeyAdvance),
+}
+
+impl TextMode {
+    /// Preserves the format of the text, advancing the key only over symbols of the alphabet.
+    pub fn preserve_format() -> Self {
+        TextMode::PreserveFormat(KeyAdvance::SymbolsOnly)
+    }
+}
+
+/// Whether the key of a polyalphabetic cipher (e.g. Vigenere or Solitaire) advances over the
+/// characters that are left untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyAdvance {
+    /// The key only advances when a symbol is transformed.
+    #[default]
+    SymbolsOnly,
+    /// The key advances over every character, transformed or not.
+    EveryCharacter,
+}
+
+/// What happens to the digits found in the text (unless they are symbols of the alphabet
+/// already, in which case they are treated like any other symbol).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigitPolicy {
+    /// Digits are left untouched.
+    #[default]
+    PassThrough,
+    /// Digits are removed from the text.
+    Strip,
+    /// Digits are spelled out as (English) words, e.g. "42" becomes "FOURTWO", and encrypted
+    /// along with the rest of the text.
+    SpellOut,
+    /// Digits are encrypted within a ring of their own, using the same key as the symbols (e.g. a
+    /// shift of 13 moves 1 onto 4).
+    SeparateRing,
+    /// Digits are appended to the alphabet, so that they get encrypted along with (and onto) the
+    /// symbols.
+    Combined,
+}
+
+/// The case the ciphertext is printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LetterCase {
+    /// Left as the cipher produced it.
+    #[default]
+    AsIs,
+    Upper,
+    Lower,
+}
+
+/// How the ciphertext is laid out, e.g. in the traditional five-letter groups.
+///
+/// The default format leaves the ciphertext as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutputFormat {
+    /// The number of characters in each group (no grouping if 0).
+    pub group_size: usize,
+    /// The number of groups on each line (no line breaks if 0).
+    pub groups_per_line: usize,
+    /// The null used to complete the last group, if any.
+    pub pad: Option<char>,
+    pub case: LetterCase,
+}
+
+impl OutputFormat {
+    /// Groups of size characters, all on one line and without padding.
+    pub fn grouped(size: usize) -> Self {
+        OutputFormat {
+            group_size: size,
+            ..OutputFormat::default()
+        }
+    }
+
+    /// Puts groups_per_line groups on each line.
+    pub fn with_lines(mut self, groups_per_line: usize) -> Self {
+        self.groups_per_line = groups_per_line;
+        self
+    }
+
+    /// Completes the last group with the given null.
+    pub fn with_pad(mut self, pad: char) -> Self {
+        self.pad = Some(pad);
+        self
+    }
+
+    pub fn with_case(mut self, case: LetterCase) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Lays out the text according to the format.
+    ///
+    /// When grouping, any whitespace already in the text is dropped first.
+    pub fn apply(&self, text: &str) -> String {
+        let grouped = self.group(text);
+
+        match self.case {
+            LetterCase::AsIs => grouped,
+            LetterCase::Upper => grouped.to_uppercase(),
+            LetterCase::Lower => grouped.to_lowercase(),
+        }
+    }
+
+    /// Splits the text into groups (and lines), padding the last group if needed.
+    fn group(&self, text: &str) -> String {
+        if self.group_size == 0 {
+            return text.to_string();
+        }
+
+        let mut chars = text
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .collect::<Vec<char>>();
+        if let Some(pad) = self.pad {
+            while chars.len() % self.group_size != 0 {
+                chars.push(pad);
+            }
+        }
+
+        let mut grouped = String::with_capacity(chars.len() * 2);
+        for (i, group) in chars.chunks(self.group_size).enumerate() {
+            if i > 0 {
+                let line_done = self.groups_per_line > 0 && i % self.groups_per_line == 0;
+                grouped.push(if line_done { '\n' } else { ' ' });
+            }
+            grouped.extend(group);
+        }
+
+        grouped
+    }
+
+    /// Undoes the grouping, so that the text can be decrypted (the nulls, being part of the
+    /// ciphertext, are left in place).
+    pub fn strip(&self, text: &str) -> String {
+        if self.group_size == 0 {
+            return text.to_string();
+        }
+
+        text.chars().filter(|ch| !ch.is_whitespace()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_format() {
+        let text = "Yhql, ylgl, ylfl.";
+
+        assert_eq!(text, OutputFormat::default().apply(text));
+        assert_eq!(text, OutputFormat::default().strip(text));
+    }
+
+    #[test]
+    fn test_groups() {
+        let text = "LXFOPVEFRNHR";
+
+        assert_eq!("LXFOP VEFRN HR", OutputFormat::grouped(5).apply(text));
+        assert_eq!(
+            "LXFOP VEFRN HRXXX",
+            OutputFormat::grouped(5).with_pad('X').apply(text)
+        );
+        assert_eq!(
+            "lxf opv\nefr nhr",
+            OutputFormat::grouped(3)
+                .with_lines(2)
+                .with_case(LetterCase::Lower)
+                .apply(text)
+        );
+    }
+
+    #[test]
+    fn test_strip() {
+        let format = OutputFormat::grouped(5).with_lines(2).with_pad('X');
+
+        assert_eq!(
+            "LXFOPVEFRNHRXXX",
+            format.strip(&format.apply("LXFOPVEFRNHR"))
+        );
+    }
+}
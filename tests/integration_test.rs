@@ -1,7 +1,9 @@
 mod common;
 use common::*;
 
-use classical_cryptography::encrypt_data;
+use classical_cryptography::{
+    decrypt_data, encrypt_data, registry, Cipher, CipherError, ShiftCipher, VigenereCipher,
+};
 
 use std::str::from_utf8;
 
@@ -22,7 +24,7 @@ fn test_reading_from_stdin() {
     str_out = str_out.replace("\n", "");
     let enc_out = str_out.split(":").collect::<Vec<&str>>()[1].to_string();
 
-    let encrypted = encrypt_data(cipher, plaintext, key);
+    let encrypted = encrypt_data(cipher, plaintext, key).unwrap();
     assert_eq!(enc_out, encrypted);
 }
 
@@ -45,8 +47,573 @@ fn test_reading_from_file() {
     str_out = str_out.replace("\n", "");
     let enc_out = str_out.split(":").collect::<Vec<&str>>()[1].to_string();
 
-    let encrypted = encrypt_data(cipher, &data, key);
+    let encrypted = encrypt_data(cipher, &data, key).unwrap();
     assert_eq!(enc_out, encrypted);
 
     teardown();
 }
+
+#[test]
+fn test_error_exit_codes() {
+    let out = run_with_args(&vec!["shift", "three", "attackatdawn", "-e"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+
+    let out = run_with_args(&vec!["enigma", "key", "attackatdawn", "-e"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(3));
+
+    let out = run_with_args(&vec![
+        "shift",
+        "3",
+        "-d",
+        "-f",
+        "tests/nonexisting_file.txt",
+    ])
+    .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(5));
+
+    let str_err = from_utf8(&out.stderr).expect("Error reading stderr.");
+    assert!(str_err.starts_with("Error:"));
+
+    // the key is checked before the input is read
+    let out = run_with_args(&vec![
+        "shift",
+        "three",
+        "-d",
+        "-f",
+        "tests/nonexisting_file.txt",
+    ])
+    .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_downstream_cipher() {
+    // a cipher living outside of the crate, used alongside the built-in ones
+    struct Reverse;
+
+    impl Cipher for Reverse {
+        fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+            Ok(self.clean_input(plaintext).chars().rev().collect())
+        }
+
+        fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+            Ok(self.clean_input(ciphertext).chars().rev().collect())
+        }
+    }
+
+    let ciphers: Vec<Box<dyn Cipher>> = vec![
+        Box::new(Reverse),
+        Box::new(ShiftCipher::new(3).unwrap()),
+        Box::new(VigenereCipher::new("lemon").unwrap()),
+    ];
+    let plaintext = "attack at dawn";
+
+    let mut ciphertext = plaintext.to_string();
+    for cipher in ciphers.iter() {
+        ciphertext = cipher.encrypt(&ciphertext).unwrap();
+    }
+    assert_eq!(
+        ciphertext,
+        encrypt_data(
+            "vigenere",
+            &encrypt_data("shift", "nwadtakcatta", "3").unwrap(),
+            "lemon"
+        )
+        .unwrap()
+    );
+
+    let mut decrypted = ciphertext;
+    for cipher in ciphers.iter().rev() {
+        decrypted = cipher.decrypt(&decrypted).unwrap();
+    }
+    assert_eq!(decrypted, "ATTACKATDAWN");
+}
+
+#[test]
+fn test_preserve_format() {
+    let out = run_with_args(&vec!["shift", "3", "Attack at dawn!", "-e", "-p"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("Dwwdfn dw gdzq!"));
+
+    let out = run_with_args(&vec![
+        "vigenere",
+        "dante",
+        "Kilo e jolly",
+        "-e",
+        "--alphabet",
+        "italian",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    // the alphabet is checked before any data is touched
+    let out = run_with_args(&vec!["vigenere", "dante", "ciao", "-e", "--alphabet", "aa"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_digit_policy() {
+    let out = run_with_args(&vec![
+        "shift",
+        "3",
+        "Super secret code: 4815",
+        "-e",
+        "--digits",
+        "separate-ring",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("VXSHUVHFUHWFRGH7148"));
+
+    let out = run_with_args(&vec!["shift", "3", "1234", "-e", "--digits", "scramble"])
+        .expect("Error running the binary.");
+    assert!(!out.status.success());
+}
+
+#[test]
+fn test_output_format() {
+    let out = run_with_args(&vec![
+        "shift",
+        "3",
+        "Attack at dawn",
+        "-e",
+        "--group",
+        "5",
+        "--pad",
+        "X",
+        "--case",
+        "lower",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("dwwdf ndwgd zqxxx"));
+
+    // the grouping doesn't get in the way of decryption
+    let out = run_with_args(&vec!["shift", "3", "dwwdf ndwgd zq", "-d", "--group", "5"])
+        .expect("Error running the binary.");
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("ATTACKATDAWN"));
+
+    let out = run_with_args(&vec!["shift", "3", "attack", "-e", "--group", "five"])
+        .expect("Error running the binary.");
+    assert!(!out.status.success());
+}
+
+#[test]
+fn test_list_ciphers() {
+    let out = run_with_args(&vec!["list-ciphers"]).expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    for info in registry::ciphers() {
+        assert!(str_out.contains(info.name));
+    }
+}
+
+#[test]
+fn test_data_like_subcommands() {
+    // free text that happens to look like a subcommand is still data
+    for (args, expected) in [
+        (vec!["-e", "shift", "3", "hello"], "KHOOR"),
+        (vec!["shift", "3", "help", "-e"], "KHOS"),
+        (vec!["-e", "hill", "3,3,2,5", "hel"], "HIYH"),
+        (vec!["-e", "shift", "3", "analyse"], "DQDOBVH"),
+        (vec!["-d", "shift", "3", "FUDFNV"], "CRACKS"),
+        (vec!["shift", "3", "identity", "-e"], "LGHQWLWB"),
+    ]
+    .iter()
+    {
+        let out = run_with_args(args).expect("Error running the binary.");
+        assert!(out.status.success(), "{:?}", args);
+        assert!(from_utf8(&out.stdout).unwrap().contains(expected));
+    }
+}
+
+#[test]
+fn test_solitaire() {
+    // the cipher name comes first or after the flags, like any other cipher
+    for args in [
+        vec!["solitaire", "mysecret", "attackatdawn", "-e"],
+        vec!["-e", "solitaire", "mysecret", "attackatdawn"],
+    ] {
+        let out = run_with_args(&args).expect("Error running the binary.");
+        assert!(out.status.success());
+
+        let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+        assert!(str_out.contains("KGEFKYHUGKMV"));
+    }
+
+    let out = run_with_args(&vec!["solitaire", "pass:", "AAAAA", "-e"])
+        .expect("Error running the binary.");
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("EXKYI"));
+}
+
+#[test]
+fn test_solitaire_deck() {
+    let out = run_with_args(&vec!["solitaire", "pass:", "--dump-deck", "1"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("Deck after keying:\n\nAC 2C 3C"));
+    // the first keystream value leaves the unkeyed deck cut by one card
+    assert!(str_out.contains("QS KS JA JB AC\n"));
+
+    let out = run_with_args(&vec!["solitaire", "pass:", "--dump-deck"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(!str_out.contains("keystream values"));
+
+    let out = run_with_args(&vec!["solitaire", "deck:AC 2C", "--dump-deck"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+
+    // only Solitaire has a deck
+    let out = run_with_args(&vec!["shift", "3", "--dump-deck"]).expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn test_analyze() {
+    let out = run_with_args(&vec!["analyze", "Attack at dawn", "--top", "3"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("Letter frequencies (12 in total)"));
+    assert!(str_out
+        .lines()
+        .any(|line| line.starts_with("A        4  33.33%")));
+    assert!(str_out.contains("Chi-squared against English"));
+
+    let out = run_with_args(&vec!["analyze", "Attack at dawn", "-n", "4"])
+        .expect("Error running the binary.");
+    assert!(!out.status.success());
+}
+
+#[test]
+fn test_crack_shift() {
+    let ciphertext = encrypt_data("shift", "Defend the east wall of the castle", "7").unwrap();
+
+    let out = run_with_args(&vec!["crack", "shift", &ciphertext, "--top", "3"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("Best guess (key 7):\n\nDEFENDTHEEASTWALLOFTHECASTLE"));
+}
+
+#[test]
+fn test_crack_vigenere() {
+    let plaintext = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+        incredulity, it was the season of Light, it was the season of Darkness";
+    let ciphertext = encrypt_data("vigenere", plaintext, "lemon").unwrap();
+
+    let out =
+        run_with_args(&vec!["crack", "vigenere", &ciphertext]).expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("Likely periods"));
+    assert!(str_out.contains("Best guess (key LEMON)"));
+
+    let out = run_with_args(&vec!["crack", "vigenere", &ciphertext, "--period", "0"])
+        .expect("Error running the binary.");
+    assert!(!out.status.success());
+}
+
+#[test]
+fn test_crack_substitution() {
+    let plaintext = "It is a truth universally acknowledged, that a single man in possession of \
+        a good fortune, must be in want of a wife. However little known the feelings or views of \
+        such a man may be on his first entering a neighbourhood";
+    let ciphertext =
+        encrypt_data("monoalphabetic", plaintext, "qwertyuiopasdfghjklzxcvbnm").unwrap();
+
+    let out = run_with_args(&vec!["crack", "substitution", &ciphertext, "--crib", "t=Z"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("ITISATRUTHUNIVERSALLYACKNOWLEDGED"));
+
+    let out = run_with_args(&vec!["crack", "substitution", &ciphertext, "--crib", "t=1"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_crack_transposition() {
+    let plaintext = "We are discovered, flee at once and take the road to the north before the \
+        soldiers reach the river crossing at dawn";
+
+    let ciphertext = encrypt_data("scytale", plaintext, "6").unwrap();
+    let out =
+        run_with_args(&vec!["crack", "scytale", &ciphertext]).expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("Best guess (key 6)"));
+    assert!(str_out.contains("WEAREDISCOVEREDFLEEATONCE"));
+
+    let ciphertext = encrypt_data("columnar", plaintext, "zebras").unwrap();
+    let out = run_with_args(&vec![
+        "crack",
+        "columnar",
+        &ciphertext,
+        "--max-columns",
+        "6",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+    assert!(str_out.contains("WEAREDISCOVEREDFLEEATONCE"));
+
+    let out = run_with_args(&vec![
+        "crack",
+        "columnar",
+        &ciphertext,
+        "--max-columns",
+        "1",
+    ])
+    .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn test_identify() {
+    let plaintext = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+        incredulity, it was the season of Light, it was the season of Darkness";
+
+    for (cipher, key, family) in [
+        ("shift", "7", "shift"),
+        ("scytale", "5", "transposition"),
+        ("vigenere", "lemon", "vigenere"),
+    ] {
+        let ciphertext = encrypt_data(cipher, plaintext, key).unwrap();
+        let out = run_with_args(&vec!["identify", &ciphertext]).expect("Error running the binary.");
+        assert!(out.status.success());
+
+        let str_out = from_utf8(&out.stdout).expect("Error reading stdout.");
+        let likely = str_out.split("Likely ciphers:").nth(1).unwrap();
+        let first = likely.lines().find(|line| !line.is_empty()).unwrap();
+        assert!(first.contains(family), "{}: {}", cipher, str_out);
+    }
+    // nothing to go on
+    for data in ["", "1234"] {
+        let out = run_with_args(&vec!["identify", data]).expect("Error running the binary.");
+        assert_eq!(out.status.code(), Some(8));
+    }
+}
+
+#[test]
+fn test_train_model() {
+    let dir = std::env::temp_dir();
+    let corpus = dir.join("classical_cryptography_train_corpus.txt");
+    let output = dir.join("classical_cryptography_train_model.txt");
+    std::fs::write(&corpus, "Nel mezzo del cammin di nostra vita").unwrap();
+
+    let out = run_with_args(&vec![
+        "train-model",
+        "italian",
+        "-a",
+        "italian",
+        "-f",
+        corpus.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+
+    let model = std::fs::read_to_string(&output).unwrap();
+    assert!(model.starts_with("name italian\nalphabet italian\n"));
+    assert!(model.contains("\nMEZZ "));
+
+    std::fs::remove_file(&corpus).unwrap();
+    std::fs::remove_file(&output).unwrap();
+
+    let out = run_with_args(&vec!["train-model", "x", "-f", corpus.to_str().unwrap()])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(5));
+}
+
+#[test]
+fn test_affine() {
+    let out = run_with_args(&vec!["affine", "5,8", "Affine cipher", "-e"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+    assert!(from_utf8(&out.stdout).unwrap().contains("IHHWVCSWFRCP"));
+
+    assert_eq!("ZBXYZ", encrypt_data("atbash", "aycba", "").unwrap());
+    assert_eq!(
+        "AFFINE",
+        decrypt_data("multiplicative", "AFFINE", "1").unwrap()
+    );
+
+    // 13 isn't coprime with 26
+    let out = run_with_args(&vec!["affine", "13,8", "Affine cipher", "-e"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_playfair() {
+    let out = run_with_args(&vec![
+        "playfair",
+        "playfair example",
+        "Hide the gold in the tree stump",
+        "-e",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+    assert!(from_utf8(&out.stdout)
+        .unwrap()
+        .contains("BMODZBXDNABEKUDMUIXMMOUVIF"));
+
+    assert_eq!(
+        "BALQLOON",
+        decrypt_data(
+            "playfair",
+            &encrypt_data("playfair", "balloon", "monarchy:q").unwrap(),
+            "monarchy:q"
+        )
+        .unwrap()
+    );
+
+    // an odd number of letters can't be decrypted
+    let out = run_with_args(&vec!["playfair", "monarchy", "abc", "-d"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(8));
+}
+
+#[test]
+fn test_hill() {
+    let out = run_with_args(&vec!["hill", "hill", "Short example", "-e"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+    assert!(from_utf8(&out.stdout).unwrap().contains("APADJTFTWLFJ"));
+
+    assert_eq!(
+        "POH",
+        encrypt_data("hill", "act", "6,24,1,13,16,10,20,17,15").unwrap()
+    );
+
+    // the determinant of the matrix is 13
+    let out = run_with_args(&vec!["hill", "1,0,0,13", "Short example", "-e"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_fractionating() {
+    let out = run_with_args(&vec![
+        "bifid",
+        "bgwkzqpndsioaxefclumthyvr",
+        "Flee at once",
+        "-e",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+    assert!(from_utf8(&out.stdout).unwrap().contains("UAEOLWRINS"));
+
+    assert_eq!(
+        "AIDETOILECIELTAIDERA",
+        decrypt_data("trifid", "FMJFVOISSUFTFPUFEQQC", "felix marie delastelle:5").unwrap()
+    );
+    assert_eq!(
+        "DGDDDAGDDGAFADDFDADVDVFAADVX",
+        encrypt_data(
+            "adfgvx",
+            "Attack at 1200AM",
+            "na1c3h8tb2ome5wrpd4f6g7i9j0klqsuvxyz,privacy"
+        )
+        .unwrap()
+    );
+
+    // half a pair of labels
+    let out = run_with_args(&vec![
+        "adfgx",
+        "btalpdhozkqfvsngicuxmrewy,cargo",
+        "FAXDF",
+        "-d",
+    ])
+    .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(8));
+}
+
+#[test]
+fn test_vigenere_family() {
+    let out = run_with_args(&vec![
+        "beaufort",
+        "fortification",
+        "Defend the east wall of the castle",
+        "-e",
+    ])
+    .expect("Error running the binary.");
+    assert!(out.status.success());
+    assert!(from_utf8(&out.stdout)
+        .unwrap()
+        .contains("CKMPVCPVWPIWUJOGIUAPVWRIWUUK"));
+
+    assert_eq!(
+        "QNXEPVYTWTWP",
+        encrypt_data("autokey", "attack at dawn", "queenly").unwrap()
+    );
+    assert_eq!(
+        "HELLOWORLD",
+        decrypt_data("gronsfeld", "KFPMTZPVMI", "31415").unwrap()
+    );
+
+    let key = std::env::temp_dir().join("classical_cryptography_running_key.txt");
+    std::fs::write(&key, "How vexingly quick daft zebras jump!").unwrap();
+    let key = key.to_str().unwrap();
+    assert_eq!(
+        encrypt_data("vigenere", "attack at dawn", "howvexinglyq").unwrap(),
+        encrypt_data("running-key", "attack at dawn", key).unwrap()
+    );
+
+    // the running key is too short
+    let out = run_with_args(&vec!["running-key", key, &"attack at dawn".repeat(3), "-e"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(8));
+}
+
+#[test]
+fn test_keyword_alphabets() {
+    let out = run_with_args(&vec!["keyword", "kangaroo", "Hello", "-e"])
+        .expect("Error running the binary.");
+    assert!(out.status.success());
+    assert!(from_utf8(&out.stdout).unwrap().contains("CRHHL"));
+
+    assert_eq!(
+        "HELLO",
+        decrypt_data("keyword-substitution", "CRHHL", "kangaroo").unwrap()
+    );
+
+    let ciphertext = encrypt_data("quagmire4", "Attack at dawn", "senory,perctivl,extra").unwrap();
+    assert_eq!(
+        "ATTACKATDAWN",
+        decrypt_data("quagmire-iv", &ciphertext, "senory,perctivl,extra").unwrap()
+    );
+
+    // Quagmire III has a single keyword
+    let out = run_with_args(&vec!["quagmire3", "senory,perctivl,extra", "Hello", "-e"])
+        .expect("Error running the binary.");
+    assert_eq!(out.status.code(), Some(2));
+}